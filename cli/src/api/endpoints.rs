@@ -1,5 +1,6 @@
 /// API endpoint paths
 use super::{JobId, PackageDescriptor};
+use crate::purl::encode_path_segment;
 
 const API_PATH: &str = "api/v0";
 
@@ -14,8 +15,15 @@ pub fn get_ping(api_uri: &str) -> String {
 }
 
 /// GET /job/
-pub fn get_all_jobs_status(api_uri: &str, limit: u32) -> String {
-    format!("{api_uri}/{API_PATH}/job/?limit={limit}&verbose=1")
+///
+/// Jobs are returned most recent first, starting after the `cursor` job.
+pub fn get_all_jobs_status(api_uri: &str, limit: u32, cursor: Option<&JobId>) -> String {
+    match cursor {
+        Some(cursor) => {
+            format!("{api_uri}/{API_PATH}/job/?limit={limit}&verbose=1&cursor={cursor}")
+        }
+        None => format!("{api_uri}/{API_PATH}/job/?limit={limit}&verbose=1"),
+    }
 }
 
 /// GET /job/<job_id>
@@ -27,15 +35,17 @@ pub fn get_job_status(api_uri: &str, job_id: &JobId, verbose: bool) -> String {
     }
 }
 
+/// DELETE /job/<job_id>
+pub fn delete_job(api_uri: &str, job_id: &JobId) -> String {
+    format!("{api_uri}/{API_PATH}/job/{job_id}")
+}
+
 /// GET /job/packages/<type>/<name>/<version>
 pub fn get_package_status(api_uri: &str, pkg: &PackageDescriptor) -> String {
-    let name_escaped = pkg.name.replace('/', "~");
-    let PackageDescriptor {
-        package_type,
-        version,
-        ..
-    } = pkg;
-    format!("{api_uri}/{API_PATH}/data/packages/{package_type}/{name_escaped}/{version}")
+    let name = encode_path_segment(&pkg.name);
+    let version = encode_path_segment(&pkg.version);
+    let package_type = &pkg.package_type;
+    format!("{api_uri}/{API_PATH}/data/packages/{package_type}/{name}/{version}")
 }
 
 /// GET /job/projects/name/<pkg_id>
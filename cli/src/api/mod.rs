@@ -73,6 +73,10 @@ impl PhylumApi {
         self.send_request(Method::POST, path, Some(s)).await
     }
 
+    async fn delete<T: DeserializeOwned>(&self, path: String) -> Result<T> {
+        self.send_request::<_, ()>(Method::DELETE, path, None).await
+    }
+
     async fn send_request<T: DeserializeOwned, B: Serialize>(
         &self,
         method: Method,
@@ -199,10 +203,7 @@ impl PhylumApi {
     }
 
     /// Get a list of projects
-    pub async fn get_projects(
-        &mut self,
-        group: Option<&str>,
-    ) -> Result<Vec<ProjectSummaryResponse>> {
+    pub async fn get_projects(&self, group: Option<&str>) -> Result<Vec<ProjectSummaryResponse>> {
         let uri = match group {
             Some(group) => endpoints::group_project_summary(&self.api_uri, group),
             None => endpoints::get_project_summary(&self.api_uri),
@@ -225,7 +226,7 @@ impl PhylumApi {
 
     /// Submit a new request to the system
     pub async fn submit_request(
-        &mut self,
+        &self,
         req_type: &PackageType,
         package_list: &[PackageDescriptor],
         is_user: bool,
@@ -259,7 +260,7 @@ impl PhylumApi {
 
     /// Get the status of a previously submitted job (verbose output)
     pub async fn get_job_status_ext(
-        &mut self,
+        &self,
         job_id: &JobId,
     ) -> Result<JobStatusResponse<PackageStatusExtended>> {
         self.get(endpoints::get_job_status(&self.api_uri, job_id, true))
@@ -268,22 +269,34 @@ impl PhylumApi {
 
     /// Get the status of all jobs
     pub async fn get_status(&mut self) -> Result<AllJobsStatusResponse> {
-        self.get(endpoints::get_all_jobs_status(&self.api_uri, 30))
+        self.get_jobs(30, None).await
+    }
+
+    /// Get a page of jobs, most recent first, starting after the `cursor` job
+    pub async fn get_jobs(
+        &self,
+        limit: u32,
+        cursor: Option<&JobId>,
+    ) -> Result<AllJobsStatusResponse> {
+        self.get(endpoints::get_all_jobs_status(&self.api_uri, limit, cursor))
+            .await
+    }
+
+    /// Cancel a job which is still being processed
+    pub async fn cancel_job(&mut self, job_id: &JobId) -> Result<CancelJobResponse> {
+        self.delete(endpoints::delete_job(&self.api_uri, job_id))
             .await
     }
 
     /// Get the details of a specific project
-    pub async fn get_project_details(
-        &mut self,
-        project_name: &str,
-    ) -> Result<ProjectDetailsResponse> {
+    pub async fn get_project_details(&self, project_name: &str) -> Result<ProjectDetailsResponse> {
         self.get(endpoints::get_project_details(&self.api_uri, project_name))
             .await
     }
 
     /// Resolve a Project Name to a Project ID
     pub async fn get_project_id(
-        &mut self,
+        &self,
         project_name: &str,
         group_name: Option<&str>,
     ) -> Result<ProjectId> {
@@ -303,13 +316,13 @@ impl PhylumApi {
     }
 
     /// Get package details
-    pub async fn get_package_details(&mut self, pkg: &PackageDescriptor) -> Result<Package> {
+    pub async fn get_package_details(&self, pkg: &PackageDescriptor) -> Result<Package> {
         self.get(endpoints::get_package_status(&self.api_uri, pkg))
             .await
     }
 
     /// Get all groups the user is part of.
-    pub async fn get_groups_list(&mut self) -> Result<ListUserGroupsResponse> {
+    pub async fn get_groups_list(&self) -> Result<ListUserGroupsResponse> {
         self.get(endpoints::group_list(&self.api_uri)).await
     }
 
@@ -494,6 +507,51 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn get_jobs_with_cursor() -> Result<()> {
+        let body = r#"{"count": 0, "jobs": [], "total_jobs": 0}"#;
+
+        let mock_server = build_mock_server().await;
+        Mock::given(method("GET"))
+            .and(path("api/v0/job/"))
+            .and(query_param("limit", "50"))
+            .and(query_param(
+                "cursor",
+                "59482a54-423b-448d-8325-f171c9dc336b",
+            ))
+            .respond_with_fn(move |_| ResponseTemplate::new(200).set_body_string(body))
+            .mount(&mock_server)
+            .await;
+
+        let client = build_phylum_api(&mock_server).await?;
+
+        let cursor = JobId::from_str("59482a54-423b-448d-8325-f171c9dc336b").unwrap();
+        let jobs = client.get_jobs(50, Some(&cursor)).await?;
+        assert!(jobs.jobs.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cancel_job() -> Result<()> {
+        let body = r#"{"msg": "Job deleted"}"#;
+
+        let mock_server = build_mock_server().await;
+        Mock::given(method("DELETE"))
+            .and(path("api/v0/job/59482a54-423b-448d-8325-f171c9dc336b"))
+            .respond_with_fn(move |_| ResponseTemplate::new(200).set_body_string(body))
+            .mount(&mock_server)
+            .await;
+
+        let mut client = build_phylum_api(&mock_server).await?;
+
+        let job = JobId::from_str("59482a54-423b-448d-8325-f171c9dc336b").unwrap();
+        let response = client.cancel_job(&job).await?;
+        assert_eq!(response.msg, "Job deleted");
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn get_package_details() -> Result<()> {
         let body = r#"
@@ -12,6 +12,16 @@ const VERSION: &str = git_version!(
 
 const FILTER_ABOUT: &str = r#"Provide a filter used to limit the issues displayed
 
+The filter is either a comma separated list of a minimum severity, risk domains and
+package URLs, or an expression combining conditions with `and`, `or`, `not` and
+parentheses. Conditions are:
+    severity >= high            compare the severity (`<`, `<=`, `=`, `!=`, `>=`, `>`)
+    domain in (mal, vuln)       match risk domains (also `domain = mal`, `domain != mal`)
+    name = "@angular/*"         match package names, with `*` and `?` wildcards
+    ecosystem = npm             match the ecosystem (npm, pypi, rubygems, maven, nuget)
+    score < 0.4                 compare the package score
+    pkg:npm/lodash              match a package URL
+
 EXAMPLES
 # Show only issues with severity of at least 'high'
     --filter=high
@@ -19,6 +29,69 @@ EXAMPLES
 # Show issues with severity of 'critical' in the 'author'
 and 'engineering' domains
     --filter=crit,aut,eng
+
+# Show only issues of the 'lodash' package
+    --filter=pkg:npm/lodash
+
+# Show malicious code and vulnerability issues with severity of at least 'high'
+    --filter='severity>=high and domain in (mal,vuln)'
+
+# Show issues of low scoring packages, except for type definitions
+    --filter='score<0.4 and not name="@types/*"'
+"#;
+
+/// Formats accepted by `--format`.
+const FORMATS: &[&str] = &[
+    "table",
+    "json",
+    "ndjson",
+    "yaml",
+    "csv",
+    "sarif",
+    "cyclonedx",
+    "cyclonedx-xml",
+    "spdx",
+    "junit",
+    "markdown",
+    "html",
+];
+
+const FORMAT_ABOUT: &str = r#"Set the output format (default: table)
+
+The `table`, `json`, `ndjson`, `yaml` and `csv` formats are supported by every command
+which prints a response. Reports in the remaining formats are produced by:
+    analyze, history <JOB_ID>   sarif, cyclonedx, cyclonedx-xml, spdx, junit, markdown, html
+    package                     markdown, html
+    parse                       cyclonedx, cyclonedx-xml, spdx (default: json)
+"#;
+
+const COLUMNS_ABOUT: &str = r#"Comma-separated list of the table columns shown with `--from-file`
+
+Columns are shown in the order given. Available columns:
+    name, version, ecosystem, license, issues
+    score                       the total package score
+    author, engineering, license-risk, malicious, vulnerability
+                                the risk domain scores
+
+(default: name,version,ecosystem,score,issues,license)
+
+EXAMPLES
+# Show the vulnerability risk of every package
+    --columns=name,version,vulnerability
+"#;
+
+const BATCH_ABOUT: &str = r#"Submits a batch of requests to the processing system
+
+Packages are read one per line, in any of the following formats:
+    {"name": "lodash", "version": "4.17.21", "type": "npm"}
+    pkg:npm/lodash@4.17.21
+    lodash,4.17.21,npm
+    lodash@4.17.21
+    lodash:4.17.21
+
+The JSON output of `phylum parse` is accepted as well. CSV input may start with a
+`name,version,type` header to specify the column order. Packages from multiple
+ecosystems are submitted as one job per ecosystem.
 "#;
 
 pub fn app<'a>() -> clap::Command<'a> {
@@ -33,6 +106,9 @@ pub fn app<'a>() -> clap::Command<'a> {
             Arg::new("config").short('c').long("config").value_name("FILE").help("Sets a custom config file").value_hint(ValueHint::FilePath),
             Arg::new("timeout").short('t').long("timeout").value_name("TIMEOUT").help("Set the timeout (in seconds) for requests to the Phylum api"),
             Arg::new("no-check-certificate").long("no-check-certificate").help("Don't validate the server certificate when performing api requests"),
+            Arg::new("format").long("format").value_name("format").possible_values(FORMATS).global(true).help("Set the output format (default: table)").long_help(FORMAT_ABOUT),
+            Arg::new("json").short('j').long("json").global(true).hide(true).conflicts_with("format").help("Produce output in json format (deprecated, use `--format json`)"),
+            Arg::new("no-color").long("no-color").global(true).help("Disable colors and emoji in the output (also disabled by `NO_COLOR` or when not writing to a terminal)"),
         ])
         .subcommand(
             Command::new("update")
@@ -43,11 +119,15 @@ pub fn app<'a>() -> clap::Command<'a> {
             Command::new("history")
                 .about("Return information about historical jobs")
                 .args(&[
-                    Arg::new("JOB_ID").value_name("JOB_ID").help("The job id to query (or `current` for the most recent job)").required(true),
+                    Arg::new("JOB_ID").value_name("JOB_ID").help("The job id to query (or `current` for the most recent job)"),
                     Arg::new("verbose").short('v').long("verbose").help("Increase verbosity of api response."),
                     Arg::new("filter").long("filter").value_name("filter").help(FILTER_ABOUT),
-                    Arg::new("json").short('j').long("json").help("Produce output in json format (default: false)"),
                     Arg::new("project").short('p').long("project").value_name("project_name").help("Project name used to filter jobs"),
+                    Arg::new("label").short('l').long("label").value_name("label").help("Label used to filter jobs"),
+                    Arg::new("status").long("status").value_name("status").possible_values(&["pass", "fail", "incomplete"]).help("Status used to filter jobs"),
+                    Arg::new("since").long("since").value_name("date").help("Only list jobs submitted on or after this date (`YYYY-MM-DD` or RFC 3339)"),
+                    Arg::new("until").long("until").value_name("date").help("Only list jobs submitted on or before this date (`YYYY-MM-DD` or RFC 3339)"),
+                    Arg::new("limit").short('n').long("limit").value_name("count").help("Maximum number of jobs to list (default: 30)"),
                 ])
                 .subcommand(
                     Command::new("project")
@@ -59,11 +139,34 @@ pub fn app<'a>() -> clap::Command<'a> {
                         .hide(true)
                 )
         )
+        .subcommand(
+            Command::new("job")
+                .about("Manage submitted jobs")
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("cancel")
+                        .about("Cancel a job which is still being processed")
+                        .args(&[
+                            Arg::new("JOB_ID").value_name("JOB_ID").help("The job id to cancel (or `current` for the most recent job)").required(true),
+                        ])
+                )
+                .subcommand(
+                    Command::new("list")
+                        .about("List submitted jobs, most recent first")
+                        .args(&[
+                            Arg::new("project").short('p').long("project").value_name("project_name").help("Only list jobs of this project"),
+                            Arg::new("label").short('l').long("label").value_name("label").help("Only list jobs with this label"),
+                            Arg::new("status").long("status").value_name("status").possible_values(&["pass", "fail", "incomplete"]).help("Only list jobs with this status"),
+                            Arg::new("since").long("since").value_name("date").help("Only list jobs submitted on or after this date (`YYYY-MM-DD` or RFC 3339)"),
+                            Arg::new("until").long("until").value_name("date").help("Only list jobs submitted on or before this date (`YYYY-MM-DD` or RFC 3339)"),
+                            Arg::new("limit").short('n').long("limit").value_name("count").help("Maximum number of jobs to list (default: 30)"),
+                        ])
+                )
+        )
         .subcommand(
             Command::new("project")
                 .about("Create, list, link and set thresholds for projects")
                 .args(&[
-                    Arg::new("json").short('j').long("json").help("Produce output in json format (default: false)"),
                     Arg::new("group").short('g').long("group").value_name("group_name").help("Group to list projects for"),
                 ])
                 .aliases(&["projects"])
@@ -79,7 +182,6 @@ pub fn app<'a>() -> clap::Command<'a> {
                     Command::new("list")
                         .about("List all existing projects")
                         .args(&[
-                            Arg::new("json").short('j').long("json").help("Produce output in json format (default: false)"),
                             Arg::new("group").short('g').long("group").value_name("group_name").help("Group to list projects for"),
                         ])
                 )
@@ -102,12 +204,15 @@ pub fn app<'a>() -> clap::Command<'a> {
         )
         .subcommand(
             Command::new("package")
-                .about("Retrieve the details of a specific package")
+                .about("Retrieve the details of a specific package, or of a list of packages")
                 .args(&[
-                    Arg::new("name").value_name("name").help("The name of the package.").required(true),
-                    Arg::new("version").value_name("version").help("The version of the package.").required(true),
+                    Arg::new("name").value_name("name").help("The name or package URL (`pkg:npm/lodash@4.17.21`) of the package.").required_unless_present("from-file"),
+                    Arg::new("version").value_name("version").help("The version of the package (optional for package URLs)."),
                     Arg::new("package-type").short('t').long("package-type").value_name("type").help("The type of the package (\"npm\", \"ruby\", \"pypi\", etc.)"),
-                    Arg::new("json").short('j').long("json").help("Produce output in json format (default: false)")
+                    Arg::new("from-file").short('f').long("from-file").value_name("FILE").value_hint(ValueHint::FilePath).help("Look up every package listed in a file (any format accepted by `phylum batch`)").conflicts_with_all(&["name", "version"]),
+                    Arg::new("concurrency").long("concurrency").value_name("N").help("Maximum number of concurrent lookups when using `--from-file` (default: 8)").requires("from-file"),
+                    Arg::new("columns").long("columns").value_name("columns").help("Comma-separated list of the table columns shown with `--from-file`").long_help(COLUMNS_ABOUT).requires("from-file"),
+                    Arg::new("filter").long("filter").value_name("filter").help(FILTER_ABOUT),
                 ])
         )
         .subcommand(
@@ -142,21 +247,20 @@ pub fn app<'a>() -> clap::Command<'a> {
                     Arg::new("label").short('l').value_name("label"),
                     Arg::new("verbose").short('v').long("verbose").help("Increase verbosity of api response."),
                     Arg::new("filter").long("filter").value_name("filter").help(FILTER_ABOUT),
-                    Arg::new("json").short('j').long("json").help("Produce output in json format (default: false)"),
                     Arg::new("project").short('p').long("project").value_name("project_name").help("Specify a project to use for analysis"),
                     Arg::new("group").short('g').long("group").value_name("group_name").help("Specify a group to use for analysis").requires("project")
                 ])
         )
         .subcommand(
             Command::new("batch")
-                .hide(true)
                 .about("Submits a batch of requests to the processing system")
+                .long_about(BATCH_ABOUT)
                 .args(&[
-                    Arg::new("file").short('f').long("file").value_name("file").help("File (or piped stdin) containing the list of packages (format `<name>:<version>`)").value_hint(ValueHint::FilePath),
-                    Arg::new("type").short('t').long("type").value_name("type").help("Package type (`npm`, `rubygems`, `pypi`, etc)"),
+                    Arg::new("file").short('f').long("file").value_name("file").help("File (or piped stdin) containing the list of packages").value_hint(ValueHint::FilePath),
+                    Arg::new("type").short('t').long("type").value_name("type").help("Default package type for entries without one (`npm`, `rubygems`, `pypi`, etc)"),
                     Arg::new("force").short('F').long("force").help("Force re-processing of packages (even if they already exist in the system)"),
-                    Arg::new("low-priority").short('L').long("low-priority"),
-                    Arg::new("label").short('l').long("label"),
+                    Arg::new("low-priority").short('L').long("low-priority").hide(true),
+                    Arg::new("label").short('l').long("label").value_name("label").help("Specify a label for the submission"),
                     Arg::new("project").short('p').long("project").value_name("project_name").help("Project to use for analysis"),
                     Arg::new("group").short('g').long("group").value_name("group_name").help("Group to use for analysis").requires("project"),
                 ])
@@ -168,11 +272,9 @@ pub fn app<'a>() -> clap::Command<'a> {
         .subcommand(
             Command::new("group")
                 .about("Interact with user groups")
-                .arg(Arg::new("json").short('j').long("json").help("Produce group list in json format (default: false)"))
                 .subcommand(
                     Command::new("list")
                         .about("List all groups the user is a member of")
-                        .arg(Arg::new("json").short('j').long("json").help("Produce output in json format (default: false)"))
                 )
                 .subcommand(
                     Command::new("create")
@@ -183,6 +285,11 @@ pub fn app<'a>() -> clap::Command<'a> {
 
     #[cfg(feature = "extensions")]
     {
+        app = app.arg(
+            Arg::new("offline")
+                .long("offline")
+                .help("Only import remote modules of extensions from the cache"),
+        );
         app = app.subcommand(extensions::command());
         app = extensions::add_extensions_subcommands(app);
     }
@@ -9,6 +9,7 @@ use spinners::{Spinner, Spinners};
 
 use phylum_cli::api::PhylumApi;
 use phylum_cli::commands::auth::*;
+use phylum_cli::commands::batch::handle_batch;
 #[cfg(feature = "extensions")]
 use phylum_cli::commands::extensions;
 use phylum_cli::commands::group::handle_group;
@@ -19,6 +20,9 @@ use phylum_cli::commands::project::handle_project;
 use phylum_cli::commands::uninstall::*;
 use phylum_cli::commands::{CommandResult, CommandValue, ExitCode};
 use phylum_cli::config::*;
+#[cfg(feature = "extensions")]
+use phylum_cli::deno::api::ApiState;
+use phylum_cli::format::output_format;
 use phylum_cli::print::*;
 use phylum_cli::update;
 use phylum_cli::{print_user_failure, print_user_success, print_user_warning};
@@ -56,25 +60,17 @@ async fn handle_commands() -> CommandResult {
     // Required for printing help messages since `get_matches()` consumes `App`
     let app_helper = &mut app.clone();
 
-    let matches = app.get_matches();
+    let args = std::env::args_os().collect::<Vec<_>>();
+    #[cfg(feature = "extensions")]
+    let (args, extension_args) = extensions::split_extension_args(&app, args);
+    let matches = app.get_matches_from(args);
+    init_color(matches.is_present("no-color"));
 
     #[cfg(feature = "selfmanage")]
     if let Some(matches) = matches.subcommand_matches("uninstall") {
         return handle_uninstall(matches);
     }
 
-    #[cfg(feature = "extensions")]
-    if let Some(matches) = matches.subcommand_matches("extension") {
-        return extensions::handle_extensions(matches).await;
-    }
-
-    #[cfg(feature = "extensions")]
-    for extension in extensions::installed_extensions().unwrap_or_default() {
-        if matches.subcommand_name() == Some(extension.name()) {
-            return extension.run().await;
-        }
-    }
-
     let settings_path = get_home_settings_path()?;
 
     let config_path = matches
@@ -93,6 +89,32 @@ async fn handle_commands() -> CommandResult {
         )
     })?;
 
+    let timeout = matches
+        .value_of("timeout")
+        .and_then(|t| t.parse::<u64>().ok());
+
+    let ignore_certs =
+        matches.is_present("no-check-certificate") || config.ignore_certs.unwrap_or_default();
+    if ignore_certs {
+        log::warn!("Ignoring TLS server certificate verification per user request.");
+    }
+
+    #[cfg(feature = "extensions")]
+    if let Some(matches) = matches.subcommand_matches("extension") {
+        return extensions::handle_extensions(matches, &config, timeout, ignore_certs).await;
+    }
+
+    #[cfg(feature = "extensions")]
+    if let Some((name, _)) = matches.subcommand() {
+        for extension in extensions::installed_extensions().unwrap_or_default() {
+            if extension.name() == name {
+                let api_state = ApiState::new(config, config_path, timeout, ignore_certs);
+                let offline = matches.is_present("offline");
+                return extension.run(api_state, offline, &extension_args).await;
+            }
+        }
+    }
+
     let mut check_for_updates = false;
 
     if matches.subcommand_matches("update").is_none() {
@@ -131,7 +153,7 @@ async fn handle_commands() -> CommandResult {
             return Ok(ExitCode::Ok.into());
         }
     } else if let Some(matches) = matches.subcommand_matches("package") {
-        if !(matches.is_present("name") && matches.is_present("version")) {
+        if !(matches.is_present("name") || matches.is_present("from-file")) {
             print_sc_help(app_helper, "package");
             return Ok(ExitCode::Ok.into());
         }
@@ -158,16 +180,6 @@ async fn handle_commands() -> CommandResult {
         return handle_parse(matches);
     }
 
-    let timeout = matches
-        .value_of("timeout")
-        .and_then(|t| t.parse::<u64>().ok());
-
-    let ignore_certs =
-        matches.is_present("no-check-certificate") || config.ignore_certs.unwrap_or_default();
-    if ignore_certs {
-        log::warn!("Ignoring TLS server certificate verification per user request.");
-    }
-
     if let Some(matches) = matches.subcommand_matches("auth") {
         return handle_auth(
             config,
@@ -199,22 +211,23 @@ async fn handle_commands() -> CommandResult {
 
     if matches.subcommand_matches("ping").is_some() {
         let resp = api.ping().await;
-        print_response(&resp, true, None);
+        print_response(&resp, output_format(&matches)?, None);
         return Ok(ExitCode::Ok.into());
     }
 
-    let should_submit = matches.subcommand_matches("analyze").is_some()
-        || matches.subcommand_matches("batch").is_some();
-
     // TODO: switch from if/else to non-exhaustive pattern match
     if let Some(matches) = matches.subcommand_matches("project") {
         handle_project(&mut api, matches).await?;
     } else if let Some(matches) = matches.subcommand_matches("package") {
         return handle_get_package(&mut api, &config.request_type, matches).await;
-    } else if should_submit {
-        return handle_submission(&mut api, config, &matches).await;
+    } else if let Some(matches) = matches.subcommand_matches("analyze") {
+        return handle_submission(&mut api, matches).await;
+    } else if let Some(matches) = matches.subcommand_matches("batch") {
+        return handle_batch(&mut api, &config.request_type, matches).await;
     } else if let Some(matches) = matches.subcommand_matches("history") {
         return handle_history(&mut api, matches).await;
+    } else if let Some(matches) = matches.subcommand_matches("job") {
+        return handle_job(&mut api, matches).await;
     } else if let Some(matches) = matches.subcommand_matches("group") {
         return handle_group(&mut api, matches).await;
     }
@@ -0,0 +1,362 @@
+//! `phylum batch` command for submitting lists of packages
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs::read_to_string;
+use std::io::{self, Read};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Result};
+use phylum_types::types::package::{PackageDescriptor, PackageType};
+
+use super::jobs::cli_project;
+use super::{CommandResult, ExitCode};
+use crate::api::PhylumApi;
+use crate::purl::{self, Purl};
+use crate::render::Renderable;
+use crate::{print_user_success, print_user_warning};
+
+/// A package list entry which could not be parsed.
+#[derive(Debug)]
+pub struct InvalidLine {
+    pub line: usize,
+    pub error: String,
+}
+
+impl fmt::Display for InvalidLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+/// Packages parsed from a package list.
+#[derive(Debug, Default)]
+pub struct PackageList {
+    pub packages: Vec<PackageDescriptor>,
+    pub invalid: Vec<InvalidLine>,
+}
+
+/// Column positions of a CSV package list.
+struct CsvColumns {
+    name: usize,
+    version: usize,
+    package_type: Option<usize>,
+}
+
+impl Default for CsvColumns {
+    fn default() -> Self {
+        Self {
+            name: 0,
+            version: 1,
+            package_type: Some(2),
+        }
+    }
+}
+
+impl CsvColumns {
+    /// Try to read column positions from a CSV header.
+    fn from_header(fields: &[&str]) -> Option<Self> {
+        let position = |column: &str| {
+            fields
+                .iter()
+                .position(|field| field.eq_ignore_ascii_case(column))
+        };
+
+        Some(Self {
+            name: position("name")?,
+            version: position("version")?,
+            package_type: position("type"),
+        })
+    }
+}
+
+/// Parse a list of packages.
+///
+/// The list is either the JSON output of `phylum parse`, or one package per line in any of the
+/// following formats:
+///
+///  - newline-delimited JSON package descriptors: `{"name": "x", "version": "1.0", "type": "npm"}`
+///  - package URLs: `pkg:npm/%40scope/name@1.0.0`
+///  - CSV with a `name,version,type` header, or columns in that order
+///  - `<name>@<version>` or `<name>:<version>`
+///
+/// Entries without an explicit type use the `package_type` default. Empty lines and lines
+/// starting with `#` are ignored.
+pub fn parse_package_list(data: &str, package_type: &PackageType) -> PackageList {
+    let mut list = PackageList::default();
+
+    if data.trim_start().starts_with('[') {
+        match serde_json::from_str(data) {
+            Ok(packages) => list.packages = packages,
+            Err(err) => list.invalid.push(InvalidLine {
+                line: err.line(),
+                error: err.to_string(),
+            }),
+        }
+        return list;
+    }
+
+    let mut csv_columns = None;
+    for (i, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let package = if line.starts_with('{') {
+            serde_json::from_str(line).map_err(|err| anyhow!("invalid JSON: {}", err))
+        } else if purl::is_purl(line) {
+            Purl::from_str(line).and_then(PackageDescriptor::try_from)
+        } else if line.contains(',') {
+            let fields = line
+                .split(',')
+                .map(|field| field.trim().trim_matches('"'))
+                .collect::<Vec<_>>();
+
+            // Only the first CSV line may be a header.
+            if csv_columns.is_none() {
+                if let Some(columns) = CsvColumns::from_header(&fields) {
+                    csv_columns = Some(columns);
+                    continue;
+                }
+            }
+
+            let columns = csv_columns.get_or_insert_with(CsvColumns::default);
+            parse_csv_fields(&fields, columns, package_type)
+        } else {
+            parse_name_version(line, package_type)
+        };
+
+        match package {
+            Ok(package) => list.packages.push(package),
+            Err(err) => list.invalid.push(InvalidLine {
+                line: i + 1,
+                error: err.to_string(),
+            }),
+        }
+    }
+
+    list
+}
+
+/// Parse a CSV package list entry.
+fn parse_csv_fields(
+    fields: &[&str],
+    columns: &CsvColumns,
+    package_type: &PackageType,
+) -> Result<PackageDescriptor> {
+    let field = |index: usize| fields.get(index).filter(|field| !field.is_empty());
+
+    let name = field(columns.name).ok_or_else(|| anyhow!("missing package name"))?;
+    let version = field(columns.version).ok_or_else(|| anyhow!("missing package version"))?;
+    let package_type = match columns.package_type.and_then(field) {
+        Some(package_type) => PackageType::from_str(package_type)
+            .map_err(|_| anyhow!("unknown package type `{}`", package_type))?,
+        None => package_type.to_owned(),
+    };
+
+    Ok(PackageDescriptor {
+        name: name.to_string(),
+        version: version.to_string(),
+        package_type,
+    })
+}
+
+/// Parse a `<name>@<version>` or `<name>:<version>` package list entry.
+fn parse_name_version(line: &str, package_type: &PackageType) -> Result<PackageDescriptor> {
+    // Ignore the first character, to allow for scoped npm packages like `@scope/name`.
+    let separator = match line.rfind('@').filter(|index| *index > 0) {
+        Some(index) => Some(index),
+        None => line.rfind(':'),
+    };
+
+    match separator {
+        Some(index) if index > 0 && index + 1 < line.len() => Ok(PackageDescriptor {
+            name: line[..index].to_owned(),
+            version: line[index + 1..].to_owned(),
+            package_type: package_type.to_owned(),
+        }),
+        _ => Err(anyhow!("expected `<name>@<version>` or `<name>:<version>`")),
+    }
+}
+
+/// Split packages into one list for each ecosystem.
+pub fn split_by_type(
+    packages: Vec<PackageDescriptor>,
+) -> Vec<(PackageType, Vec<PackageDescriptor>)> {
+    let mut split: Vec<(PackageType, Vec<PackageDescriptor>)> = Vec::new();
+
+    for package in packages {
+        match split
+            .iter_mut()
+            .find(|(package_type, _)| *package_type == package.package_type)
+        {
+            Some((_, packages)) => packages.push(package),
+            None => split.push((package.package_type.to_owned(), vec![package])),
+        }
+    }
+
+    split
+}
+
+/// Handle the `batch` subcommand.
+///
+/// Packages of different ecosystems are submitted as separate jobs.
+pub async fn handle_batch(
+    api: &mut PhylumApi,
+    request_type: &PackageType,
+    matches: &clap::ArgMatches,
+) -> CommandResult {
+    let (project, group) = cli_project(api, matches).await?;
+
+    let data = match matches.value_of("file") {
+        Some(file) => read_to_string(file)
+            .with_context(|| format!("Unable to read package list `{}`", file))?,
+        None => {
+            log::info!("Waiting on stdin...");
+            let mut data = String::new();
+            io::stdin().read_to_string(&mut data)?;
+            data
+        }
+    };
+
+    // If a package type was provided on the command line, prefer that
+    //  to the global setting
+    let default_type = match matches.value_of("type") {
+        Some(package_type) => PackageType::from_str(package_type)
+            .map_err(|_| anyhow!("Unknown package type `{}`", package_type))?,
+        None => request_type.to_owned(),
+    };
+
+    let list = parse_package_list(&data, &default_type);
+    for invalid in &list.invalid {
+        print_user_warning!("Skipping invalid package on {}", invalid);
+    }
+
+    if list.packages.is_empty() {
+        return Err(anyhow!("No valid packages found in the package list"));
+    }
+
+    let label = matches.value_of("label").map(String::from);
+    let is_user = !matches.is_present("force");
+
+    for (package_type, packages) in split_by_type(list.packages) {
+        log::debug!(
+            "Submitting {} packages of type {}...",
+            packages.len(),
+            package_type
+        );
+        let job_id = api
+            .submit_request(
+                &package_type,
+                &packages,
+                is_user,
+                project,
+                label.clone(),
+                group.clone(),
+            )
+            .await?;
+
+        log::debug!("Response => {:?}", job_id);
+        print_user_success!(
+            "Job ID: {} ({} {} packages)",
+            job_id,
+            packages.len(),
+            package_type.render()
+        );
+    }
+
+    Ok(ExitCode::Ok.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_name_version_lines() {
+        let data =
+            "# Audit list\nreact@16.13.1\n\n@schematics/angular@9.1.9\norg.yaml:snakeyaml:1.30\n";
+        let list = parse_package_list(data, &PackageType::Npm);
+
+        assert!(list.invalid.is_empty());
+        assert_eq!(list.packages.len(), 3);
+        assert_eq!(list.packages[0].name, "react");
+        assert_eq!(list.packages[0].version, "16.13.1");
+        assert_eq!(list.packages[1].name, "@schematics/angular");
+        assert_eq!(list.packages[1].version, "9.1.9");
+        assert_eq!(list.packages[2].name, "org.yaml:snakeyaml");
+        assert_eq!(list.packages[2].version, "1.30");
+    }
+
+    #[test]
+    fn parse_json_array() {
+        let data = r#"[{"name": "requests", "version": "2.27.1", "type": "pypi"}]"#;
+        let list = parse_package_list(data, &PackageType::Npm);
+
+        assert_eq!(list.packages.len(), 1);
+        assert_eq!(list.packages[0].name, "requests");
+        assert_eq!(list.packages[0].package_type, PackageType::PyPi);
+    }
+
+    #[test]
+    fn parse_mixed_formats() {
+        let data = r#"{"name": "requests", "version": "2.27.1", "type": "pypi"}
+pkg:gem/rails@7.0.3
+pkg:npm/%40angular/core@12.1.0
+name,version,type
+lodash,4.17.21,npm
+"#;
+        let list = parse_package_list(data, &PackageType::Maven);
+
+        assert!(list.invalid.is_empty());
+        let packages = list
+            .packages
+            .iter()
+            .map(|pkg| (pkg.name.as_str(), pkg.version.as_str(), &pkg.package_type))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            packages,
+            vec![
+                ("requests", "2.27.1", &PackageType::PyPi),
+                ("rails", "7.0.3", &PackageType::RubyGems),
+                ("@angular/core", "12.1.0", &PackageType::Npm),
+                ("lodash", "4.17.21", &PackageType::Npm),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_csv_header_order() {
+        let data = "type,name,version\npypi,numpy,1.22.4\n";
+        let list = parse_package_list(data, &PackageType::Npm);
+
+        assert_eq!(list.packages.len(), 1);
+        assert_eq!(list.packages[0].name, "numpy");
+        assert_eq!(list.packages[0].version, "1.22.4");
+        assert_eq!(list.packages[0].package_type, PackageType::PyPi);
+    }
+
+    #[test]
+    fn invalid_lines_are_reported() {
+        let data = "react@16.13.1\nreact\n{\"name\": \"x\"}\npkg:npm/left-pad\n";
+        let list = parse_package_list(data, &PackageType::Npm);
+
+        assert_eq!(list.packages.len(), 1);
+        let lines = list.invalid.iter().map(|i| i.line).collect::<Vec<_>>();
+        assert_eq!(lines, vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn split_mixed_ecosystems() {
+        let data = "pkg:npm/react@16.13.1\npkg:pypi/numpy@1.22.4\npkg:npm/lodash@4.17.21\n";
+        let list = parse_package_list(data, &PackageType::Npm);
+
+        let split = split_by_type(list.packages);
+        assert_eq!(split.len(), 2);
+        assert_eq!(split[0].0, PackageType::Npm);
+        assert_eq!(split[0].1.len(), 2);
+        assert_eq!(split[1].0, PackageType::PyPi);
+        assert_eq!(split[1].1.len(), 1);
+    }
+}
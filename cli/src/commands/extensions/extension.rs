@@ -1,19 +1,37 @@
+use std::collections::HashSet;
 use std::convert::TryFrom;
+use std::ffi::OsString;
 use std::fs::{self, DirBuilder};
 #[cfg(unix)]
 use std::os::unix::fs::DirBuilderExt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Result};
+use clap::{Arg, ArgMatches, Command};
 use lazy_static::lazy_static;
 use regex::Regex;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tempfile::TempDir;
 use walkdir::WalkDir;
 
+use super::source::ExtensionSource;
 use crate::commands::{CommandResult, ExitCode};
-use crate::deno::DenoRuntime;
+use crate::deno::api::ApiState;
+use crate::deno::permissions::Permissions;
+use crate::deno::remote::RemoteModules;
+use crate::deno::{DenoRuntime, ExtensionContext};
 
-const MANIFEST_NAME: &str = "PhylumExt.toml";
+pub(super) const MANIFEST_NAME: &str = "PhylumExt.toml";
+
+/// Metadata written to the directory of installed extensions.
+const METADATA_NAME: &str = ".phylum-install.json";
+
+/// Names and long options of the CLI's global arguments, which extensions can't declare.
+const RESERVED_NAMES: &[&str] = &["help", "format", "json", "no-color", "offline"];
+
+/// Short options of the CLI's global arguments, which extensions can't declare.
+const RESERVED_SHORTS: &[char] = &['h', 'j'];
 
 lazy_static! {
     static ref EXTENSION_NAME_RE: Regex = Regex::new(r#"^[a-z0-9_-]+$"#).unwrap();
@@ -28,8 +46,91 @@ pub struct Extension {
 #[derive(Deserialize, Debug)]
 pub struct ExtensionManifest {
     name: String,
+    version: Option<String>,
     description: Option<String>,
     entry_point: String,
+    /// Arguments accepted by the extension. Without them, all arguments are forwarded as is.
+    #[serde(default, rename = "args")]
+    arguments: Vec<ExtensionArg>,
+    /// Resources the extension can access.
+    #[serde(default)]
+    permissions: Permissions,
+}
+
+/// Installation details of an extension.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct InstallMetadata {
+    /// Where the extension was installed from.
+    pub source: ExtensionSource,
+}
+
+/// An argument of an extension subcommand, declared in its manifest.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ExtensionArg {
+    name: String,
+    help: Option<String>,
+    short: Option<char>,
+    long: Option<String>,
+    /// Whether an option takes a value. Arguments without `short` or `long` are positional, and
+    /// always take values.
+    #[serde(default)]
+    takes_value: bool,
+    #[serde(default)]
+    required: bool,
+    #[serde(default)]
+    multiple: bool,
+}
+
+impl ExtensionArg {
+    fn is_positional(&self) -> bool {
+        self.short.is_none() && self.long.is_none()
+    }
+
+    fn takes_value(&self) -> bool {
+        self.takes_value || self.is_positional()
+    }
+
+    /// Build the `clap` argument.
+    fn to_arg(&self) -> Arg<'static> {
+        let mut arg = Arg::new(leak(&self.name))
+            .takes_value(self.takes_value())
+            .required(self.required)
+            .multiple_occurrences(self.multiple && !self.is_positional())
+            .multiple_values(self.multiple && self.is_positional());
+        if let Some(help) = &self.help {
+            arg = arg.help(leak(help));
+        }
+        if let Some(short) = self.short {
+            arg = arg.short(short);
+        }
+        if let Some(long) = &self.long {
+            arg = arg.long(leak(long));
+        }
+        if self.takes_value() {
+            arg = arg.value_name(leak(&self.name));
+        }
+        arg
+    }
+
+    /// Get the value of the argument, as passed to the extension.
+    fn value(&self, matches: &ArgMatches) -> Value {
+        match (self.takes_value(), self.multiple) {
+            (true, true) => matches
+                .values_of(self.name.as_str())
+                .map(|values| values.map(Value::from).collect::<Vec<_>>().into())
+                .unwrap_or_else(|| Value::Array(Vec::new())),
+            (true, false) => matches.value_of(self.name.as_str()).into(),
+            (false, _) => matches.is_present(self.name.as_str()).into(),
+        }
+    }
+}
+
+/// Convert a manifest string for use in `clap` commands, which only accept static strings.
+///
+/// Extension subcommands are built once per execution, so their strings are never freed.
+fn leak(text: &str) -> &'static str {
+    Box::leak(text.to_owned().into_boxed_str())
 }
 
 impl Extension {
@@ -37,6 +138,10 @@ impl Extension {
         &self.manifest.name
     }
 
+    pub fn version(&self) -> Option<&str> {
+        self.manifest.version.as_deref()
+    }
+
     pub fn description(&self) -> Option<&str> {
         self.manifest.description.as_deref()
     }
@@ -45,14 +150,84 @@ impl Extension {
         &self.manifest.entry_point
     }
 
-    /// Install the extension in the default path.
-    pub fn install(&self) -> Result<()> {
+    pub fn permissions(&self) -> &Permissions {
+        &self.manifest.permissions
+    }
+
+    /// Build the subcommand listing the extension in the CLI.
+    ///
+    /// The arguments following the extension name are parsed by [`Extension::command`]
+    /// instead, so they can't be taken by the global arguments of the CLI.
+    pub fn subcommand(&self) -> Command<'static> {
+        let mut command = Command::new(leak(self.name()));
+        if let Some(description) = self.description() {
+            command = command.about(leak(description));
+        }
+        command
+    }
+
+    /// Build the command parsing the arguments of the extension.
+    pub fn command(&self) -> Command<'static> {
+        let command = self
+            .subcommand()
+            .bin_name(format!("phylum {}", self.name()))
+            .no_binary_name(true);
+
+        if self.manifest.arguments.is_empty() {
+            command.trailing_var_arg(true).arg(
+                Arg::new("args")
+                    .value_name("ARGS")
+                    .help("Arguments passed to the extension")
+                    .multiple_values(true)
+                    .allow_hyphen_values(true),
+            )
+        } else {
+            command.args(self.manifest.arguments.iter().map(ExtensionArg::to_arg))
+        }
+    }
+
+    /// Get the arguments passed to the extension.
+    ///
+    /// Returns the values of the positional arguments in order, and the values of all arguments
+    /// by name.
+    fn arguments(&self, matches: &ArgMatches) -> (Vec<String>, Map<String, Value>) {
+        if self.manifest.arguments.is_empty() {
+            let args = matches
+                .values_of("args")
+                .map(|values| values.map(String::from).collect())
+                .unwrap_or_default();
+            return (args, Map::new());
+        }
+
+        let args = self
+            .manifest
+            .arguments
+            .iter()
+            .filter(|arg| arg.is_positional())
+            .flat_map(|arg| matches.values_of(arg.name.as_str()).into_iter().flatten())
+            .map(String::from)
+            .collect();
+        let options = self
+            .manifest
+            .arguments
+            .iter()
+            .map(|arg| (arg.name.clone(), arg.value(matches)))
+            .collect();
+        (args, options)
+    }
+
+    /// Get the installation details of an installed extension.
+    pub fn metadata(&self) -> Option<InstallMetadata> {
+        let metadata = fs::read_to_string(self.path.join(METADATA_NAME)).ok()?;
+        serde_json::from_str(&metadata).ok()
+    }
+
+    /// Install the extension in the default path, recording where it was installed from.
+    pub fn install(&self, source: ExtensionSource) -> Result<()> {
         println!("Installing extension {}...", self.name());
 
         let target_prefix = extension_path(self.name())?;
 
-        // TODO we may want to implement `upgrade` in the future, which would
-        // allow writing to the path of an already installed extension.
         if target_prefix.exists() {
             return Err(anyhow!("extension already exists, skipping"));
         }
@@ -63,30 +238,97 @@ impl Extension {
             ));
         }
 
+        let staged = self.stage(source)?;
+        fs::rename(staged.path(), &target_prefix)?;
+        // The staging directory was moved in place, don't clean it up.
+        let _ = staged.into_path();
+
+        println!("Extension {} installed successfully", self.name());
+
+        Ok(())
+    }
+
+    /// Replace the installed version of the extension, keeping it for `rollback`.
+    pub fn upgrade(&self, source: ExtensionSource) -> Result<()> {
+        println!("Upgrading extension {}...", self.name());
+
+        let target_prefix = extension_path(self.name())?;
+        if !target_prefix.exists() {
+            return Err(anyhow!(
+                "extension {} is not installed, skipping",
+                self.name()
+            ));
+        }
+
+        let staged = self.stage(source)?;
+
+        let previous_prefix = previous_extension_path(self.name())?;
+        if previous_prefix.exists() {
+            fs::remove_dir_all(&previous_prefix)?;
+        }
+        create_private_dir(&previous_extensions_path()?)?;
+
+        fs::rename(&target_prefix, &previous_prefix)?;
+        if let Err(err) = fs::rename(staged.path(), &target_prefix) {
+            // Put the installed version back in place.
+            fs::rename(&previous_prefix, &target_prefix)?;
+            return Err(err.into());
+        }
+        let _ = staged.into_path();
+
+        println!("Extension {} upgraded successfully", self.name());
+
+        Ok(())
+    }
+
+    /// Restore the version of an extension installed before its last upgrade.
+    ///
+    /// The replaced version is kept, so a rollback can be undone by another rollback.
+    pub fn rollback(name: &str) -> Result<Extension> {
+        let target_prefix = extension_path(name)?;
+        let previous_prefix = previous_extension_path(name)?;
+
+        if !previous_prefix.exists() {
+            return Err(anyhow!("{}: no previous version to roll back to", name));
+        }
+        Extension::try_from(previous_prefix.clone())?;
+
+        // Swap both versions through a temporary location.
+        let swap = tempfile::Builder::new()
+            .prefix(".rollback-")
+            .tempdir_in(extensions_path()?)?;
+        let swap_path = swap.path().join(name);
+        fs::rename(&target_prefix, &swap_path)?;
+        fs::rename(&previous_prefix, &target_prefix)?;
+        fs::rename(&swap_path, &previous_prefix)?;
+
+        Extension::load(name)
+    }
+
+    /// Copy the extension to a temporary directory next to the installed extensions, so it can
+    /// be moved in place atomically.
+    fn stage(&self, source: ExtensionSource) -> Result<TempDir> {
+        let extensions_path = extensions_path()?;
+        create_private_dir(&extensions_path)?;
+        let staged = tempfile::Builder::new()
+            .prefix(".staging-")
+            .tempdir_in(&extensions_path)?;
+
         for entry in WalkDir::new(&self.path) {
             let source_path = entry?.into_path();
-            let dest_path = target_prefix.join(source_path.strip_prefix(&self.path)?);
+            let dest_path = staged.path().join(source_path.strip_prefix(&self.path)?);
 
             if source_path.is_dir() {
-                let mut builder = DirBuilder::new();
-
-                #[cfg(unix)]
-                builder.mode(0o700);
-
-                builder.recursive(true);
-                builder.create(&dest_path)?;
+                create_private_dir(&dest_path)?;
             } else if source_path.is_file() {
-                if dest_path.exists() {
-                    return Err(anyhow!("{}: already exists", dest_path.to_string_lossy()));
-                } else {
-                    fs::copy(source_path, dest_path)?;
-                }
+                fs::copy(source_path, dest_path)?;
             }
         }
 
-        println!("Extension {} installed successfully", self.name());
+        let metadata = serde_json::to_string_pretty(&InstallMetadata { source })?;
+        fs::write(staged.path().join(METADATA_NAME), metadata)?;
 
-        Ok(())
+        Ok(staged)
     }
 
     pub fn uninstall(self) -> Result<()> {
@@ -102,6 +344,11 @@ impl Extension {
 
         fs::remove_dir_all(&self.path)?;
 
+        let previous_prefix = previous_extension_path(self.name())?;
+        if previous_prefix.exists() {
+            fs::remove_dir_all(&previous_prefix)?;
+        }
+
         println!("Extension {} uninstalled successfully", self.name());
 
         Ok(())
@@ -112,10 +359,32 @@ impl Extension {
         Extension::try_from(extension_path(name)?)
     }
 
-    /// Execute an extension subcommand.
-    pub async fn run(&self) -> CommandResult {
-        let script_path = self.path.join(&self.manifest.entry_point);
-        let deno = DenoRuntime::new();
+    /// Execute an extension subcommand with the arguments following its name.
+    ///
+    /// When `offline` is set, remote modules are only imported from the cache.
+    pub async fn run(
+        &self,
+        api_state: ApiState,
+        offline: bool,
+        args: &[OsString],
+    ) -> CommandResult {
+        let matches = self.command().get_matches_from(args);
+        let (args, options) = self.arguments(&matches);
+        let context = ExtensionContext::new(args, options, self.permissions())?;
+        let remote_modules = RemoteModules::new(
+            &self.path,
+            offline,
+            api_state.timeout(),
+            api_state.ignore_certs(),
+        )?;
+
+        let entry_point = self.path.join(&self.manifest.entry_point);
+        let mut deno = DenoRuntime::new(
+            api_state,
+            self.permissions().clone(),
+            remote_modules,
+            context,
+        )?;
         deno.run(&entry_point.to_string_lossy()).await?;
         Ok(ExitCode::Ok.into())
     }
@@ -165,9 +434,36 @@ impl TryFrom<PathBuf> for Extension {
             ));
         }
 
+        let mut names = HashSet::new();
+        for arg in &manifest.arguments {
+            if !names.insert(&arg.name) {
+                return Err(anyhow!(
+                    "{}: duplicate argument `{}`",
+                    manifest.name,
+                    arg.name
+                ));
+            }
+
+            let reserved = RESERVED_NAMES.contains(&arg.name.as_str())
+                || arg
+                    .long
+                    .as_deref()
+                    .map_or(false, |long| RESERVED_NAMES.contains(&long))
+                || arg
+                    .short
+                    .map_or(false, |short| RESERVED_SHORTS.contains(&short));
+            if reserved {
+                return Err(anyhow!(
+                    "{}: argument `{}` conflicts with an argument of the CLI",
+                    manifest.name,
+                    arg.name
+                ));
+            }
+        }
+
         // TODO add further validation if necessary:
         // - Check that the entry point is a supported format (.wasm?)
-        // - Check that the entry point is appropriately signed
+        // Signatures are verified when installing, see `signature::verify_extension`.
         Ok(Extension { path, manifest })
     }
 }
@@ -184,3 +480,27 @@ pub fn extension_path(name: &str) -> Result<PathBuf, anyhow::Error> {
 
     Ok(extensions_path()?.join(name))
 }
+
+// Construct and return the path of the versions replaced by upgrades:
+// $XDG_DATA_HOME/phylum/extensions/.previous
+fn previous_extensions_path() -> Result<PathBuf> {
+    Ok(extensions_path()?.join(".previous"))
+}
+
+fn previous_extension_path(name: &str) -> Result<PathBuf> {
+    // Validate the name like installed extensions.
+    extension_path(name)?;
+    Ok(previous_extensions_path()?.join(name))
+}
+
+/// Create a directory only accessible by the user, with its parents.
+fn create_private_dir(path: &Path) -> Result<()> {
+    let mut builder = DirBuilder::new();
+
+    #[cfg(unix)]
+    builder.mode(0o700);
+
+    builder.recursive(true);
+    builder.create(path)?;
+    Ok(())
+}
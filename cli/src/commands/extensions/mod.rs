@@ -1,13 +1,26 @@
 mod extension;
+mod signature;
+mod source;
 
-use std::{collections::HashSet, convert::TryFrom, fs, io::ErrorKind, path::PathBuf};
+use std::{
+    collections::HashSet, convert::TryFrom, ffi::OsString, fs, io::ErrorKind, path::Path,
+    time::Duration,
+};
 
 use crate::commands::{CommandResult, CommandValue, ExitCode};
+use crate::config::Config;
+use crate::print_user_warning;
 pub use extension::*;
+pub use signature::*;
+pub use source::*;
 
-use anyhow::Result;
-use clap::{arg, ArgMatches, Command, ValueHint};
+use anyhow::{anyhow, Result};
+use atty::Stream;
+use clap::{arg, Arg, ArgMatches, ArgSettings, Command, ValueHint};
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::Confirm;
 use log::{error, warn};
+use reqwest::Client;
 
 pub fn command<'a>() -> Command<'a> {
     Command::new("extension")
@@ -15,7 +28,25 @@ pub fn command<'a>() -> Command<'a> {
         .subcommand(
             Command::new("add")
                 .about("Install extension")
-                .arg(arg!([PATH]).required(true).value_hint(ValueHint::DirPath)),
+                .arg(
+                    arg!([SOURCE] "Directory, `.tar.gz` or `.zip` archive path or URL, or git URL with an optional `#<ref>`")
+                        .required(true)
+                        .value_hint(ValueHint::AnyPath),
+                )
+                .arg(arg!(-y --yes "Accept the permissions requested by the extension"))
+                .arg(arg!(--"allow-unsigned" "Install the extension without a trusted signature")),
+        )
+        .subcommand(
+            Command::new("upgrade")
+                .about("Upgrade extension from the source it was installed from")
+                .arg(arg!([NAME]).required(true))
+                .arg(arg!(-y --yes "Accept new permissions requested by the extension"))
+                .arg(arg!(--"allow-unsigned" "Install the extension without a trusted signature")),
+        )
+        .subcommand(
+            Command::new("rollback")
+                .about("Restore the version of extension installed before its last upgrade")
+                .arg(arg!([NAME]).required(true)),
         )
         .subcommand(
             Command::new("remove")
@@ -54,15 +85,90 @@ pub fn add_extensions_subcommands(command: Command<'_>) -> Command<'_> {
                 true
             }
         })
-        .fold(command, |command, ext| {
-            command.subcommand(Command::new(ext.name()))
-        })
+        .fold(command, |command, ext| command.subcommand(ext.subcommand()))
+}
+
+/// Split the command line arguments after the name of an installed extension.
+///
+/// Extensions parse the arguments following their name on their own, so the global arguments
+/// of the CLI can't take them. Returns the arguments of the CLI, and those of the extension.
+pub fn split_extension_args(
+    app: &Command<'_>,
+    mut args: Vec<OsString>,
+) -> (Vec<OsString>, Vec<OsString>) {
+    let mut index = 1;
+    while index < args.len() {
+        let arg = args[index].to_string_lossy().into_owned();
+        if arg == "--" {
+            break;
+        } else if let Some(long) = arg.strip_prefix("--") {
+            if !long.contains('=') && takes_value(app, |arg| arg.get_long() == Some(long)) {
+                index += 1;
+            }
+        } else if let Some(shorts) = arg.strip_prefix('-').filter(|shorts| !shorts.is_empty()) {
+            // The value of a short option is either the rest of the argument, or the next one.
+            let value_start = shorts
+                .char_indices()
+                .find(|(_, short)| takes_value(app, |arg| arg.get_short() == Some(*short)))
+                .map(|(start, short)| start + short.len_utf8());
+            if value_start == Some(shorts.len()) {
+                index += 1;
+            }
+        } else {
+            let is_extension = installed_extensions()
+                .unwrap_or_default()
+                .iter()
+                .any(|extension| extension.name() == arg);
+            if is_extension {
+                let extension_args = args.split_off(index + 1);
+                return (args, extension_args);
+            }
+            break;
+        }
+        index += 1;
+    }
+
+    (args, Vec::new())
+}
+
+/// Check if the argument of `app` matching `matches` takes a value.
+fn takes_value(app: &Command<'_>, matches: impl Fn(&Arg<'_>) -> bool) -> bool {
+    app.get_arguments()
+        .any(|arg| matches(arg) && arg.is_set(ArgSettings::TakesValue))
 }
 
 /// Entry point for the `extensions` subcommand.
-pub async fn handle_extensions(matches: &ArgMatches) -> CommandResult {
+///
+/// Archives are downloaded with the `timeout` and `ignore_certs` settings of the CLI.
+pub async fn handle_extensions(
+    matches: &ArgMatches,
+    config: &Config,
+    timeout: Option<u64>,
+    ignore_certs: bool,
+) -> CommandResult {
+    let client = Client::builder()
+        .timeout(Duration::from_secs(timeout.unwrap_or(std::u64::MAX)))
+        .danger_accept_invalid_certs(ignore_certs)
+        .build()?;
+
     match matches.subcommand() {
-        Some(("add", matches)) => handle_add_extension(matches.value_of_t("PATH")?).await,
+        Some(("add", matches)) => {
+            handle_add_extension(
+                matches.value_of_t("SOURCE")?,
+                install_options(matches, config, &client),
+            )
+            .await
+        }
+        Some(("upgrade", matches)) => {
+            handle_upgrade_extension(
+                matches.value_of("NAME").unwrap(),
+                install_options(matches, config, &client),
+            )
+            .await
+        }
+        Some(("rollback", matches)) => {
+            handle_rollback_extension(matches.value_of("NAME").unwrap()).await
+        }
         Some(("remove", matches)) => {
             handle_remove_extension(matches.value_of("NAME").unwrap()).await
         }
@@ -71,16 +177,156 @@ pub async fn handle_extensions(matches: &ArgMatches) -> CommandResult {
     }
 }
 
+/// Options of the `extension add` and `extension upgrade` subcommands.
+struct InstallOptions<'a> {
+    /// Accept the permissions of the extension without a prompt.
+    accept: bool,
+    allow_unsigned: bool,
+    trusted_keys: &'a [String],
+    /// Client downloading archives.
+    client: &'a Client,
+}
+
+fn install_options<'a>(
+    matches: &ArgMatches,
+    config: &'a Config,
+    client: &'a Client,
+) -> InstallOptions<'a> {
+    InstallOptions {
+        accept: matches.is_present("yes"),
+        allow_unsigned: matches.is_present("allow-unsigned"),
+        trusted_keys: config.extension_keys.as_deref().unwrap_or_default(),
+        client,
+    }
+}
+
 /// Handle the `extension add` subcommand path.
-/// Add the extension from the specified path.
-async fn handle_add_extension(path: PathBuf) -> CommandResult {
-    let extension = Extension::try_from(path)?;
+/// Add the extension from the specified directory, archive or git repository.
+///
+/// The extension must be signed by a trusted key, and the user has to accept the permissions
+/// it requests.
+async fn handle_add_extension(
+    source: ExtensionSource,
+    options: InstallOptions<'_>,
+) -> CommandResult {
+    let fetched = source.fetch(options.client).await?;
+    let extension = Extension::try_from(fetched.path.clone())?;
+    check_signature(&fetched.path, &options)?;
+
+    if !extension.permissions().is_empty() {
+        accept_permissions(&extension, options.accept)?;
+    }
+
+    extension.install(source)?;
 
-    extension.install()?;
+    Ok(CommandValue::Code(ExitCode::Ok))
+}
+
+/// Handle the `extension upgrade` subcommand path.
+/// Reinstall the extension from the source it was installed from.
+///
+/// Permissions which differ from those of the installed version have to be accepted again.
+async fn handle_upgrade_extension(name: &str, options: InstallOptions<'_>) -> CommandResult {
+    let installed = Extension::load(name)?;
+    let source = installed
+        .metadata()
+        .map(|metadata| metadata.source)
+        .ok_or_else(|| {
+            anyhow!(
+                "{}: installation source is unknown, reinstall the extension to upgrade it",
+                name
+            )
+        })?;
+
+    let fetched = source.fetch(options.client).await?;
+    let extension = Extension::try_from(fetched.path.clone())?;
+    if extension.name() != installed.name() {
+        return Err(anyhow!(
+            "{}: source now contains extension {}, skipping",
+            name,
+            extension.name()
+        ));
+    }
+    check_signature(&fetched.path, &options)?;
+
+    if extension.permissions() != installed.permissions() && !extension.permissions().is_empty() {
+        accept_permissions(&extension, options.accept)?;
+    }
+
+    extension.upgrade(source)?;
+    println!(
+        "Upgraded {} from version {} to {}",
+        name,
+        installed.version().unwrap_or("unknown"),
+        extension.version().unwrap_or("unknown")
+    );
 
     Ok(CommandValue::Code(ExitCode::Ok))
 }
 
+/// Handle the `extension rollback` subcommand path.
+/// Restore the version of the extension replaced by its last upgrade.
+async fn handle_rollback_extension(name: &str) -> CommandResult {
+    let extension = Extension::rollback(name)?;
+
+    println!(
+        "Extension {} rolled back to version {}",
+        name,
+        extension.version().unwrap_or("unknown")
+    );
+
+    Ok(CommandValue::Code(ExitCode::Ok))
+}
+
+/// Check that the extension at `path` is signed by a trusted key, unless unsigned extensions
+/// are allowed.
+fn check_signature(path: &Path, options: &InstallOptions) -> Result<()> {
+    match verify_extension(path, options.trusted_keys) {
+        Ok(key) => {
+            log::debug!("Extension is signed by {}", key);
+            Ok(())
+        }
+        Err(err) if options.allow_unsigned => {
+            print_user_warning!("Installing extension without a trusted signature: {}", err);
+            Ok(())
+        }
+        Err(err) => Err(anyhow!(
+            "{}, use `--allow-unsigned` to install it anyway",
+            err
+        )),
+    }
+}
+
+/// List the permissions requested by an extension, and ask the user to accept them unless
+/// `accept` is set.
+fn accept_permissions(extension: &Extension, accept: bool) -> Result<()> {
+    println!(
+        "Extension {} requests the following permissions:",
+        extension.name()
+    );
+    print!("{}", extension.permissions());
+
+    if !accept && !confirm_permissions()? {
+        return Err(anyhow!("permissions were not accepted, skipping"));
+    }
+
+    Ok(())
+}
+
+/// Ask the user to accept the permissions of an extension.
+fn confirm_permissions() -> Result<bool> {
+    if !atty::is(Stream::Stdin) {
+        return Err(anyhow!(
+            "cannot confirm permissions without a terminal, use `--yes` to accept them"
+        ));
+    }
+
+    Ok(Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Install the extension with these permissions?")
+        .default(false)
+        .interact()?)
+}
+
 /// Handle the `extension remove` subcommand path.
 /// Remove the extension named as specified.
 async fn handle_remove_extension(name: &str) -> CommandResult {
@@ -100,7 +346,12 @@ async fn handle_list_extensions() -> CommandResult {
         println!("No extensions are currently installed.");
     } else {
         extensions.into_iter().for_each(|ext| {
-            println!("{:20}   {}", ext.name(), ext.description().unwrap_or(""));
+            println!(
+                "{:20}   {:10}   {}",
+                ext.name(),
+                ext.version().unwrap_or("-"),
+                ext.description().unwrap_or("")
+            );
         });
     }
 
@@ -123,6 +374,11 @@ pub fn installed_extensions() -> Result<Vec<Extension>> {
     };
 
     Ok(dir_entry
+        // Skip staged installations and previous versions.
+        .filter(|dir_entry| match dir_entry {
+            Ok(dir_entry) => !dir_entry.file_name().to_string_lossy().starts_with('.'),
+            Err(_) => true,
+        })
         .filter_map(|dir_entry| {
             match dir_entry
                 .map_err(|e| e.into())
@@ -0,0 +1,146 @@
+//! Signature verification of extensions.
+//!
+//! Signed extensions ship a `PhylumExt.sha256` file listing the SHA-256 hash of each of their
+//! files, in the format of `sha256sum`, and its minisign signature in `PhylumExt.sha256.minisig`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use minisign_verify::{PublicKey, Signature};
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+/// Hashes of the files of an extension.
+pub const CHECKSUMS_NAME: &str = "PhylumExt.sha256";
+
+/// Minisign signature of the hashes.
+pub const SIGNATURE_NAME: &str = "PhylumExt.sha256.minisig";
+
+/// Verify that the extension at `path` is signed by one of the `trusted_keys`, and that none of
+/// its files were added, removed or modified since it was signed.
+///
+/// Returns the public key which signed the extension.
+pub fn verify_extension<'a>(path: &Path, trusted_keys: &'a [String]) -> Result<&'a str> {
+    let signature_path = path.join(SIGNATURE_NAME);
+    if !signature_path.exists() {
+        return Err(anyhow!(
+            "extension is not signed, missing {}",
+            SIGNATURE_NAME
+        ));
+    }
+
+    let checksums = fs::read(path.join(CHECKSUMS_NAME))
+        .with_context(|| format!("signed extension is missing {}", CHECKSUMS_NAME))?;
+    let signature = Signature::decode(&fs::read_to_string(signature_path)?)
+        .map_err(|err| anyhow!("invalid extension signature: {}", err))?;
+
+    let key = trusted_keys
+        .iter()
+        .find(|key| match PublicKey::from_base64(key) {
+            Ok(public_key) => public_key.verify(&checksums, &signature, false).is_ok(),
+            Err(err) => {
+                log::warn!("Invalid trusted extension key {}: {}", key, err);
+                false
+            }
+        })
+        .ok_or_else(|| anyhow!("extension is not signed by a trusted key"))?;
+
+    verify_files(path, &String::from_utf8_lossy(&checksums))?;
+
+    Ok(key)
+}
+
+/// Verify that the files of the extension at `path` match their signed `checksums`.
+///
+/// Symlinks are rejected, since they could point at files which aren't covered by the
+/// signature.
+fn verify_files(path: &Path, checksums: &str) -> Result<()> {
+    let mut expected = parse_checksums(checksums)?;
+    for entry in WalkDir::new(path) {
+        let entry = entry?;
+        if entry.file_type().is_dir() {
+            continue;
+        }
+
+        let relative_path = entry
+            .path()
+            .strip_prefix(path)?
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if !entry.file_type().is_file() {
+            return Err(anyhow!(
+                "{}: signed extensions can only contain regular files",
+                relative_path
+            ));
+        }
+        if relative_path == CHECKSUMS_NAME || relative_path == SIGNATURE_NAME {
+            continue;
+        }
+
+        let hash = expected
+            .remove(&relative_path)
+            .ok_or_else(|| anyhow!("{}: file is not covered by the signature", relative_path))?;
+        if hash != format!("{:x}", Sha256::digest(&fs::read(entry.path())?)) {
+            return Err(anyhow!(
+                "{}: file was modified after signing",
+                relative_path
+            ));
+        }
+    }
+
+    if let Some(missing) = expected.keys().next() {
+        return Err(anyhow!("{}: signed file is missing", missing));
+    }
+
+    Ok(())
+}
+
+/// Parse the hashes of the signed files, by path.
+fn parse_checksums(checksums: &str) -> Result<BTreeMap<String, String>> {
+    checksums
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let (hash, path) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| anyhow!("invalid line in {}: {}", CHECKSUMS_NAME, line))?;
+            // `sha256sum` prefixes paths with `*` in binary mode, and with `./` when using `find`.
+            let path = path.trim_start().trim_start_matches('*');
+            let path = path.strip_prefix("./").unwrap_or(path);
+            Ok((path.to_owned(), hash.to_lowercase()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_checksums() {
+        let checksums = parse_checksums("ABC  ./main.js\ndef *lib/util.js\n\n").unwrap();
+        assert_eq!(checksums["main.js"], "abc");
+        assert_eq!(checksums["lib/util.js"], "def");
+        assert!(parse_checksums("abc").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn verifies_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("main.js"), "").unwrap();
+        let checksums = format!("{:x}  main.js\n", Sha256::digest(b""));
+        assert!(verify_files(dir.path(), &checksums).is_ok());
+
+        fs::write(dir.path().join("main.js"), "changed").unwrap();
+        assert!(verify_files(dir.path(), &checksums).is_err());
+        fs::write(dir.path().join("main.js"), "").unwrap();
+
+        std::os::unix::fs::symlink("main.js", dir.path().join("link.js")).unwrap();
+        assert!(verify_files(dir.path(), &checksums).is_err());
+    }
+}
@@ -0,0 +1,298 @@
+//! Locations extensions are installed from.
+
+use std::fmt;
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Result};
+use flate2::read::GzDecoder;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tempfile::TempDir;
+
+use super::extension::MANIFEST_NAME;
+
+/// Where an extension is installed from, recorded in its installation metadata for upgrades.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExtensionSource {
+    /// A local directory.
+    Path { path: PathBuf },
+    /// A git repository, at a branch, tag or commit.
+    Git {
+        url: String,
+        #[serde(rename = "ref")]
+        reference: Option<String>,
+    },
+    /// A `.tar.gz` or `.zip` archive, as a local path or an HTTP(S) URL.
+    Archive { location: String },
+}
+
+/// Extension files fetched from a source, removed once dropped.
+pub struct FetchedExtension {
+    /// Directory containing the extension manifest.
+    pub path: PathBuf,
+    // Keeps the temporary directory alive.
+    _tmp_dir: Option<TempDir>,
+}
+
+impl ExtensionSource {
+    /// Fetch the extension files, downloading archives with `client`.
+    pub async fn fetch(&self, client: &Client) -> Result<FetchedExtension> {
+        match self {
+            Self::Path { path } => Ok(FetchedExtension {
+                path: path.clone(),
+                _tmp_dir: None,
+            }),
+            Self::Git { url, reference } => {
+                let tmp_dir = TempDir::new()?;
+                let checkout = tmp_dir.path().join("extension");
+                clone_repository(url, reference.as_deref(), &checkout)?;
+                Ok(FetchedExtension {
+                    path: checkout,
+                    _tmp_dir: Some(tmp_dir),
+                })
+            }
+            Self::Archive { location } => {
+                let tmp_dir = TempDir::new()?;
+                let data = read_archive(client, location).await?;
+                if is_zip(location) {
+                    zip::ZipArchive::new(Cursor::new(data))?.extract(tmp_dir.path())?;
+                } else {
+                    tar::Archive::new(GzDecoder::new(Cursor::new(data))).unpack(tmp_dir.path())?;
+                }
+                Ok(FetchedExtension {
+                    path: manifest_dir(tmp_dir.path())?,
+                    _tmp_dir: Some(tmp_dir),
+                })
+            }
+        }
+    }
+}
+
+impl FromStr for ExtensionSource {
+    type Err = anyhow::Error;
+
+    /// Parse a source passed to `phylum extension add`.
+    ///
+    /// Archives are recognized by their extension. Git repositories are URLs which aren't
+    /// archives, or any location prefixed with `git+`, optionally followed by `#<ref>`.
+    /// Everything else is a local directory.
+    fn from_str(source: &str) -> Result<Self> {
+        if is_archive(source) {
+            let location = if is_http_url(source) {
+                source.to_owned()
+            } else {
+                let path = Path::new(source)
+                    .canonicalize()
+                    .with_context(|| format!("{}: archive not found", source))?;
+                path.to_string_lossy().into_owned()
+            };
+            return Ok(Self::Archive { location });
+        }
+
+        let git_url = source.strip_prefix("git+").or_else(|| {
+            let is_url = ["https://", "http://", "ssh://", "git://", "git@"]
+                .iter()
+                .any(|prefix| source.starts_with(prefix));
+            is_url.then(|| source)
+        });
+        if let Some(git_url) = git_url {
+            let (url, reference) = match git_url.rsplit_once('#') {
+                Some((url, reference)) => (url, Some(reference.to_owned())),
+                None => (git_url, None),
+            };
+            if let Some(reference) = &reference {
+                check_reference(reference)?;
+            }
+            return Ok(Self::Git {
+                url: url.to_owned(),
+                reference,
+            });
+        }
+
+        let path = Path::new(source);
+        let path = path
+            .canonicalize()
+            .with_context(|| format!("{}: extension directory not found", path.display()))?;
+        Ok(Self::Path { path })
+    }
+}
+
+impl fmt::Display for ExtensionSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Path { path } => write!(f, "{}", path.display()),
+            Self::Git {
+                url,
+                reference: Some(reference),
+            } => write!(f, "git+{}#{}", url, reference),
+            Self::Git { url, .. } => write!(f, "git+{}", url),
+            Self::Archive { location } => write!(f, "{}", location),
+        }
+    }
+}
+
+fn is_http_url(location: &str) -> bool {
+    location.starts_with("https://") || location.starts_with("http://")
+}
+
+fn is_zip(location: &str) -> bool {
+    location.to_lowercase().ends_with(".zip")
+}
+
+fn is_archive(location: &str) -> bool {
+    let location = location.to_lowercase();
+    [".zip", ".tar.gz", ".tgz"]
+        .iter()
+        .any(|extension| location.ends_with(extension))
+}
+
+/// Check that a git reference can't be mistaken for an option of `git`.
+fn check_reference(reference: &str) -> Result<()> {
+    if reference.is_empty() || reference.starts_with('-') {
+        return Err(anyhow!("Invalid git reference `{}`", reference));
+    }
+    Ok(())
+}
+
+/// Clone a git repository at `reference`, without its history.
+fn clone_repository(url: &str, reference: Option<&str>, dest: &Path) -> Result<()> {
+    log::debug!("Cloning {}", url);
+
+    // Commits can't be passed to `--branch`, so the whole repository is cloned to check them out.
+    let mut clone = Command::new("git");
+    clone.arg("clone").arg("--quiet");
+    if reference.is_none() {
+        clone.args(&["--depth", "1"]);
+    }
+    run_git(clone.arg("--").arg(url).arg(dest))?;
+
+    if let Some(reference) = reference {
+        check_reference(reference)?;
+        run_git(
+            Command::new("git")
+                .arg("-C")
+                .arg(dest)
+                .args(&["checkout", "--quiet", reference, "--"]),
+        )?;
+    }
+
+    fs::remove_dir_all(dest.join(".git"))?;
+
+    Ok(())
+}
+
+fn run_git(command: &mut Command) -> Result<()> {
+    let output = command
+        .output()
+        .context("Unable to run `git`, is it installed?")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Read an archive from a local path or an HTTP(S) URL.
+async fn read_archive(client: &Client, location: &str) -> Result<Vec<u8>> {
+    if is_http_url(location) {
+        log::debug!("Downloading {}", location);
+        let response = client.get(location).send().await?.error_for_status()?;
+        return Ok(response.bytes().await?.to_vec());
+    }
+
+    let mut data = Vec::new();
+    fs::File::open(location)
+        .with_context(|| format!("{}: unable to open archive", location))?
+        .read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Find the extension in an unpacked archive, either at its root or in its only directory.
+fn manifest_dir(root: &Path) -> Result<PathBuf> {
+    if root.join(MANIFEST_NAME).exists() {
+        return Ok(root.to_path_buf());
+    }
+
+    let entries = fs::read_dir(root)?.collect::<Result<Vec<_>, _>>()?;
+    match entries.as_slice() {
+        [entry] if entry.path().join(MANIFEST_NAME).exists() => Ok(entry.path()),
+        _ => Err(anyhow!("archive does not contain a {}", MANIFEST_NAME)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    #[test]
+    fn parses_sources() {
+        let source = ExtensionSource::from_str("https://example.com/ext.tar.gz").unwrap();
+        assert_eq!(
+            source,
+            ExtensionSource::Archive {
+                location: "https://example.com/ext.tar.gz".into()
+            }
+        );
+
+        assert!(ExtensionSource::from_str("missing.ZIP").is_err());
+
+        let source = ExtensionSource::from_str("https://github.com/org/ext.git#v1.0.0").unwrap();
+        assert_eq!(
+            source,
+            ExtensionSource::Git {
+                url: "https://github.com/org/ext.git".into(),
+                reference: Some("v1.0.0".into()),
+            }
+        );
+        assert_eq!(
+            source.to_string(),
+            "git+https://github.com/org/ext.git#v1.0.0"
+        );
+
+        let source = ExtensionSource::from_str("git+file:///tmp/ext").unwrap();
+        assert_eq!(
+            source,
+            ExtensionSource::Git {
+                url: "file:///tmp/ext".into(),
+                reference: None,
+            }
+        );
+
+        let source = ExtensionSource::from_str("src").unwrap();
+        assert!(matches!(source, ExtensionSource::Path { path } if path.is_absolute()));
+
+        assert!(ExtensionSource::from_str("missing-directory").is_err());
+
+        assert!(ExtensionSource::from_str("git+https://github.com/org/ext.git#--help").is_err());
+        assert!(ExtensionSource::from_str("git+https://github.com/org/ext.git#").is_err());
+    }
+
+    #[tokio::test]
+    async fn archive_downloads_honour_timeout() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/ext.tar.gz"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+            .mount(&mock_server)
+            .await;
+
+        let client = Client::builder()
+            .timeout(Duration::from_millis(100))
+            .build()
+            .unwrap();
+        let location = format!("{}/ext.tar.gz", mock_server.uri());
+        assert!(read_archive(&client, &location).await.is_err());
+    }
+}
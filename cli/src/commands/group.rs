@@ -4,6 +4,7 @@ use clap::ArgMatches;
 
 use crate::api::PhylumApi;
 use crate::commands::{CommandResult, ExitCode};
+use crate::format::output_format;
 use crate::print;
 use crate::print_user_success;
 
@@ -19,8 +20,7 @@ pub async fn handle_group(api: &mut PhylumApi, mut matches: &ArgMatches) -> Comm
 
         let response = api.get_groups_list().await;
 
-        let pretty_print = !matches.is_present("json");
-        print::print_response(&response, pretty_print, None);
+        print::print_response(&response, output_format(matches)?, None);
 
         Ok(ExitCode::Ok.into())
     }
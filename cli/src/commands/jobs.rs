@@ -1,74 +1,199 @@
-use std::io;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::str::FromStr;
 
 use ansi_term::Color::Blue;
 use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use futures::stream::{self, StreamExt};
 use phylum_types::types::common::ProjectId;
 use reqwest::StatusCode;
 use serde::Serialize;
 
 use phylum_types::types::common::JobId;
 use phylum_types::types::job::*;
-use phylum_types::types::package::*;
+use phylum_types::types::package::{PackageDescriptor, PackageStatusExtended};
 
 use crate::api::{PhylumApi, PhylumApiError};
+use crate::commands::batch::split_by_type;
+use crate::commands::packages::DEFAULT_CONCURRENCY;
 use crate::commands::parse::get_packages_from_lockfile;
-use crate::commands::{CommandResult, CommandValue};
-use crate::config::{get_current_project, Config, ProjectConfig};
+use crate::commands::{CommandResult, CommandValue, ExitCode};
+use crate::config::{get_current_project, project_file, ProjectConfig};
 use crate::filter::Filter;
-use crate::print::print_response;
+use crate::format::{output_format, Format, Output, OutputFormat};
+use crate::ignore::{Ignore, Suppression, IGNORE_FILE};
+use crate::policy::{most_severe, parse_publication_date, Policy, POLICY_FILE};
+use crate::print::{paint, print_response};
+use crate::print_user_failure;
 use crate::print_user_success;
 use crate::print_user_warning;
-use crate::summarize::Summarize;
+use crate::render::Renderable;
+use crate::report::junit::Junit;
+use crate::report::markup::Document;
+use crate::report::sarif::Sarif;
+use crate::report::ReportFormat;
+use crate::sbom::cyclonedx::Bom;
+use crate::sbom::spdx::SpdxDocument;
+use crate::sbom::Sbom;
 
 use super::project::get_project_list;
 
 fn handle_status<T>(
-    resp: Result<JobStatusResponse<T>, PhylumApiError>,
-    pretty: bool,
+    resp: &Result<JobStatusResponse<T>, PhylumApiError>,
+    format: OutputFormat,
     filter: Option<Filter>,
 ) -> Action
 where
-    T: std::fmt::Debug + Serialize + Summarize,
-    JobStatusResponse<T>: Summarize,
+    T: std::fmt::Debug + Serialize,
+    JobStatusResponse<T>: Output,
 {
     let mut action = Action::None;
 
     if let Err(Some(StatusCode::NOT_FOUND)) = resp.as_ref().map_err(|e| e.status()) {
         print_user_warning!(
             "No results found. Submit a lockfile for processing:\n\n\t{}\n",
-            Blue.paint("phylum analyze <lock_file>")
+            paint(Blue, "phylum analyze <lock_file>")
         );
     } else {
-        if let Ok(ref resp) = resp {
+        if let Ok(resp) = resp {
             if !resp.pass {
                 action = resp.action.to_owned();
             }
         }
-        print_response(&resp, pretty, filter);
+        print_response(resp, format, filter);
     }
 
     action
 }
 
+/// A job's action, along with its detailed status if it was fetched.
+///
+/// The detailed status has the issues suppressed by the ignore file removed, and is reused
+/// to evaluate the policy file.
+type StatusOutcome = (Action, Option<JobStatusResponse<PackageStatusExtended>>);
+
 /// Display user-friendly overview of a job
 pub async fn get_job_status(
     api: &mut PhylumApi,
     job_id: &JobId,
     verbose: bool,
-    pretty: bool,
+    format: OutputFormat,
     filter: Option<Filter>,
-) -> Action {
+) -> Result<StatusOutcome> {
+    let ignore = load_ignore()?;
+
     if verbose {
-        let resp = api.get_job_status_ext(job_id).await;
-        handle_status(resp, pretty, filter)
+        let mut resp = api.get_job_status_ext(job_id).await;
+        let suppression = match (&ignore, resp.as_mut()) {
+            (Some(ignore), Ok(job)) => Some(ignore.suppress(job, today())),
+            _ => None,
+        };
+        let action = handle_status(&resp, format, filter);
+        match (suppression, resp) {
+            (Some(suppression), Ok(job)) => {
+                print_suppression(&suppression, format == OutputFormat::Table);
+                Ok((suppression.action(&job), Some(job)))
+            }
+            (_, resp) => Ok((action, resp.ok())),
+        }
     } else {
         let resp = api.get_job_status(job_id).await;
-        handle_status(resp, pretty, filter)
+        let action = handle_status(&resp, format, filter);
+        match ignore {
+            // The suppressed issues can only be checked with the detailed job status.
+            Some(ignore) if !matches!(action, Action::None) => {
+                let mut job = api.get_job_status_ext(job_id).await?;
+                let suppression = ignore.suppress(&mut job, today());
+                print_suppression(&suppression, format == OutputFormat::Table);
+                Ok((suppression.action(&job), Some(job)))
+            }
+            Some(ignore) => {
+                warn_expired(&ignore.expired(today()));
+                Ok((action, None))
+            }
+            None => Ok((action, None)),
+        }
+    }
+}
+
+/// Load the ignore file of the current project, if it has one.
+fn load_ignore() -> Result<Option<Ignore>> {
+    project_file(IGNORE_FILE)
+        .map(|path| Ignore::load(&path))
+        .transpose()
+}
+
+/// Print the issues suppressed by the ignore file, and warn about its expired entries.
+///
+/// The suppressed issues are listed after the table output, other formats only mention
+/// their number to keep the output machine-readable.
+fn print_suppression(suppression: &Suppression, table: bool) {
+    warn_expired(&suppression.expired);
+
+    if suppression.is_empty() {
+        return;
+    }
+    if table {
+        println!("{}", suppression.render());
+    } else {
+        eprintln!(
+            "{} issues suppressed by {}",
+            suppression.suppressed.len(),
+            IGNORE_FILE
+        );
+    }
+}
+
+/// Warn about the expired entries of the ignore file.
+fn warn_expired(expired: &[String]) {
+    for expired in expired {
+        print_user_warning!("Expired entry in {}: {}", IGNORE_FILE, expired);
     }
 }
 
+/// Get the current date, for checking expiration dates.
+fn today() -> NaiveDate {
+    Utc::now().naive_utc().date()
+}
+
+/// Print a job report in one of the formats for other tools
+async fn print_job_report(
+    api: &mut PhylumApi,
+    job_id: &JobId,
+    format: ReportFormat,
+    lockfile: Option<&Path>,
+    filter: Option<Filter>,
+) -> Result<StatusOutcome> {
+    let mut job = api.get_job_status_ext(job_id).await?;
+    let suppression = load_ignore()?.map(|ignore| ignore.suppress(&mut job, today()));
+
+    let report = match format {
+        ReportFormat::Sarif => {
+            serde_json::to_string_pretty(&Sarif::new(&job, lockfile, filter.as_ref()))?
+        }
+        ReportFormat::CycloneDx => serde_json::to_string_pretty(&Bom::new(&Sbom::from_job(&job)))?,
+        ReportFormat::CycloneDxXml => Bom::new(&Sbom::from_job(&job)).to_xml(),
+        ReportFormat::Spdx => {
+            serde_json::to_string_pretty(&SpdxDocument::new(&Sbom::from_job(&job)))?
+        }
+        ReportFormat::Junit => Junit::new(&job, filter.as_ref()).to_xml(),
+        ReportFormat::Markdown => Document::from_job(&job, filter.as_ref()).to_markdown(),
+        ReportFormat::Html => Document::from_job(&job, filter.as_ref()).to_html(),
+    };
+    println!("{}", report);
+
+    let action = match suppression {
+        Some(suppression) => {
+            print_suppression(&suppression, false);
+            suppression.action(&job)
+        }
+        None if job.pass => Action::None,
+        None => job.action.clone(),
+    };
+    Ok((action, Some(job)))
+}
+
 /// Resolve a potential job_id, which could be a UUID string or the value
 /// 'current' which means the UUID of the current running job.
 fn resolve_job_id(job_id: &str) -> Result<JobId> {
@@ -87,12 +212,13 @@ fn resolve_job_id(job_id: &str) -> Result<JobId> {
 /// associated with projects, and get the detailed run results for a specific
 /// job run.
 pub async fn handle_history(api: &mut PhylumApi, matches: &clap::ArgMatches) -> CommandResult {
-    let pretty_print = !matches.is_present("json");
+    let format = Format::from_matches(matches, Format::Output(OutputFormat::Table))?;
     let verbose = matches.is_present("verbose");
     let mut action = Action::None;
     let display_filter = matches
         .value_of("filter")
-        .and_then(|v| Filter::from_str(v).ok());
+        .map(Filter::from_str)
+        .transpose()?;
 
     if let Some(matches) = matches.subcommand_matches("project") {
         let project_name = matches.value_of("project_name");
@@ -106,7 +232,7 @@ pub async fn handle_history(api: &mut PhylumApi, matches: &clap::ArgMatches) ->
                 );
 
                 let resp = api.get_project_details(project_name).await;
-                print_response(&resp, pretty_print, None);
+                print_response(&resp, format.output()?, None);
             } else {
                 print_user_warning!(
                     "`phylum history project <PROJECT> <JOB_ID>` is deprecated, \
@@ -116,159 +242,452 @@ pub async fn handle_history(api: &mut PhylumApi, matches: &clap::ArgMatches) ->
                 // TODO The original code had unwrap in it above. This needs to
                 // be refactored in general for better flow
                 let job_id = resolve_job_id(project_job_id.expect("No job id found"))?;
-                action = get_job_status(api, &job_id, verbose, pretty_print, display_filter).await
+                let (job_action, job) =
+                    get_job_status(api, &job_id, verbose, format.output()?, display_filter).await?;
+                action = most_severe(job_action, evaluate_policy(api, &job_id, job).await?);
             }
         } else {
             print_user_warning!(
                 "`phylum history project` is deprecated, use `phylum project` instead"
             );
 
-            get_project_list(api, pretty_print, None).await;
+            get_project_list(api, format.output()?, None).await;
         }
     } else if matches.is_present("JOB_ID") {
         let job_id = resolve_job_id(matches.value_of("JOB_ID").expect("No job id found"))?;
-        action = get_job_status(api, &job_id, verbose, pretty_print, display_filter).await;
-    } else if let Some(project) = matches.value_of("project") {
-        let resp = api.get_project_details(project).await.map(|r| r.jobs);
-        print_response(&resp, pretty_print, None);
+        let (job_action, job) = match format {
+            Format::Report(format) => {
+                print_job_report(api, &job_id, format, None, display_filter).await?
+            }
+            Format::Output(format) => {
+                get_job_status(api, &job_id, verbose, format, display_filter).await?
+            }
+        };
+        action = most_severe(job_action, evaluate_policy(api, &job_id, job).await?);
     } else {
-        let resp = api.get_status().await;
+        print_job_list(api, matches).await?;
+    }
 
-        if let Err(Some(StatusCode::NOT_FOUND)) = resp.as_ref().map_err(|e| e.status()) {
-            print_user_warning!(
-                "No results found. Submit a lockfile for processing:\n\n\t{}\n",
-                Blue.paint("phylum analyze <lock_file>")
-            );
+    Ok(CommandValue::Action(action))
+}
+
+/// Number of jobs requested per page when listing jobs.
+const JOBS_PAGE_SIZE: u32 = 50;
+
+/// Maximum number of pages requested when listing jobs.
+const MAX_JOBS_PAGES: usize = 20;
+
+/// Default number of jobs to list.
+const DEFAULT_JOBS_LIMIT: usize = 30;
+
+/// Outcome of a job.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JobOutcome {
+    Pass,
+    Fail,
+    Incomplete,
+}
+
+impl JobOutcome {
+    /// Get the outcome of a job.
+    pub fn of(job: &JobDescriptor) -> Self {
+        if job.num_incomplete > 0 {
+            JobOutcome::Incomplete
+        } else if job.pass {
+            JobOutcome::Pass
         } else {
-            if pretty_print {
-                println!("Projects and most recent runs\n",);
+            JobOutcome::Fail
+        }
+    }
+}
+
+impl FromStr for JobOutcome {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "pass" => Ok(JobOutcome::Pass),
+            "fail" => Ok(JobOutcome::Fail),
+            "incomplete" => Ok(JobOutcome::Incomplete),
+            _ => Err(anyhow!(
+                "Invalid job status `{}`, expected `pass`, `fail` or `incomplete`",
+                s
+            )),
+        }
+    }
+}
+
+/// Criteria for listing jobs.
+#[derive(Debug, Default)]
+pub struct JobFilter {
+    pub project: Option<String>,
+    pub label: Option<String>,
+    pub status: Option<JobOutcome>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl JobFilter {
+    /// Read the filter from the `project`, `label`, `status`, `since` and `until` arguments.
+    pub fn from_matches(matches: &clap::ArgMatches) -> Result<Self> {
+        Ok(Self {
+            project: matches.value_of("project").map(String::from),
+            label: matches.value_of("label").map(String::from),
+            status: matches
+                .value_of("status")
+                .map(JobOutcome::from_str)
+                .transpose()?,
+            since: matches
+                .value_of("since")
+                .map(|since| parse_date(since, false))
+                .transpose()?,
+            until: matches
+                .value_of("until")
+                .map(|until| parse_date(until, true))
+                .transpose()?,
+        })
+    }
+
+    /// Check if a job matches all criteria.
+    pub fn matches(&self, job: &JobDescriptor) -> bool {
+        if self
+            .project
+            .as_ref()
+            .map_or(false, |project| *project != job.project)
+        {
+            return false;
+        }
+
+        self.matches_ignoring_project(job)
+    }
+
+    /// Check if a job matches all criteria but the project, for jobs already known to belong
+    /// to the project.
+    fn matches_ignoring_project(&self, job: &JobDescriptor) -> bool {
+        if self
+            .label
+            .as_ref()
+            .map_or(false, |label| *label != job.label)
+        {
+            return false;
+        }
+
+        if self
+            .status
+            .map_or(false, |status| status != JobOutcome::of(job))
+        {
+            return false;
+        }
+
+        if self.since.is_some() || self.until.is_some() {
+            let date = match job_date(job) {
+                Some(date) => date,
+                None => return false,
+            };
+
+            if self.since.map_or(false, |since| date < since)
+                || self.until.map_or(false, |until| date > until)
+            {
+                return false;
             }
+        }
+
+        true
+    }
+}
+
+/// Parse a date argument, either as RFC 3339 or as `YYYY-MM-DD`.
+///
+/// Dates without a time refer to the start of the day, or its end if `end_of_day` is set.
+fn parse_date(date: &str, end_of_day: bool) -> Result<DateTime<Utc>> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(date) {
+        return Ok(date.with_timezone(&Utc));
+    }
+
+    let day = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| anyhow!("Invalid date `{}`, expected `YYYY-MM-DD` or RFC 3339", date))?;
+    let time = if end_of_day {
+        day.and_hms(23, 59, 59)
+    } else {
+        day.and_hms(0, 0, 0)
+    };
+
+    Ok(DateTime::from_utc(time, Utc))
+}
 
-            print_response(&resp, pretty_print, None);
+/// Get the submission date of a job.
+fn job_date(job: &JobDescriptor) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(&job.date)
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+/// List up to `limit` jobs matching the filter, most recent first.
+///
+/// The jobs of a project are taken from its details, other jobs are found by paging through
+/// the job endpoint until enough matching jobs are found.
+pub async fn list_jobs(
+    api: &PhylumApi,
+    filter: &JobFilter,
+    limit: usize,
+) -> Result<Vec<JobDescriptor>, PhylumApiError> {
+    if let Some(project) = &filter.project {
+        let jobs = api.get_project_details(project).await?.jobs;
+        return Ok(jobs
+            .into_iter()
+            .filter(|job| filter.matches_ignoring_project(job))
+            .take(limit)
+            .collect());
+    }
+
+    let mut jobs = Vec::new();
+    let mut seen = HashSet::new();
+    let mut cursor: Option<JobId> = None;
+
+    for _ in 0..MAX_JOBS_PAGES {
+        if jobs.len() >= limit {
+            break;
         }
+
+        let page = api.get_jobs(JOBS_PAGE_SIZE, cursor.as_ref()).await?;
+        let page_len = page.jobs.len();
+        let last_job_id = page.jobs.last().map(|job| job.job_id);
+
+        for job in page.jobs {
+            // Jobs are sorted by date, so no later page can match.
+            if filter.since.map_or(false, |since| {
+                job_date(&job).map_or(false, |date| date < since)
+            }) {
+                return Ok(jobs);
+            }
+
+            if seen.insert(job.job_id) && filter.matches(&job) {
+                jobs.push(job);
+            }
+
+            if jobs.len() >= limit {
+                return Ok(jobs);
+            }
+        }
+
+        // Stop once the server has no more jobs, or doesn't move past the cursor.
+        if page_len < JOBS_PAGE_SIZE as usize || last_job_id.is_none() || last_job_id == cursor {
+            break;
+        }
+        cursor = last_job_id;
     }
 
-    Ok(CommandValue::Action(action))
+    Ok(jobs)
+}
+
+/// Handle the `job` subcommand.
+pub async fn handle_job(api: &mut PhylumApi, matches: &clap::ArgMatches) -> CommandResult {
+    if let Some(matches) = matches.subcommand_matches("cancel") {
+        let job_id = resolve_job_id(matches.value_of("JOB_ID").unwrap())?;
+        let resp = api.cancel_job(&job_id).await;
+        print_response(&resp, output_format(matches)?, None);
+
+        if resp.is_err() {
+            return Ok(ExitCode::Generic.into());
+        }
+    } else if let Some(matches) = matches.subcommand_matches("list") {
+        print_job_list(api, matches).await?;
+    }
+
+    Ok(ExitCode::Ok.into())
+}
+
+/// Print the jobs matching the `project`, `label`, `status`, `since`, `until` and `limit`
+/// arguments.
+async fn print_job_list(api: &PhylumApi, matches: &clap::ArgMatches) -> Result<()> {
+    let format = output_format(matches)?;
+    let filter = JobFilter::from_matches(matches)?;
+    let limit = matches
+        .value_of("limit")
+        .map(|limit| limit.parse::<usize>())
+        .transpose()
+        .context("Invalid job limit")?
+        .unwrap_or(DEFAULT_JOBS_LIMIT);
+
+    let resp = list_jobs(api, &filter, limit).await;
+
+    if let Err(Some(StatusCode::NOT_FOUND)) = resp.as_ref().map_err(|e| e.status()) {
+        print_user_warning!(
+            "No results found. Submit a lockfile for processing:\n\n\t{}\n",
+            paint(Blue, "phylum analyze <lock_file>")
+        );
+    } else if format == OutputFormat::Table && matches!(&resp, Ok(jobs) if jobs.is_empty()) {
+        print_user_warning!("No jobs matching the filter found");
+    } else {
+        print_response(&resp, format, None);
+    }
+
+    Ok(())
 }
 
 /// Handles submission of packages to the system for analysis and
 /// displays summary information about the submitted package(s)
-pub async fn handle_submission(
-    api: &mut PhylumApi,
-    config: Config,
-    matches: &clap::ArgMatches,
-) -> CommandResult {
-    let mut packages = vec![];
-    let mut request_type = config.request_type; // default request type
-    let mut synch = false; // get status after submission
-    let mut verbose = false;
-    let mut pretty_print = false;
-    let mut display_filter = None;
-    let mut action = Action::None;
-    let is_user; // is a user (non-batch) request
-    let project;
-    let group;
-    let label;
-
-    if let Some(matches) = matches.subcommand_matches("analyze") {
-        (project, group) = cli_project(api, matches).await?;
-
-        // Should never get here if `LOCKFILE` was not specified
-        let lockfile = matches
-            .value_of("LOCKFILE")
-            .ok_or_else(|| anyhow!("Lockfile not found"))?;
-        let res = get_packages_from_lockfile(Path::new(lockfile))
-            .context("Unable to locate any valid package in package lockfile")?;
-
-        packages = res.0;
-        request_type = res.1;
-
-        label = matches.value_of("label");
-        verbose = matches.is_present("verbose");
-        pretty_print = !matches.is_present("json");
-        display_filter = matches
-            .value_of("filter")
-            .and_then(|v| Filter::from_str(v).ok());
-        is_user = !matches.is_present("force");
-        synch = true;
-    } else if let Some(matches) = matches.subcommand_matches("batch") {
-        (project, group) = cli_project(api, matches).await?;
-
-        let mut eof = false;
-        let mut line = String::new();
-        let mut reader: Box<dyn io::BufRead> = if let Some(file) = matches.value_of("file") {
-            // read entries from the file
-            Box::new(io::BufReader::new(std::fs::File::open(file).unwrap()))
+pub async fn handle_submission(api: &mut PhylumApi, matches: &clap::ArgMatches) -> CommandResult {
+    let (project, group) = cli_project(api, matches).await?;
+
+    // Should never get here if `LOCKFILE` was not specified
+    let lockfile = matches
+        .value_of("LOCKFILE")
+        .ok_or_else(|| anyhow!("Lockfile not found"))?;
+    let (packages, request_type) = get_packages_from_lockfile(Path::new(lockfile))
+        .context("Unable to locate any valid package in package lockfile")?;
+
+    if packages.is_empty() {
+        return Err(anyhow!("No packages found in `{}`", lockfile));
+    }
+
+    let label = matches.value_of("label");
+    let verbose = matches.is_present("verbose");
+    let format = Format::from_matches(matches, Format::Output(OutputFormat::Table))?;
+    let display_filter = matches
+        .value_of("filter")
+        .map(Filter::from_str)
+        .transpose()?;
+    let is_user = !matches.is_present("force");
+
+    // SBOMs may contain packages of several ecosystems, which are submitted as separate jobs.
+    let submissions = match request_type {
+        Some(request_type) if packages.iter().all(|p| p.package_type == request_type) => {
+            vec![(request_type, packages)]
+        }
+        _ => split_by_type(packages),
+    };
+
+    let mut job_ids = Vec::new();
+    for (package_type, packages) in &submissions {
+        log::debug!("Submitting request...");
+        let job_id = api
+            .submit_request(
+                package_type,
+                packages,
+                is_user,
+                project,
+                label.map(String::from),
+                group.clone(),
+            )
+            .await?;
+
+        log::debug!("Response => {:?}", job_id);
+        if submissions.len() > 1 {
+            print_user_success!(
+                "Job ID: {} ({} {} packages)",
+                job_id,
+                packages.len(),
+                package_type.render()
+            );
         } else {
-            // read from stdin
-            log::info!("Waiting on stdin...");
-            Box::new(io::BufReader::new(io::stdin()))
+            print_user_success!("Job ID: {}", job_id);
+        }
+        job_ids.push(job_id);
+    }
+
+    log::debug!("Requesting status...");
+    let mut action = Action::None;
+    for job_id in &job_ids {
+        let (job_action, job) = match format {
+            Format::Report(format) => {
+                let lockfile = Some(Path::new(lockfile));
+                print_job_report(api, job_id, format, lockfile, display_filter.clone()).await?
+            }
+            Format::Output(format) => {
+                get_job_status(api, job_id, verbose, format, display_filter.clone()).await?
+            }
         };
+        action = most_severe(action, job_action);
+        action = most_severe(action, evaluate_policy(api, job_id, job).await?);
+    }
 
-        // If a package type was provided on the command line, prefer that
-        //  to the global setting
-        if matches.is_present("type") {
-            request_type =
-                PackageType::from_str(matches.value_of("type").unwrap()).unwrap_or(request_type);
+    Ok(CommandValue::Action(action))
+}
+
+/// Evaluate the policy file of the current project against a job, and print the violations.
+///
+/// `job` is the detailed job status, if it was already fetched with the ignore file applied.
+/// Returns the action required by the violations which are not allowlisted, or
+/// `Action::None` if the project has no policy file.
+async fn evaluate_policy(
+    api: &mut PhylumApi,
+    job_id: &JobId,
+    job: Option<JobStatusResponse<PackageStatusExtended>>,
+) -> Result<Action> {
+    let path = match project_file(POLICY_FILE) {
+        Some(path) => path,
+        None => return Ok(Action::None),
+    };
+    log::info!("Evaluating policy file {}", path.display());
+    let policy = Policy::load(&path)?;
+    let job = match job {
+        Some(job) => job,
+        None => {
+            let mut job = api.get_job_status_ext(job_id).await?;
+            if let Some(ignore) = load_ignore()? {
+                ignore.suppress(&mut job, today());
+            }
+            job
         }
-        label = matches.value_of("label");
-        is_user = !matches.is_present("force");
-
-        while !eof {
-            match reader.read_line(&mut line) {
-                Ok(0) => eof = true,
-                Ok(_) => {
-                    line.pop();
-                    let mut pkg_info = line.split(':').collect::<Vec<&str>>();
-                    if pkg_info.len() < 2 {
-                        log::debug!("Invalid package input: `{}`", line);
-                        continue;
-                    }
-                    let pkg_version = pkg_info.pop().unwrap();
-                    let pkg_name = pkg_info.join(":");
-
-                    packages.push(PackageDescriptor {
-                        name: pkg_name.to_owned(),
-                        version: pkg_version.to_owned(),
-                        package_type: request_type.to_owned(),
-                    });
-                    line.clear();
-                }
-                Err(err) => {
-                    return Err(anyhow!(err));
+    };
+
+    let api = &*api;
+    let lookups = stream::iter(policy.age_checked_packages(&job, today()))
+        .map(|package| async move {
+            let pkg = PackageDescriptor {
+                name: package.basic_status.name.clone(),
+                version: package.basic_status.version.clone(),
+                package_type: package.package_type.clone(),
+            };
+            let resp = api.get_package_details(&pkg).await;
+            (pkg, resp)
+        })
+        .buffered(DEFAULT_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+
+    let mut published = HashMap::new();
+    for (pkg, resp) in lookups {
+        match resp {
+            Ok(details) => {
+                let date = details
+                    .published_date
+                    .as_deref()
+                    .and_then(parse_publication_date);
+                if let Some(date) = date {
+                    published.insert((pkg.name, pkg.version), date);
                 }
             }
+            Err(err) => log::debug!("Failed to look up {}@{}: {}", pkg.name, pkg.version, err),
         }
-    } else {
-        unreachable!();
     }
 
-    log::debug!("Submitting request...");
-    let job_id = api
-        .submit_request(
-            &request_type,
-            &packages,
-            is_user,
-            project,
-            label.map(String::from),
-            group.map(String::from),
-        )
-        .await?;
-
-    log::debug!("Response => {:?}", job_id);
-    print_user_success!("Job ID: {}", job_id);
-
-    if synch {
-        log::debug!("Requesting status...");
-        action = get_job_status(api, &job_id, verbose, pretty_print, display_filter).await;
+    let evaluation = policy.evaluate(&job, &published, today());
+
+    for expired in &evaluation.expired {
+        print_user_warning!("Expired policy allowlist entry: {}", expired);
     }
-    Ok(CommandValue::Action(action))
+    for violation in &evaluation.violations {
+        let message = format!(
+            "{}: {} ({})",
+            violation.package, violation.detail, violation.rule
+        );
+        match (&violation.allowed, &violation.action) {
+            (Some(reason), _) => log::info!("Allowed policy violation {}: {}", message, reason),
+            (None, Action::Break) => print_user_failure!("Policy violation {}", message),
+            (None, Action::Warn) => print_user_warning!("Policy violation {}", message),
+            (None, Action::None) => log::info!("Policy violation {}", message),
+        }
+    }
+
+    Ok(evaluation.action())
 }
 
 /// Get the current project.
 ///
 /// Assumes that the clap `matches` has a `project` and `group` arguments option.
-async fn cli_project(
+pub(crate) async fn cli_project(
     api: &mut PhylumApi,
     matches: &clap::ArgMatches,
 ) -> Result<(ProjectId, Option<String>)> {
@@ -289,3 +708,178 @@ async fn cli_project(
             )
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    use super::*;
+    use crate::test::mockito::*;
+
+    fn job(project: &str, label: &str, date: &str) -> JobDescriptor {
+        job_with_status(project, label, date, true, 0)
+    }
+
+    fn job_with_status(
+        project: &str,
+        label: &str,
+        date: &str,
+        pass: bool,
+        num_incomplete: u32,
+    ) -> JobDescriptor {
+        let job = format!(
+            r#"{{
+                "date": "{}",
+                "job_id": "e0ea0e13-f5f1-4142-85b8-7aa22bfb984f",
+                "label": "{}",
+                "num_dependencies": 14,
+                "packages": [],
+                "msg": "Project met threshold requirements",
+                "pass": {},
+                "action": "warn",
+                "project": "{}",
+                "score": 1.0,
+                "ecosystem": "npm",
+                "num_incomplete": {}
+            }}"#,
+            date, label, pass, project, num_incomplete
+        );
+        serde_json::from_str(&job).unwrap()
+    }
+
+    #[test]
+    fn parse_date_formats() {
+        let start = parse_date("2021-05-17", false).unwrap();
+        assert_eq!(start.to_rfc3339(), "2021-05-17T00:00:00+00:00");
+
+        let end = parse_date("2021-05-17", true).unwrap();
+        assert_eq!(end.to_rfc3339(), "2021-05-17T23:59:59+00:00");
+
+        let exact = parse_date("2021-05-17T12:30:00+02:00", false).unwrap();
+        assert_eq!(exact.to_rfc3339(), "2021-05-17T10:30:00+00:00");
+
+        assert!(parse_date("17/05/2021", false).is_err());
+    }
+
+    #[test]
+    fn job_filter_matches() {
+        let job = job("test-project", "main", "Mon, 17 May 2021 17:39:34 GMT");
+
+        assert!(JobFilter::default().matches(&job));
+
+        let filter = JobFilter {
+            project: Some("test-project".into()),
+            label: Some("main".into()),
+            status: Some(JobOutcome::Pass),
+            since: Some(parse_date("2021-05-17", false).unwrap()),
+            until: Some(parse_date("2021-05-17", true).unwrap()),
+        };
+        assert!(filter.matches(&job));
+
+        let filter = JobFilter {
+            label: Some("release".into()),
+            ..JobFilter::default()
+        };
+        assert!(!filter.matches(&job));
+
+        let filter = JobFilter {
+            since: Some(parse_date("2021-05-18", false).unwrap()),
+            ..JobFilter::default()
+        };
+        assert!(!filter.matches(&job));
+    }
+
+    #[test]
+    fn job_filter_status() {
+        let date = "Mon, 17 May 2021 17:39:34 GMT";
+        let passed = job_with_status("test-project", "main", date, true, 0);
+        let failed = job_with_status("test-project", "main", date, false, 0);
+        let incomplete = job_with_status("test-project", "main", date, false, 3);
+
+        let filter = |status| JobFilter {
+            status: Some(JobOutcome::from_str(status).unwrap()),
+            ..JobFilter::default()
+        };
+
+        assert!(filter("pass").matches(&passed));
+        assert!(!filter("pass").matches(&failed));
+        assert!(filter("fail").matches(&failed));
+        assert!(!filter("fail").matches(&incomplete));
+        assert!(filter("incomplete").matches(&incomplete));
+        assert!(JobOutcome::from_str("passed").is_err());
+    }
+
+    #[tokio::test]
+    async fn list_jobs_stops_when_cursor_is_ignored() {
+        let page = (0..50)
+            .map(|_| job("test-project", "main", "Mon, 17 May 2021 17:39:34 GMT"))
+            .collect::<Vec<_>>();
+        let body = serde_json::json!({ "count": 50, "jobs": page, "total_jobs": 500 });
+
+        // The same page, repeating a single job, is returned whatever the cursor.
+        let mock_server = build_mock_server().await;
+        Mock::given(method("GET"))
+            .and(path("api/v0/job/"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .expect(4)
+            .mount(&mock_server)
+            .await;
+        let api = build_phylum_api(&mock_server).await.unwrap();
+
+        let filter = JobFilter {
+            label: Some("release".into()),
+            ..JobFilter::default()
+        };
+        assert!(list_jobs(&api, &filter, 30).await.unwrap().is_empty());
+
+        let jobs = list_jobs(&api, &JobFilter::default(), 30).await.unwrap();
+        assert_eq!(jobs.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn list_project_jobs_from_details() {
+        let date = "Mon, 17 May 2021 17:39:34 GMT";
+        let body = serde_json::json!({
+            "name": "test-project",
+            "id": "59482a54-423b-448d-8325-f171c9dc336b",
+            "ecosystem": "npm",
+            "thresholds": {
+                "author": 0.0,
+                "engineering": 0.0,
+                "license": 0.0,
+                "malicious": 0.0,
+                "total": 0.0,
+                "vulnerability": 0.0
+            },
+            "jobs": [
+                job("test-project", "main", date),
+                job("test-project", "release", date),
+            ],
+        });
+
+        let mock_server = build_mock_server().await;
+        Mock::given(method("GET"))
+            .and(path("api/v0/job/projects/name/test-project"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("api/v0/job/"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(0)
+            .mount(&mock_server)
+            .await;
+        let api = build_phylum_api(&mock_server).await.unwrap();
+
+        let filter = JobFilter {
+            project: Some("test-project".into()),
+            label: Some("release".into()),
+            ..JobFilter::default()
+        };
+        let jobs = list_jobs(&api, &filter, 30).await.unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].label, "release");
+    }
+}
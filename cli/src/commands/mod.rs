@@ -3,6 +3,7 @@ use std::process;
 use phylum_types::types::job::Action;
 
 pub mod auth;
+pub mod batch;
 #[cfg(feature = "extensions")]
 pub mod extensions;
 pub mod group;
@@ -1,36 +1,64 @@
+use std::convert::TryFrom;
+use std::fs::read_to_string;
+use std::path::Path;
 use std::str::FromStr;
 
 use ansi_term::Color::Blue;
-use anyhow::anyhow;
+use anyhow::{anyhow, Context, Result};
 use clap::ArgMatches;
+use futures::stream::{self, StreamExt};
 use phylum_types::types::package::*;
 use reqwest::StatusCode;
 
 use crate::api::PhylumApi;
+use crate::commands::batch::parse_package_list;
 use crate::commands::{CommandResult, ExitCode};
-use crate::print::print_response;
+use crate::filter::Filter;
+use crate::format::{Format, OutputFormat};
+use crate::print::{paint, print_response};
 use crate::print_user_warning;
+use crate::purl::{self, Purl};
+use crate::report::markup::Document;
+use crate::report::ReportFormat;
+use crate::types::{PackageColumn, PackageList};
 
-fn parse_package(options: &ArgMatches, request_type: &PackageType) -> Option<PackageDescriptor> {
-    if !(options.is_present("name") && options.is_present("version")) {
-        return None;
-    }
+/// Default number of concurrent lookups for `phylum package --from-file`.
+pub(crate) const DEFAULT_CONCURRENCY: usize = 8;
+
+/// Get the package type, preferring the command line over the global setting.
+fn package_type(options: &ArgMatches, request_type: &PackageType) -> PackageType {
+    options
+        .value_of("package-type")
+        .and_then(|package_type| PackageType::from_str(package_type).ok())
+        .unwrap_or_else(|| request_type.to_owned())
+}
 
-    let name = options.value_of("name").unwrap().to_string(); // required option
-    let version = options.value_of("version").unwrap().to_string();
-    let mut package_type = request_type.to_owned();
+/// Get the package from the `name` and `version` arguments.
+///
+/// The name may also be a package URL, in which case the version argument is optional.
+fn parse_package(options: &ArgMatches, request_type: &PackageType) -> Result<PackageDescriptor> {
+    let name = options.value_of("name").unwrap(); // required option
+    let version = options.value_of("version");
 
-    // If a package type was provided on the command line, prefer that
-    //  to the global setting
-    if options.is_present("package-type") {
-        package_type = PackageType::from_str(options.value_of("package-type").unwrap())
-            .unwrap_or(package_type);
+    if purl::is_purl(name) {
+        let mut purl = Purl::from_str(name)?;
+        if let Some(version) = version {
+            if purl.version.is_some() {
+                return Err(anyhow!(
+                    "Package version specified in both package URL and arguments"
+                ));
+            }
+            purl.version = Some(version.to_owned());
+        }
+        return PackageDescriptor::try_from(purl);
     }
 
-    Some(PackageDescriptor {
-        name,
-        version,
-        package_type,
+    let version = version.ok_or_else(|| anyhow!("Missing package version"))?;
+
+    Ok(PackageDescriptor {
+        name: name.to_owned(),
+        version: version.to_owned(),
+        package_type: package_type(options, request_type),
     })
 }
 
@@ -40,22 +68,139 @@ pub async fn handle_get_package(
     req_type: &PackageType,
     matches: &clap::ArgMatches,
 ) -> CommandResult {
-    let pretty_print = !matches.is_present("json");
-    let pkg = parse_package(matches, req_type);
-    if pkg.is_none() {
-        return Err(anyhow!("Could not find or parse package information"));
+    if matches.is_present("from-file") {
+        return handle_get_packages(api, req_type, matches).await;
     }
-    let resp = api.get_package_details(&pkg.unwrap()).await;
+
+    let format = package_format(matches)?;
+    let filter = display_filter(matches)?;
+    let pkg =
+        parse_package(matches, req_type).context("Could not find or parse package information")?;
+    let resp = api.get_package_details(&pkg).await;
     log::debug!("==> {:?}", resp);
 
     if let Err(Some(StatusCode::NOT_FOUND)) = resp.as_ref().map_err(|e| e.status()) {
         print_user_warning!(
             "No matching packages found. Submit a lockfile for processing:\n\n\t{}\n",
-            Blue.paint("phylum analyze <lock_file>")
+            paint(Blue, "phylum analyze <lock_file>")
         );
         Ok(ExitCode::PackageNotFound.into())
     } else {
-        print_response(&resp, pretty_print, None);
+        match (format, &resp) {
+            (Format::Report(format), Ok(package)) => {
+                print_report(format, Document::from_package(package, filter.as_ref()))
+            }
+            (format, _) => print_response(&resp, format.output()?, filter),
+        }
+        Ok(ExitCode::Ok.into())
+    }
+}
+
+/// Get the format selected for the package details.
+fn package_format(matches: &ArgMatches) -> Result<Format> {
+    let format = Format::from_matches(matches, Format::Output(OutputFormat::Table))?;
+    match format {
+        Format::Report(ReportFormat::Markdown | ReportFormat::Html) | Format::Output(_) => {
+            Ok(format)
+        }
+        Format::Report(report) => Err(anyhow!(
+            "The `{}` format is not supported by this command",
+            report
+        )),
+    }
+}
+
+/// Get the filter for the issues displayed.
+fn display_filter(matches: &ArgMatches) -> Result<Option<Filter>> {
+    matches.value_of("filter").map(Filter::from_str).transpose()
+}
+
+/// Print a Markdown or HTML report.
+fn print_report(format: ReportFormat, document: Document) {
+    match format {
+        ReportFormat::Html => print!("{}", document.to_html()),
+        _ => print!("{}", document.to_markdown()),
+    }
+}
+
+/// Look up the details of every package in a file.
+async fn handle_get_packages(
+    api: &PhylumApi,
+    req_type: &PackageType,
+    matches: &clap::ArgMatches,
+) -> CommandResult {
+    let format = package_format(matches)?;
+    let filter = display_filter(matches)?;
+    let concurrency = match matches.value_of("concurrency") {
+        Some(concurrency) => concurrency
+            .parse::<usize>()
+            .ok()
+            .filter(|concurrency| *concurrency > 0)
+            .ok_or_else(|| anyhow!("Invalid concurrency: {}", concurrency))?,
+        None => DEFAULT_CONCURRENCY,
+    };
+    let columns = matches
+        .value_of("columns")
+        .map(PackageColumn::parse_list)
+        .transpose()?;
+
+    // Should never get here if `from-file` was not specified.
+    let path = matches.value_of("from-file").unwrap();
+    let data = read_to_string(Path::new(path))
+        .with_context(|| format!("Unable to read package list `{}`", path))?;
+    let list = parse_package_list(&data, &package_type(matches, req_type));
+    for invalid in &list.invalid {
+        print_user_warning!("Skipping invalid package on {}", invalid);
+    }
+    let packages = list.packages;
+
+    log::debug!("Looking up {} packages", packages.len());
+
+    let responses = stream::iter(packages)
+        .map(|pkg| async move {
+            let resp = api.get_package_details(&pkg).await;
+            (pkg, resp)
+        })
+        .buffered(concurrency)
+        .collect::<Vec<_>>()
+        .await;
+
+    let mut found = Vec::new();
+    let mut missing = 0;
+    for (pkg, resp) in responses {
+        match resp {
+            Ok(package) => found.push(package),
+            Err(err) if err.status() == Some(StatusCode::NOT_FOUND) => {
+                print_user_warning!("No matching package found: {}@{}", pkg.name, pkg.version);
+                missing += 1;
+            }
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("Failed to look up {}@{}", pkg.name, pkg.version));
+            }
+        }
+    }
+
+    match format {
+        Format::Report(format) => {
+            let mut document = Document::default();
+            for package in &found {
+                document.append(Document::from_package(package, filter.as_ref()));
+            }
+            print_report(format, document);
+        }
+        Format::Output(format) => {
+            let mut list = PackageList::new(found);
+            if let Some(columns) = columns {
+                list.columns = columns;
+            }
+            print_response(&Ok(list), format, filter)
+        }
+    }
+
+    if missing > 0 {
+        Ok(ExitCode::PackageNotFound.into())
+    } else {
         Ok(ExitCode::Ok.into())
     }
 }
@@ -6,11 +6,20 @@ use std::path::Path;
 
 use anyhow::{anyhow, Result};
 use phylum_types::types::package::{PackageDescriptor, PackageType};
+use serde::Serialize;
 
 use super::{CommandResult, ExitCode};
+use crate::format::{Format, Output, OutputFormat};
 use crate::lockfiles::{
-    CSProj, GemLock, GradleLock, PackageLock, Parse, PipFile, Poetry, Pom, PyRequirements, YarnLock,
+    CSProj, CycloneDx, GemLock, GradleLock, PackageLock, Parse, PipFile, Poetry, Pom,
+    PyRequirements, Spdx, YarnLock,
 };
+use crate::purl::Purl;
+use crate::render::Renderable;
+use crate::report::ReportFormat;
+use crate::sbom::cyclonedx::Bom;
+use crate::sbom::spdx::SpdxDocument;
+use crate::sbom::Sbom;
 
 const LOCKFILE_PARSERS: &[(&str, &dyn Parse)] = &[
     ("yarn", &YarnLock),
@@ -22,8 +31,33 @@ const LOCKFILE_PARSERS: &[(&str, &dyn Parse)] = &[
     ("mvn", &Pom),
     ("gradle", &GradleLock),
     ("nuget", &CSProj),
+    ("cyclonedx", &CycloneDx),
+    ("spdx", &Spdx),
 ];
 
+/// A parsed package, along with its package URL.
+#[derive(Serialize)]
+struct ParsedPackage<'a> {
+    #[serde(flatten)]
+    package: &'a PackageDescriptor,
+    purl: String,
+}
+
+impl Renderable for ParsedPackage<'_> {
+    fn render(&self) -> String {
+        format!("{}{}", self.package.render(), self.purl)
+    }
+}
+
+impl<'a> From<&'a PackageDescriptor> for ParsedPackage<'a> {
+    fn from(package: &'a PackageDescriptor) -> Self {
+        Self {
+            package,
+            purl: Purl::from(package).to_string(),
+        }
+    }
+}
+
 pub fn lockfile_types() -> Vec<&'static str> {
     LOCKFILE_PARSERS
         .iter()
@@ -36,39 +70,65 @@ pub fn handle_parse(matches: &clap::ArgMatches) -> CommandResult {
     let lockfile_type = matches.value_of("lockfile-type").unwrap_or("auto");
     // LOCKFILE is a required parameter, so .unwrap() should be safe.
     let lockfile = matches.value_of("LOCKFILE").unwrap();
+    let data = read_to_string(lockfile)?;
 
-    let pkgs = if lockfile_type == "auto" {
-        let (pkgs, _) = try_get_packages(Path::new(lockfile))?;
-        pkgs
+    let (parser, pkgs) = if lockfile_type == "auto" {
+        detect_parser(&data)?
     } else {
-        let parser = LOCKFILE_PARSERS
-            .iter()
-            .filter_map(|(name, parser)| (*name == lockfile_type).then(|| parser))
-            .next()
-            .unwrap();
-
-        let data = read_to_string(lockfile)?;
-        parser.parse(&data)?
+        // The lockfile type is one of `lockfile_types()`, so .unwrap() should be safe.
+        let parser = lockfile_parser(lockfile_type).unwrap();
+        (parser, parser.parse(&data)?)
+    };
+
+    let format = match Format::from_matches(matches, Format::Output(OutputFormat::Json))? {
+        Format::Output(format) => {
+            let pkgs = pkgs.iter().map(ParsedPackage::from).collect::<Vec<_>>();
+            pkgs.write_output(format, None)?;
+            return Ok(ExitCode::Ok.into());
+        }
+        Format::Report(format) => format,
     };
 
-    serde_json::to_writer_pretty(&mut io::stdout(), &pkgs)?;
+    let dependencies = parser.parse_dependencies(&data)?;
+    let name = Path::new(lockfile)
+        .file_name()
+        .map_or_else(|| lockfile.into(), |name| name.to_string_lossy());
+    let sbom = Sbom::from_packages(&name, &pkgs, &dependencies);
+
+    match format {
+        ReportFormat::CycloneDx => {
+            serde_json::to_writer_pretty(&mut io::stdout(), &Bom::new(&sbom))?
+        }
+        ReportFormat::CycloneDxXml => print!("{}", Bom::new(&sbom).to_xml()),
+        ReportFormat::Spdx => {
+            serde_json::to_writer_pretty(&mut io::stdout(), &SpdxDocument::new(&sbom))?
+        }
+        _ => {
+            return Err(anyhow!(
+                "The `{}` format is not supported by this command",
+                format
+            ))
+        }
+    }
 
     Ok(ExitCode::Ok.into())
 }
-/// Attempt to get packages from an unknown lockfile type
-pub fn try_get_packages(path: &Path) -> Result<(Vec<PackageDescriptor>, PackageType)> {
-    log::warn!(
-        "Attempting to obtain packages from unrecognized lockfile type: {}",
-        path.to_string_lossy()
-    );
 
-    let data = read_to_string(path)?;
+/// Find the parser for a lockfile type, by its name in `lockfile_types()`
+pub fn lockfile_parser(lockfile_type: &str) -> Option<&'static dyn Parse> {
+    LOCKFILE_PARSERS
+        .iter()
+        .find(|(name, _)| *name == lockfile_type)
+        .map(|(_, parser)| *parser)
+}
 
+/// Find the parser for a lockfile of unknown type, along with its packages
+pub fn detect_parser(data: &str) -> Result<(&'static dyn Parse, Vec<PackageDescriptor>)> {
     for (name, parser) in LOCKFILE_PARSERS.iter() {
-        if let Ok(pkgs) = parser.parse(data.as_str()) {
+        if let Ok(pkgs) = parser.parse(data) {
             if !pkgs.is_empty() {
                 log::debug!("File detected as type: {}", name);
-                return Ok((pkgs, parser.package_type()));
+                return Ok((*parser, pkgs));
             }
         }
     }
@@ -76,31 +136,37 @@ pub fn try_get_packages(path: &Path) -> Result<(Vec<PackageDescriptor>, PackageT
     Err(anyhow!("Failed to identify lockfile type"))
 }
 
+/// Attempt to get packages from an unknown lockfile type
+pub fn try_get_packages(path: &Path) -> Result<(Vec<PackageDescriptor>, Option<PackageType>)> {
+    log::warn!(
+        "Attempting to obtain packages from unrecognized lockfile type: {}",
+        path.to_string_lossy()
+    );
+
+    let data = read_to_string(path)?;
+    let (parser, pkgs) = detect_parser(&data)?;
+
+    Ok((pkgs, parser.package_type()))
+}
+
 /// Determine the lockfile type based on its name and parse
 /// accordingly to obtain the packages from it
-pub fn get_packages_from_lockfile(path: &Path) -> Result<(Vec<PackageDescriptor>, PackageType)> {
+///
+/// The package type is unknown for files which can list packages of several ecosystems.
+pub fn get_packages_from_lockfile(
+    path: &Path,
+) -> Result<(Vec<PackageDescriptor>, Option<PackageType>)> {
     let file = path
         .file_name()
         .and_then(|file| file.to_str())
         .ok_or_else(|| anyhow!("Lockfile path has no file name"))?;
-    let ext = path.extension().and_then(|ext| ext.to_str());
 
-    let pattern = match ext {
-        Some("csproj") => ".csproj",
-        _ => file,
-    };
-
-    let res = match pattern {
-        "Gemfile.lock" => parse(GemLock, path)?,
-        "package-lock.json" => parse(PackageLock, path)?,
-        "yarn.lock" => parse(YarnLock, path)?,
-        "requirements.txt" => parse(PyRequirements, path)?,
-        "Pipfile" | "Pipfile.lock" => parse(PipFile, path)?,
-        "poetry.lock" => parse(Poetry, path)?,
-        "effective-pom.xml" => parse(Pom, path)?,
-        "gradle.lockfile" => parse(GradleLock, path)?,
-        ".csproj" => parse(CSProj, path)?,
-        _ => try_get_packages(path)?,
+    let res = match parser_for_path(path) {
+        Some(parser) => {
+            let pkgs = parser.parse(&read_to_string(path)?)?;
+            (pkgs, parser.package_type())
+        }
+        None => try_get_packages(path)?,
     };
 
     log::debug!("Read {} packages from file `{}`", res.0.len(), file);
@@ -108,13 +174,33 @@ pub fn get_packages_from_lockfile(path: &Path) -> Result<(Vec<PackageDescriptor>
     Ok(res)
 }
 
-/// Get all packages for a specific lockfile type.
-fn parse<P: Parse + Sized>(
-    parser: P,
-    path: &Path,
-) -> Result<(Vec<PackageDescriptor>, PackageType)> {
-    let pkg_type = parser.package_type();
-    parser.parse_file(path).map(|pkgs| (pkgs, pkg_type))
+/// Find the parser for a lockfile based on its name
+pub fn parser_for_path(path: &Path) -> Option<&'static dyn Parse> {
+    let file = path.file_name().and_then(|file| file.to_str())?;
+    let ext = path.extension().and_then(|ext| ext.to_str());
+
+    let pattern = match ext {
+        Some("csproj") => ".csproj",
+        Some("spdx") => ".spdx",
+        _ if file.ends_with(".spdx.json") => ".spdx",
+        _ if file.ends_with(".cdx.json") || file.ends_with(".cdx.xml") => ".cdx",
+        _ => file,
+    };
+
+    match pattern {
+        "Gemfile.lock" => Some(&GemLock),
+        "package-lock.json" => Some(&PackageLock),
+        "yarn.lock" => Some(&YarnLock),
+        "requirements.txt" => Some(&PyRequirements),
+        "Pipfile" | "Pipfile.lock" => Some(&PipFile),
+        "poetry.lock" => Some(&Poetry),
+        "effective-pom.xml" => Some(&Pom),
+        "gradle.lockfile" => Some(&GradleLock),
+        ".csproj" => Some(&CSProj),
+        "bom.json" | "bom.xml" | ".cdx" => Some(&CycloneDx),
+        ".spdx" => Some(&Spdx),
+        _ => None,
+    }
 }
 
 #[cfg(test)]
@@ -139,7 +225,25 @@ mod tests {
 
         for (file, expected_type) in &test_cases {
             let (_, pkg_type) = try_get_packages(Path::new(file)).unwrap();
-            assert_eq!(pkg_type, *expected_type, "{}", file);
+            assert_eq!(pkg_type.as_ref(), Some(expected_type), "{}", file);
+        }
+    }
+
+    #[test]
+    fn it_can_identify_sboms() {
+        let test_cases = [
+            "tests/fixtures/vendor.cdx.json",
+            "tests/fixtures/vendor.cdx.xml",
+            "tests/fixtures/vendor.spdx.json",
+            "tests/fixtures/vendor.spdx",
+        ];
+
+        for file in &test_cases {
+            let (from_name, pkg_type) = get_packages_from_lockfile(Path::new(file)).unwrap();
+            let (detected, _) = try_get_packages(Path::new(file)).unwrap();
+            assert!(!from_name.is_empty(), "{}", file);
+            assert_eq!(pkg_type, None, "{}", file);
+            assert_eq!(from_name, detected, "{}", file);
         }
     }
 }
@@ -7,32 +7,33 @@ use chrono::Local;
 use super::{CommandResult, ExitCode};
 use crate::api::PhylumApi;
 use crate::config::{get_current_project, save_config, ProjectConfig, PROJ_CONF_FILE};
+use crate::format::{output_format, OutputFormat};
 use crate::print::*;
 use crate::print_user_failure;
 use crate::print_user_success;
 use crate::prompt::prompt_threshold;
 
 /// List the projects in this account.
-pub async fn get_project_list(api: &mut PhylumApi, pretty_print: bool, group: Option<&str>) {
+pub async fn get_project_list(api: &mut PhylumApi, format: OutputFormat, group: Option<&str>) {
     let resp = api.get_projects(group).await;
 
-    // Print table header when we're not outputting in JSON format.
-    if pretty_print {
-        let proj_title = format!("{}", Blue.paint("Project Name"));
-        let id_title = format!("{}", Blue.paint("Project ID"));
-        println!("{:<38}{}", proj_title, id_title);
+    // Print table header when we're outputting a table.
+    if format == OutputFormat::Table {
+        let proj_title = paint(Blue, format!("{:<29}", "Project Name"));
+        let id_title = paint(Blue, "Project ID");
+        println!("{}{}", proj_title, id_title);
     }
 
-    print_response(&resp, pretty_print, None);
-    println!();
+    print_response(&resp, format, None);
+    if format == OutputFormat::Table {
+        println!();
+    }
 }
 
 /// Handle the project subcommand. Provides facilities for creating a new project,
 /// linking a current repository to an existing project, listing projects and
 /// setting project thresholds for risk domains.
 pub async fn handle_project(api: &mut PhylumApi, matches: &clap::ArgMatches) -> CommandResult {
-    let pretty_print = !matches.is_present("json");
-
     if let Some(matches) = matches.subcommand_matches("create") {
         let name = matches.value_of("name").unwrap();
         let group = matches.value_of("group");
@@ -55,8 +56,7 @@ pub async fn handle_project(api: &mut PhylumApi, matches: &clap::ArgMatches) ->
         print_user_success!("Successfully created new project, {}", name);
     } else if let Some(matches) = matches.subcommand_matches("list") {
         let group = matches.value_of("group");
-        let pretty_print = pretty_print && !matches.is_present("json");
-        get_project_list(api, pretty_print, group).await;
+        get_project_list(api, output_format(matches)?, group).await;
     } else if let Some(matches) = matches.subcommand_matches("link") {
         let project_name = matches.value_of("name").unwrap();
         let group_name = matches.value_of("group");
@@ -78,7 +78,7 @@ pub async fn handle_project(api: &mut PhylumApi, matches: &clap::ArgMatches) ->
 
         print_user_success!(
             "Linked the current working directory to the project {}.",
-            format!("{}", White.paint(proj_conf.name))
+            format!("{}", paint(White, proj_conf.name))
         );
     } else if let Some(matches) = matches.subcommand_matches("set-thresholds") {
         let mut project_name = matches.value_of("name").unwrap_or("current");
@@ -110,21 +110,21 @@ pub async fn handle_project(api: &mut PhylumApi, matches: &clap::ArgMatches) ->
         println!();
         println!(
             "    * {}: print a message to standard error",
-            format_args!("{}", White.paint("Print a warning"))
+            format_args!("{}", paint(White, "Print a warning"))
         );
         println!(
             "    * {}: If we are in CI/CD break the build and return a non-zero exit code",
-            format_args!("{}", White.paint("Break the build"))
+            format_args!("{}", paint(White, "Break the build"))
         );
         println!(
             "    * {}: Ignore the failure and continue",
-            format_args!("{}", White.paint("Nothing, fail silently"))
+            format_args!("{}", paint(White, "Nothing, fail silently"))
         );
         println!();
 
         println!(
             "Specify the thresholds and actions for {}. Accepted values are 0-100 or 'Disabled'.",
-            format_args!("{}", White.paint(project_name))
+            format_args!("{}", paint(White, project_name))
         );
         println!();
 
@@ -172,20 +172,20 @@ pub async fn handle_project(api: &mut PhylumApi, matches: &clap::ArgMatches) ->
             Ok(_) => {
                 print_user_success!(
                     "Set all thresholds for the {} project",
-                    White.paint(project_name)
+                    paint(White, project_name)
                 );
             }
             _ => {
                 print_user_failure!(
                     "Failed to set thresholds for the {} project",
-                    White.paint(project_name)
+                    paint(White, project_name)
                 );
                 return Ok(ExitCode::SetThresholdsFailure.into());
             }
         }
     } else {
         let group = matches.value_of("group");
-        get_project_list(api, pretty_print, group).await;
+        get_project_list(api, output_format(matches)?, group).await;
     }
 
     Ok(ExitCode::Ok.into())
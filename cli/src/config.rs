@@ -37,6 +37,8 @@ pub struct Config {
     pub packages: Option<Packages>,
     pub last_update: Option<usize>,
     pub ignore_certs: Option<bool>,
+    /// Minisign public keys trusted to sign extensions.
+    pub extension_keys: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -60,6 +62,7 @@ impl Default for Config {
             packages: None,
             last_update: None,
             ignore_certs: None,
+            extension_keys: None,
         }
     }
 }
@@ -150,6 +153,17 @@ pub fn find_project_conf(starting_directory: &Path) -> Option<PathBuf> {
     }
 }
 
+/// Find a file of the current project by name.
+///
+/// Project files are looked up next to the `.phylum_project` file, or in the current
+/// directory outside of projects.
+pub fn project_file(name: &str) -> Option<PathBuf> {
+    let dir = find_project_conf(Path::new("."))
+        .and_then(|conf| conf.parent().map(Path::to_path_buf))
+        .unwrap_or_else(|| PathBuf::from("."));
+    Some(dir.join(name)).filter(|path| path.is_file())
+}
+
 pub fn get_current_project() -> Option<ProjectConfig> {
     find_project_conf(Path::new(".")).and_then(|s| {
         log::info!(
@@ -251,6 +265,7 @@ mod tests {
             packages: Some(packages),
             last_update: None,
             ignore_certs: None,
+            extension_keys: None,
         };
         let temp_dir = temp_dir();
         let test_config_file = temp_dir.as_path().join("test_config");
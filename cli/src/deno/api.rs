@@ -0,0 +1,205 @@
+//! Phylum API ops for extensions.
+//!
+//! These ops back the `phylum:api` module, which extensions import to talk to the Phylum API.
+
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context};
+use deno_core::error::AnyError;
+use deno_core::{op, OpDecl, OpState};
+use phylum_types::types::common::{JobId, ProjectId};
+use phylum_types::types::group::ListUserGroupsResponse;
+use phylum_types::types::job::JobStatusResponse;
+use phylum_types::types::package::{
+    Package, PackageDescriptor, PackageStatusExtended, PackageType,
+};
+use phylum_types::types::project::ProjectSummaryResponse;
+use tokio::sync::{Mutex, OnceCell};
+
+use crate::api::PhylumApi;
+use crate::auth::UserInfo;
+use crate::config::{get_current_project, save_config, Config, ProjectConfig};
+use crate::deno::permissions::Permissions;
+
+/// Phylum API client of an extension.
+///
+/// The client is only authenticated once an extension uses the API, so extensions which don't
+/// need it never trigger a login.
+pub struct ApiState {
+    config: Mutex<Config>,
+    config_path: PathBuf,
+    timeout: Option<u64>,
+    ignore_certs: bool,
+    api: OnceCell<PhylumApi>,
+}
+
+impl ApiState {
+    pub fn new(
+        config: Config,
+        config_path: PathBuf,
+        timeout: Option<u64>,
+        ignore_certs: bool,
+    ) -> Self {
+        Self {
+            config: Mutex::new(config),
+            config_path,
+            timeout,
+            ignore_certs,
+            api: OnceCell::new(),
+        }
+    }
+
+    /// Request timeout in seconds, from `--timeout`.
+    pub fn timeout(&self) -> Option<u64> {
+        self.timeout
+    }
+
+    /// Whether TLS certificates are ignored, from `--no-check-certificate`.
+    pub fn ignore_certs(&self) -> bool {
+        self.ignore_certs
+    }
+
+    /// Get the API client, logging in on first use.
+    pub async fn api(&self) -> anyhow::Result<&PhylumApi> {
+        self.api
+            .get_or_try_init(|| async {
+                let mut config = self.config.lock().await;
+                let config = &mut *config;
+                let api = PhylumApi::new(
+                    &mut config.auth_info,
+                    &config.connection.uri,
+                    self.timeout,
+                    self.ignore_certs,
+                )
+                .await
+                .context("Error creating client")?;
+
+                // Logging in may have updated the auth info.
+                save_config(&self.config_path, config).with_context(|| {
+                    format!(
+                        "Failed to save configuration to '{}'",
+                        self.config_path.to_string_lossy()
+                    )
+                })?;
+
+                Ok(api)
+            })
+            .await
+    }
+}
+
+/// Get the API state of the runtime, if the extension is allowed to use the API.
+fn api_state(op_state: &Rc<RefCell<OpState>>) -> anyhow::Result<Rc<ApiState>> {
+    let op_state = op_state.borrow();
+    op_state.borrow::<Permissions>().check_api()?;
+    Ok(op_state.borrow::<Rc<ApiState>>().clone())
+}
+
+/// Submit a list of packages for analysis, returning the job ID.
+///
+/// Without a project name, the project of the current directory's `.phylum_project` is used.
+#[op]
+async fn op_analyze(
+    op_state: Rc<RefCell<OpState>>,
+    package_type: String,
+    packages: Vec<PackageDescriptor>,
+    project: Option<String>,
+    group: Option<String>,
+    label: Option<String>,
+) -> Result<JobId, AnyError> {
+    let state = api_state(&op_state)?;
+    let api = state.api().await?;
+
+    let package_type = PackageType::from_str(&package_type)
+        .map_err(|_| anyhow!("Invalid package type: {}", package_type))?;
+
+    let (project, group): (ProjectId, Option<String>) = match project {
+        Some(project) => (api.get_project_id(&project, group.as_deref()).await?, group),
+        None => get_current_project()
+            .map(|p: ProjectConfig| (p.id, p.group_name))
+            .ok_or_else(|| {
+                anyhow!("Failed to find a valid project configuration, specify a project name")
+            })?,
+    };
+
+    let job_id = api
+        .submit_request(&package_type, &packages, true, project, label, group)
+        .await?;
+    Ok(job_id)
+}
+
+/// Get the status of a job, with the details of its packages.
+#[op]
+async fn op_get_job_status(
+    op_state: Rc<RefCell<OpState>>,
+    job_id: String,
+) -> Result<JobStatusResponse<PackageStatusExtended>, AnyError> {
+    let state = api_state(&op_state)?;
+    let api = state.api().await?;
+
+    let job_id = JobId::from_str(&job_id).map_err(|_| anyhow!("Invalid job ID: {}", job_id))?;
+    Ok(api.get_job_status_ext(&job_id).await?)
+}
+
+/// Get the details of a package.
+#[op]
+async fn op_get_package_details(
+    op_state: Rc<RefCell<OpState>>,
+    name: String,
+    version: String,
+    package_type: String,
+) -> Result<Package, AnyError> {
+    let state = api_state(&op_state)?;
+    let api = state.api().await?;
+
+    let package_type = PackageType::from_str(&package_type)
+        .map_err(|_| anyhow!("Invalid package type: {}", package_type))?;
+    let package = PackageDescriptor {
+        name,
+        version,
+        package_type,
+    };
+    Ok(api.get_package_details(&package).await?)
+}
+
+/// List the projects of the user, or of a group.
+#[op]
+async fn op_get_projects(
+    op_state: Rc<RefCell<OpState>>,
+    group: Option<String>,
+) -> Result<Vec<ProjectSummaryResponse>, AnyError> {
+    let state = api_state(&op_state)?;
+    let api = state.api().await?;
+    Ok(api.get_projects(group.as_deref()).await?)
+}
+
+/// List the groups of the user.
+#[op]
+async fn op_get_groups(op_state: Rc<RefCell<OpState>>) -> Result<ListUserGroupsResponse, AnyError> {
+    let state = api_state(&op_state)?;
+    let api = state.api().await?;
+    Ok(api.get_groups_list().await?)
+}
+
+/// Get information about the authenticated user.
+#[op]
+async fn op_get_user_info(op_state: Rc<RefCell<OpState>>) -> Result<UserInfo, AnyError> {
+    let state = api_state(&op_state)?;
+    let api = state.api().await?;
+    Ok(api.user_info().await?)
+}
+
+/// The Phylum API ops.
+pub(crate) fn ops() -> Vec<OpDecl> {
+    vec![
+        op_analyze::decl(),
+        op_get_job_status::decl(),
+        op_get_package_details::decl(),
+        op_get_projects::decl(),
+        op_get_groups::decl(),
+        op_get_user_info::decl(),
+    ]
+}
@@ -0,0 +1,285 @@
+// Phylum API bindings for extensions.
+//
+// ```ts
+// import { analyze, getJobStatus } from "phylum:api";
+//
+// const jobId = await analyze("npm", [{ name: "express", version: "4.18.1", type: "npm" }]);
+// const status = await getJobStatus(jobId);
+// console.log(status.pass);
+// ```
+
+declare const Deno: {
+  core: {
+    opAsync<T>(name: string, ...args: unknown[]): Promise<T>;
+    opSync<T>(name: string, ...args: unknown[]): T;
+  };
+};
+
+/** Invocation of the extension, available through the `Phylum` global. */
+export interface PhylumContext {
+  /**
+   * Values of the positional arguments declared in `PhylumExt.toml`, or all arguments
+   * passed to the extension when it declares none.
+   */
+  readonly args: readonly string[];
+  /** Values of the arguments declared in `PhylumExt.toml`, by name. */
+  readonly options: Readonly<
+    Record<string, string | string[] | boolean | null>
+  >;
+  /** Working directory of the CLI. */
+  readonly cwd: string;
+  /** Environment variables starting with `PHYLUM_`. */
+  readonly env: Readonly<Record<string, string>>;
+  /** Terminate the CLI immediately with an exit code. */
+  exit(code?: number): never;
+}
+
+declare global {
+  const Phylum: PhylumContext;
+}
+
+export type PackageType = "npm" | "pypi" | "maven" | "rubygems" | "nuget";
+
+export type RiskLevel = "info" | "low" | "medium" | "high" | "critical";
+
+export type RiskDomain =
+  | "malicious_code"
+  | "vulnerability"
+  | "engineering"
+  | "license"
+  | "author";
+
+export type Action = "none" | "warn" | "break";
+
+export interface PackageDescriptor {
+  name: string;
+  version: string;
+  type: PackageType;
+}
+
+export interface Issue {
+  title: string;
+  description: string;
+  severity: RiskLevel;
+  domain: RiskDomain;
+}
+
+export interface PackageStatus {
+  name: string;
+  version: string;
+  type: PackageType;
+  license: string | null;
+  package_score: number | null;
+  num_dependencies: number;
+  num_vulnerabilities: number;
+  last_updated: number;
+  status: string;
+  issues: Issue[];
+  risk_vectors: Record<string, number>;
+}
+
+export interface Thresholds {
+  author: number;
+  engineering: number;
+  license: number;
+  malicious: number;
+  vulnerability: number;
+  total: number;
+}
+
+export interface JobStatus {
+  job_id: string;
+  user_id: string;
+  user_email: string;
+  project: string;
+  project_name: string;
+  ecosystem: string;
+  label: string | null;
+  status: string;
+  msg: string;
+  pass: boolean;
+  action: Action;
+  score: number;
+  num_incomplete: number;
+  thresholds: Thresholds;
+  created_at: number;
+  last_updated: number;
+  packages: PackageStatus[];
+}
+
+export interface RiskScores {
+  total: number;
+  author: number;
+  engineering: number;
+  license: number;
+  malicious_code: number;
+  vulnerability: number;
+}
+
+export interface PackageIssue {
+  title: string;
+  description: string;
+  impact: RiskLevel;
+  risk_type: string;
+}
+
+export interface Package {
+  id: string;
+  name: string;
+  version: string;
+  registry: PackageType;
+  description: string | null;
+  license: string | null;
+  published_date: string | null;
+  risk_scores: RiskScores;
+  issues: PackageIssue[];
+  [key: string]: unknown;
+}
+
+export interface ProjectSummary {
+  id: string;
+  name: string;
+  updated_at: string;
+  [key: string]: unknown;
+}
+
+export interface Group {
+  group_name: string;
+  owner_email: string;
+  created_at: string;
+  [key: string]: unknown;
+}
+
+export interface UserInfo {
+  email: string;
+  sub: string | null;
+  name: string | null;
+  given_name: string | null;
+  family_name: string | null;
+  preferred_username: string | null;
+  email_verified: boolean | null;
+}
+
+/**
+ * Submit packages for analysis, returning the ID of the job.
+ *
+ * Without a `project`, the project linked to the current directory is used.
+ */
+export function analyze(
+  packageType: PackageType,
+  packages: PackageDescriptor[],
+  project?: string,
+  group?: string,
+  label?: string,
+): Promise<string> {
+  return Deno.core.opAsync(
+    "op_analyze",
+    packageType,
+    packages,
+    project ?? null,
+    group ?? null,
+    label ?? null,
+  );
+}
+
+/** Get the status of a job, with the details of its packages. */
+export function getJobStatus(jobId: string): Promise<JobStatus> {
+  return Deno.core.opAsync("op_get_job_status", jobId);
+}
+
+/** Get the details of a package. */
+export function getPackageDetails(
+  name: string,
+  version: string,
+  packageType: PackageType,
+): Promise<Package> {
+  return Deno.core.opAsync(
+    "op_get_package_details",
+    name,
+    version,
+    packageType,
+  );
+}
+
+/** List the projects of the user, or of one of their groups. */
+export function getProjects(group?: string): Promise<ProjectSummary[]> {
+  return Deno.core.opAsync("op_get_projects", group ?? null);
+}
+
+/** List the groups of the user. */
+export async function getGroups(): Promise<Group[]> {
+  const response = await Deno.core.opAsync<{ groups: Group[] }>(
+    "op_get_groups",
+  );
+  return response.groups;
+}
+
+/** Get information about the authenticated user. */
+export function getUserInfo(): Promise<UserInfo> {
+  return Deno.core.opAsync("op_get_user_info");
+}
+
+export interface ParsedLockfile {
+  /** Type of the packages, `null` for SBOMs listing packages of several ecosystems. */
+  packageType: PackageType | null;
+  packages: PackageDescriptor[];
+}
+
+export type LockfileErrorKind =
+  | "io"
+  | "unknown_type"
+  | "unsupported_format"
+  | "invalid";
+
+/** Error thrown when a lockfile can't be parsed. */
+export class LockfileError extends Error {
+  kind: LockfileErrorKind;
+  lockfileType: string | null;
+
+  constructor(
+    error: {
+      kind: LockfileErrorKind;
+      message: string;
+      lockfileType: string | null;
+    },
+  ) {
+    super(error.message);
+    this.name = "LockfileError";
+    this.kind = error.kind;
+    this.lockfileType = error.lockfileType;
+  }
+}
+
+function parsedLockfile(
+  response: ParsedLockfile | { error: ConstructorParameters<typeof LockfileError>[0] },
+): ParsedLockfile {
+  if ("error" in response) {
+    throw new LockfileError(response.error);
+  }
+  return response;
+}
+
+/**
+ * Parse the lockfile at `path`.
+ *
+ * The lockfile type is one of those accepted by `phylum parse --lockfile-type`. Without it,
+ * the type is detected from the file name and contents.
+ */
+export function parseLockfile(
+  path: string,
+  lockfileType?: string,
+): ParsedLockfile {
+  return parsedLockfile(
+    Deno.core.opSync("op_parse_lockfile", path, lockfileType ?? null),
+  );
+}
+
+/** Parse the contents of a lockfile. */
+export function parseLockfileData(
+  data: string,
+  lockfileType?: string,
+): ParsedLockfile {
+  return parsedLockfile(
+    Deno.core.opSync("op_parse_lockfile_data", data, lockfileType ?? null),
+  );
+}
@@ -0,0 +1,197 @@
+//! Lockfile parsing ops for extensions.
+
+use std::fs;
+use std::path::Path;
+
+use deno_core::error::AnyError;
+use deno_core::{op, OpDecl, OpState};
+use phylum_types::types::package::{PackageDescriptor, PackageType};
+use serde::Serialize;
+
+use crate::commands::parse::{detect_parser, lockfile_parser, lockfile_types, parser_for_path};
+use crate::deno::permissions::Permissions;
+use crate::lockfiles::Parse;
+
+/// Packages parsed from a lockfile.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedLockfile {
+    /// Type of the packages, unless the lockfile can list packages of several ecosystems.
+    pub package_type: Option<PackageType>,
+    pub packages: Vec<PackageDescriptor>,
+}
+
+/// Reason for a lockfile failing to parse.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockfileErrorKind {
+    /// The lockfile could not be read.
+    Io,
+    /// The requested lockfile type does not exist.
+    UnknownType,
+    /// The lockfile type could not be detected.
+    UnsupportedFormat,
+    /// The lockfile is invalid for its type.
+    Invalid,
+}
+
+/// Lockfile parsing error, as thrown to extensions.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockfileError {
+    pub kind: LockfileErrorKind,
+    pub message: String,
+    pub lockfile_type: Option<String>,
+}
+
+impl LockfileError {
+    fn new(kind: LockfileErrorKind, message: impl ToString) -> Self {
+        Self {
+            kind,
+            message: message.to_string(),
+            lockfile_type: None,
+        }
+    }
+}
+
+/// Result of the lockfile ops.
+///
+/// Errors are returned rather than thrown, so the `phylum:api` module can turn them into
+/// `LockfileError` exceptions with all their details.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum ParseResponse {
+    Parsed(ParsedLockfile),
+    Failed { error: LockfileError },
+}
+
+impl From<Result<ParsedLockfile, LockfileError>> for ParseResponse {
+    fn from(result: Result<ParsedLockfile, LockfileError>) -> Self {
+        match result {
+            Ok(parsed) => Self::Parsed(parsed),
+            Err(error) => Self::Failed { error },
+        }
+    }
+}
+
+/// Parse a lockfile's contents.
+///
+/// Without a lockfile type, or with `auto`, the type is detected from the file name if there is
+/// one, then from the contents.
+pub fn parse_lockfile(
+    data: &str,
+    path: Option<&Path>,
+    lockfile_type: Option<&str>,
+) -> Result<ParsedLockfile, LockfileError> {
+    let lockfile_type = lockfile_type.filter(|lockfile_type| *lockfile_type != "auto");
+    let parser: &dyn Parse = match lockfile_type {
+        Some(lockfile_type) => lockfile_parser(lockfile_type).ok_or_else(|| {
+            LockfileError::new(
+                LockfileErrorKind::UnknownType,
+                format!(
+                    "Unknown lockfile type `{}`, expected one of: {}",
+                    lockfile_type,
+                    lockfile_types().join(", ")
+                ),
+            )
+        })?,
+        None => match path.and_then(parser_for_path) {
+            Some(parser) => parser,
+            None => {
+                let (parser, packages) = detect_parser(data)
+                    .map_err(|err| LockfileError::new(LockfileErrorKind::UnsupportedFormat, err))?;
+                return Ok(ParsedLockfile {
+                    package_type: parser.package_type(),
+                    packages,
+                });
+            }
+        },
+    };
+
+    let packages = parser.parse(data).map_err(|err| LockfileError {
+        lockfile_type: lockfile_type.map(String::from),
+        ..LockfileError::new(LockfileErrorKind::Invalid, err)
+    })?;
+
+    Ok(ParsedLockfile {
+        package_type: parser.package_type(),
+        packages,
+    })
+}
+
+/// Parse the lockfile at `path`.
+///
+/// Reading a lockfile without permission throws, rather than returning a `LockfileError`.
+#[op]
+fn op_parse_lockfile(
+    op_state: &mut OpState,
+    path: String,
+    lockfile_type: Option<String>,
+) -> Result<ParseResponse, AnyError> {
+    let path = Path::new(&path);
+    op_state.borrow::<Permissions>().check_read(path)?;
+    let result = fs::read_to_string(path)
+        .map_err(|err| {
+            LockfileError::new(
+                LockfileErrorKind::Io,
+                format!("Unable to read lockfile `{}`: {}", path.display(), err),
+            )
+        })
+        .and_then(|data| parse_lockfile(&data, Some(path), lockfile_type.as_deref()));
+    Ok(result.into())
+}
+
+/// Parse the contents of a lockfile.
+#[op]
+fn op_parse_lockfile_data(
+    data: String,
+    lockfile_type: Option<String>,
+) -> Result<ParseResponse, AnyError> {
+    Ok(parse_lockfile(&data, None, lockfile_type.as_deref()).into())
+}
+
+/// The lockfile ops.
+pub(crate) fn ops() -> Vec<OpDecl> {
+    vec![op_parse_lockfile::decl(), op_parse_lockfile_data::decl()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> String {
+        fs::read_to_string(Path::new("tests/fixtures").join(name)).unwrap()
+    }
+
+    #[test]
+    fn parses_lockfiles() {
+        let data = fixture("Gemfile.lock");
+        let parsed = parse_lockfile(&data, Some(Path::new("Gemfile.lock")), None).unwrap();
+        assert_eq!(parsed.package_type, Some(PackageType::RubyGems));
+        assert!(!parsed.packages.is_empty());
+
+        let detected = parse_lockfile(&data, None, None).unwrap();
+        assert_eq!(detected.packages, parsed.packages);
+
+        let named = parse_lockfile(&data, None, Some("gem")).unwrap();
+        assert_eq!(named.packages, parsed.packages);
+    }
+
+    #[test]
+    fn returns_structured_errors() {
+        let data = fixture("Gemfile.lock");
+        let err = parse_lockfile(&data, None, Some("cargo")).unwrap_err();
+        assert_eq!(err.kind, LockfileErrorKind::UnknownType);
+
+        let err = parse_lockfile(&data, None, Some("npm")).unwrap_err();
+        assert_eq!(err.kind, LockfileErrorKind::Invalid);
+        assert_eq!(err.lockfile_type.as_deref(), Some("npm"));
+
+        let err = parse_lockfile("", None, None).unwrap_err();
+        assert_eq!(err.kind, LockfileErrorKind::UnsupportedFormat);
+
+        let response = ParseResponse::from(Err(err));
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["error"]["kind"], "unsupported_format");
+    }
+}
@@ -0,0 +1,395 @@
+//! Deno runtime for extensions.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::rc::Rc;
+
+use anyhow::{anyhow, Result};
+use deno_ast::{EmitOptions, MediaType, ParseParams, SourceTextInfo};
+use deno_core::error::AnyError;
+use deno_core::{
+    op, Extension, JsRuntime, ModuleLoader, ModuleSource, ModuleSourceFuture, ModuleSpecifier,
+    ModuleType, RuntimeOptions, SourceMapGetter,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use self::api::ApiState;
+use self::permissions::Permissions;
+use self::remote::RemoteModules;
+
+pub mod api;
+pub mod lockfile;
+pub mod permissions;
+pub mod remote;
+pub mod stdlib;
+
+/// URL scheme of the modules built into the CLI.
+const PHYLUM_SCHEME: &str = "phylum";
+
+/// Invocation of an extension, exposed to scripts through the `Phylum` global.
+#[derive(Debug, Serialize)]
+pub struct ExtensionContext {
+    /// Values of the positional arguments, or all arguments without an argument schema.
+    pub args: Vec<String>,
+    /// Values of the declared arguments, by name.
+    pub options: Map<String, Value>,
+    pub cwd: PathBuf,
+    /// Environment variables the extension is allowed to read.
+    pub env: BTreeMap<String, String>,
+}
+
+impl ExtensionContext {
+    pub fn new(
+        args: Vec<String>,
+        options: Map<String, Value>,
+        permissions: &Permissions,
+    ) -> Result<Self> {
+        let env = permissions
+            .env
+            .iter()
+            .filter_map(|name| Some((name.clone(), env::var(name).ok()?)))
+            .collect();
+
+        Ok(Self {
+            args,
+            options,
+            cwd: env::current_dir()?,
+            env,
+        })
+    }
+}
+
+/// Deno runtime state.
+pub struct DenoRuntime {
+    runtime: JsRuntime,
+}
+
+impl DenoRuntime {
+    /// Create a new Deno runtime.
+    ///
+    /// Ops check the `permissions` of the extension before accessing any resource, and remote
+    /// imports are resolved through `remote_modules`.
+    pub fn new(
+        api_state: ApiState,
+        permissions: Permissions,
+        remote_modules: RemoteModules,
+        context: ExtensionContext,
+    ) -> Result<Self> {
+        let api_state = Rc::new(api_state);
+        let phylum_api = Extension::builder()
+            .ops(
+                api::ops()
+                    .into_iter()
+                    .chain(lockfile::ops())
+                    .chain(stdlib::ops())
+                    .chain([op_exit::decl()])
+                    .collect(),
+            )
+            .state(move |state| {
+                state.put(api_state.clone());
+                state.put(permissions.clone());
+                Ok(())
+            })
+            .build();
+
+        let source_maps = SourceMaps::default();
+        let mut runtime = JsRuntime::new(RuntimeOptions {
+            module_loader: Some(Rc::new(TypescriptModuleLoader::new(
+                source_maps.clone(),
+                remote_modules,
+            ))),
+            source_map_getter: Some(Box::new(source_maps)),
+            extensions: vec![phylum_api],
+            ..Default::default()
+        });
+
+        // Define the `Phylum` global.
+        let bootstrap = format!(
+            "({})({});",
+            include_str!("runtime.js").trim_end().trim_end_matches(';'),
+            serde_json::to_string(&context)?
+        );
+        runtime.execute_script("phylum:runtime", &bootstrap)?;
+
+        Ok(Self { runtime })
+    }
+
+    /// Execute a JavaScript module from its main entry point.
+    ///
+    /// Fails with the stack trace of exceptions thrown during evaluation, and of promise
+    /// rejections which are never handled.
+    pub async fn run(&mut self, entrypoint: &str) -> Result<()> {
+        let module_specifier = deno_core::resolve_path(entrypoint)?;
+        let module = self
+            .runtime
+            .load_main_module(&module_specifier, None)
+            .await?;
+        let mut evaluation = self.runtime.mod_evaluate(module);
+
+        // Evaluation only completes once the event loop resolves top-level awaits, but the
+        // event loop can also fail first with an unhandled rejection.
+        tokio::select! {
+            biased;
+
+            result = &mut evaluation => result??,
+            result = self.runtime.run_event_loop(false) => {
+                result?;
+                return evaluation.await?;
+            }
+        }
+
+        // Run the remaining tasks of the extension.
+        self.runtime.run_event_loop(false).await?;
+
+        Ok(())
+    }
+}
+
+/// Terminate the CLI with the exit code passed to `Phylum.exit`.
+#[op]
+fn op_exit(code: i32) -> Result<(), AnyError> {
+    // Exiting skips the flush of buffered output.
+    io::stdout().flush()?;
+    io::stderr().flush()?;
+    std::process::exit(code)
+}
+
+/// Original sources and source maps of transpiled modules.
+///
+/// Used by the runtime to report stack traces with the original TypeScript locations.
+#[derive(Clone, Default)]
+struct SourceMaps(Rc<RefCell<HashMap<String, TranspiledSource>>>);
+
+struct TranspiledSource {
+    source: String,
+    source_map: Vec<u8>,
+}
+
+impl SourceMaps {
+    fn insert(&self, module_specifier: &ModuleSpecifier, source: String, source_map: String) {
+        self.0.borrow_mut().insert(
+            module_specifier.to_string(),
+            TranspiledSource {
+                source,
+                source_map: source_map.into_bytes(),
+            },
+        );
+    }
+}
+
+impl SourceMapGetter for SourceMaps {
+    fn get_source_map(&self, file_name: &str) -> Option<Vec<u8>> {
+        Some(self.0.borrow().get(file_name)?.source_map.clone())
+    }
+
+    fn get_source_line(&self, file_name: &str, line_number: usize) -> Option<String> {
+        let sources = self.0.borrow();
+        let line = sources.get(file_name)?.source.lines().nth(line_number)?;
+        Some(line.to_owned())
+    }
+}
+
+/// See https://github.com/denoland/deno/blob/main/core/examples/ts_module_loader.rs.
+struct TypescriptModuleLoader {
+    source_maps: SourceMaps,
+    remote_modules: Rc<RemoteModules>,
+}
+
+impl TypescriptModuleLoader {
+    fn new(source_maps: SourceMaps, remote_modules: RemoteModules) -> Self {
+        Self {
+            source_maps,
+            remote_modules: Rc::new(remote_modules),
+        }
+    }
+}
+
+impl ModuleLoader for TypescriptModuleLoader {
+    fn resolve(&self, specifier: &str, referrer: &str, _is_main: bool) -> Result<ModuleSpecifier> {
+        if specifier.starts_with(&format!("{}:", PHYLUM_SCHEME)) {
+            return Ok(ModuleSpecifier::parse(specifier)?);
+        }
+
+        let resolved = deno_core::resolve_import(specifier, referrer)?;
+
+        // Remote modules must not read local files.
+        if resolved.scheme() == "file" && referrer.starts_with("https:") {
+            return Err(anyhow!(
+                "Remote module `{}` can't import local module `{}`",
+                referrer,
+                resolved
+            ));
+        }
+
+        Ok(resolved)
+    }
+
+    fn load(
+        &self,
+        module_specifier: &ModuleSpecifier,
+        _maybe_referrer: Option<ModuleSpecifier>,
+        _is_dyn_import: bool,
+    ) -> Pin<Box<ModuleSourceFuture>> {
+        let module_specifier = module_specifier.clone();
+        let source_maps = self.source_maps.clone();
+        let remote_modules = self.remote_modules.clone();
+        Box::pin(async move {
+            let (code, path) = match module_specifier.scheme() {
+                PHYLUM_SCHEME => return load_builtin_module(&module_specifier, &source_maps),
+                "https" => {
+                    let code = remote_modules.load(&module_specifier).await?;
+                    (code, PathBuf::from(module_specifier.path()))
+                }
+                "file" => {
+                    let path = module_specifier
+                        .to_file_path()
+                        .map_err(|_| anyhow!("Invalid module path"))?;
+                    (fs::read_to_string(&path)?, path)
+                }
+                scheme => return Err(anyhow!("Unsupported module scheme: {}", scheme)),
+            };
+
+            // Determine source file type.
+            let media_type = MediaType::from(&path);
+            let (module_type, should_transpile) = match media_type {
+                MediaType::JavaScript | MediaType::Mjs | MediaType::Cjs => {
+                    (ModuleType::JavaScript, false)
+                }
+                MediaType::TypeScript
+                | MediaType::Jsx
+                | MediaType::Mts
+                | MediaType::Cts
+                | MediaType::Dts
+                | MediaType::Dmts
+                | MediaType::Dcts
+                | MediaType::Tsx => (ModuleType::JavaScript, true),
+                MediaType::Json => (ModuleType::Json, false),
+                _ => return Err(anyhow!("Unknown JS module format: {}", module_specifier)),
+            };
+
+            // Transpile the source if necessary.
+            let code = if should_transpile {
+                transpile(&module_specifier, code, media_type, &source_maps)?
+            } else {
+                code
+            };
+
+            Ok(ModuleSource {
+                code: code.into_bytes().into_boxed_slice(),
+                module_url_specified: module_specifier.to_string(),
+                module_url_found: module_specifier.to_string(),
+                module_type,
+            })
+        })
+    }
+}
+
+/// Load one of the modules built into the CLI, like `phylum:api`.
+fn load_builtin_module(
+    module_specifier: &ModuleSpecifier,
+    source_maps: &SourceMaps,
+) -> Result<ModuleSource> {
+    let code = match module_specifier.path() {
+        "api" => include_str!("api.ts"),
+        "std" => include_str!("stdlib.ts"),
+        _ => return Err(anyhow!("Unknown module: {}", module_specifier)),
+    };
+    let code = transpile(
+        module_specifier,
+        code.to_owned(),
+        MediaType::TypeScript,
+        source_maps,
+    )?;
+
+    Ok(ModuleSource {
+        code: code.into_bytes().into_boxed_slice(),
+        module_url_specified: module_specifier.to_string(),
+        module_url_found: module_specifier.to_string(),
+        module_type: ModuleType::JavaScript,
+    })
+}
+
+/// Transpile TypeScript and JSX sources to JavaScript, recording their source maps.
+fn transpile(
+    module_specifier: &ModuleSpecifier,
+    code: String,
+    media_type: MediaType,
+    source_maps: &SourceMaps,
+) -> Result<String> {
+    let parsed = deno_ast::parse_module(ParseParams {
+        specifier: module_specifier.to_string(),
+        text_info: SourceTextInfo::from_string(code.clone()),
+        capture_tokens: false,
+        scope_analysis: false,
+        maybe_syntax: None,
+        media_type,
+    })?;
+    let transpiled = parsed.transpile(&EmitOptions {
+        inline_source_map: false,
+        source_map: true,
+        ..Default::default()
+    })?;
+
+    if let Some(source_map) = transpiled.source_map {
+        source_maps.insert(module_specifier, code, source_map);
+    }
+
+    Ok(transpiled.text)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn loads_builtin_modules() {
+        let source_maps = SourceMaps::default();
+        let remote_modules = RemoteModules::new(Path::new("."), true, None, false).unwrap();
+        let loader = TypescriptModuleLoader::new(source_maps.clone(), remote_modules);
+        let specifier = loader
+            .resolve("phylum:api", "file:///extension/main.ts", false)
+            .unwrap();
+        assert_eq!(specifier.as_str(), "phylum:api");
+
+        let source = loader.load(&specifier, None, false).await.unwrap();
+        let code = String::from_utf8(source.code.into_vec()).unwrap();
+        assert!(code.contains("op_analyze"));
+        assert!(!code.contains("interface JobStatus"));
+        assert!(source_maps.get_source_map("phylum:api").is_some());
+        assert!(source_maps
+            .get_source_line("phylum:api", 0)
+            .unwrap()
+            .starts_with("// Phylum API"));
+
+        let std = ModuleSpecifier::parse("phylum:std").unwrap();
+        let source = loader.load(&std, None, false).await.unwrap();
+        let code = String::from_utf8(source.code.into_vec()).unwrap();
+        assert!(code.contains("op_fetch"));
+
+        let unknown = ModuleSpecifier::parse("phylum:unknown").unwrap();
+        assert!(loader.load(&unknown, None, false).await.is_err());
+    }
+
+    #[test]
+    fn remote_modules_cannot_import_local_files() {
+        let remote_modules = RemoteModules::new(Path::new("."), true, None, false).unwrap();
+        let loader = TypescriptModuleLoader::new(SourceMaps::default(), remote_modules);
+
+        let resolved = loader
+            .resolve("./utils.ts", "https://example.com/mod.ts", false)
+            .unwrap();
+        assert_eq!(resolved.as_str(), "https://example.com/utils.ts");
+
+        assert!(loader
+            .resolve("file:///etc/passwd", "https://example.com/mod.ts", false)
+            .is_err());
+    }
+}
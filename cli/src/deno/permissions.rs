@@ -0,0 +1,268 @@
+//! Permissions of extensions, declared in the `[permissions]` section of their manifest.
+//!
+//! ```toml
+//! [permissions]
+//! read = ["./", "~/.npmrc"]
+//! write = ["./phylum-report"]
+//! env = ["GITHUB_TOKEN"]
+//! net = ["api.github.com", "*.phylum.io"]
+//! run = ["git"]
+//! api = true
+//! ```
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use url::Url;
+
+/// Resources an extension is allowed to access. Everything else is denied.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Permissions {
+    /// Paths which can be read, including everything below them.
+    pub read: Vec<String>,
+    /// Paths which can be written, including everything below them.
+    pub write: Vec<String>,
+    /// Environment variables which can be read.
+    pub env: Vec<String>,
+    /// Hosts which can be reached. `*.example.com` allows all subdomains of `example.com`.
+    pub net: Vec<String>,
+    /// Commands which can be run.
+    pub run: Vec<String>,
+    /// Whether the Phylum API can be used on behalf of the user.
+    pub api: bool,
+}
+
+impl Permissions {
+    /// Check if no permissions are requested.
+    pub fn is_empty(&self) -> bool {
+        self.read.is_empty()
+            && self.write.is_empty()
+            && self.env.is_empty()
+            && self.net.is_empty()
+            && self.run.is_empty()
+            && !self.api
+    }
+
+    pub fn check_read(&self, path: &Path) -> Result<()> {
+        check_path(&self.read, path)
+            .ok_or_else(|| anyhow!("Requires read access to `{}`", path.display()))
+    }
+
+    pub fn check_write(&self, path: &Path) -> Result<()> {
+        check_path(&self.write, path)
+            .ok_or_else(|| anyhow!("Requires write access to `{}`", path.display()))
+    }
+
+    pub fn check_env(&self, name: &str) -> Result<()> {
+        if self.env.iter().any(|allowed| allowed == name) {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Requires access to the `{}` environment variable",
+                name
+            ))
+        }
+    }
+
+    pub fn check_net(&self, url: &Url) -> Result<()> {
+        let host = url
+            .host_str()
+            .ok_or_else(|| anyhow!("Invalid URL without host: {}", url))?;
+        let host_with_port = url.port().map(|port| format!("{}:{}", host, port));
+
+        let allowed = self.net.iter().any(|allowed| {
+            if let Some(domain) = allowed.strip_prefix("*.") {
+                host.ends_with(&format!(".{}", domain))
+            } else {
+                allowed == host || Some(allowed) == host_with_port.as_ref()
+            }
+        });
+
+        if allowed {
+            Ok(())
+        } else {
+            Err(anyhow!("Requires network access to `{}`", host))
+        }
+    }
+
+    pub fn check_run(&self, command: &str) -> Result<()> {
+        if self.run.iter().any(|allowed| allowed == command) {
+            Ok(())
+        } else {
+            Err(anyhow!("Requires permission to run `{}`", command))
+        }
+    }
+
+    pub fn check_api(&self) -> Result<()> {
+        if self.api {
+            Ok(())
+        } else {
+            Err(anyhow!("Requires access to the Phylum API"))
+        }
+    }
+}
+
+impl fmt::Display for Permissions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sections = [
+            ("Read files", &self.read),
+            ("Write files", &self.write),
+            ("Read environment variables", &self.env),
+            ("Access hosts", &self.net),
+            ("Run commands", &self.run),
+        ];
+        for (title, values) in sections {
+            if !values.is_empty() {
+                writeln!(f, "  {}: {}", title, values.join(", "))?;
+            }
+        }
+        if self.api {
+            writeln!(f, "  Use the Phylum API on your behalf")?;
+        }
+        Ok(())
+    }
+}
+
+/// Check if a path is below one of the allowed paths.
+fn check_path(allowed: &[String], path: &Path) -> Option<()> {
+    let path = resolve_path(path);
+    allowed
+        .iter()
+        .map(|allowed| resolve_path(Path::new(&*shellexpand::tilde(allowed))))
+        // Paths which can't be resolved are empty, and would allow everything.
+        .filter(|allowed| !allowed.as_os_str().is_empty())
+        .any(|allowed| path.starts_with(allowed))
+        .then(|| ())
+}
+
+/// Maximum number of symlinks followed when resolving a path.
+const MAX_SYMLINKS: usize = 40;
+
+/// Get the absolute path of a file which may not exist, without `.` and `..` components.
+fn resolve_path(path: &Path) -> PathBuf {
+    let path = match env::current_dir() {
+        Ok(cwd) => cwd.join(path),
+        Err(_) => path.to_path_buf(),
+    };
+    resolve_symlinks(&path, MAX_SYMLINKS)
+}
+
+/// Resolve the symlinks of an absolute path, to prevent escaping the allowed paths.
+///
+/// Components are resolved one at a time, since the path may not exist. Dangling symlinks are
+/// followed too, as writing to them creates their target.
+fn resolve_symlinks(path: &Path, symlinks: usize) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    let mut components = path.components();
+    while let Some(component) = components.next() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            component => {
+                resolved.push(component);
+                if let Ok(target) = fs::read_link(&resolved) {
+                    // Symlink loops can't be accessed, so don't allow anything.
+                    if symlinks == 0 {
+                        return PathBuf::new();
+                    }
+                    resolved.pop();
+                    let target = resolved.join(target).join(components.as_path());
+                    return resolve_symlinks(&target, symlinks - 1);
+                }
+            }
+        }
+    }
+    resolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn permissions(toml: &str) -> Permissions {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn checks_paths() {
+        let permissions = permissions(r#"read = ["src"]"#);
+        assert!(permissions.check_read(Path::new("src/deno")).is_ok());
+        assert!(permissions
+            .check_read(Path::new("./src/../src/lib.rs"))
+            .is_ok());
+        assert!(permissions
+            .check_read(Path::new("src/../Cargo.toml"))
+            .is_err());
+        assert!(permissions
+            .check_read(Path::new("src/missing/../../tests"))
+            .is_err());
+        assert!(permissions.check_write(Path::new("src/lib.rs")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn checks_symlinked_paths() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempfile::tempdir().unwrap();
+        let allowed = dir.path().join("allowed");
+        let outside = dir.path().join("outside");
+        fs::create_dir_all(&allowed).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        symlink(&outside, allowed.join("link")).unwrap();
+        symlink(outside.join("missing"), allowed.join("dangling")).unwrap();
+        symlink("loop", allowed.join("loop")).unwrap();
+
+        let permissions = Permissions {
+            write: vec![allowed.to_string_lossy().into_owned()],
+            ..Permissions::default()
+        };
+        assert!(permissions.check_write(&allowed.join("new/file")).is_ok());
+        assert!(permissions.check_write(&allowed.join("link")).is_err());
+        assert!(permissions
+            .check_write(&allowed.join("link/new/file"))
+            .is_err());
+        assert!(permissions.check_write(&allowed.join("dangling")).is_err());
+        assert!(permissions
+            .check_write(&allowed.join("new/../link/file"))
+            .is_err());
+        assert!(permissions.check_write(&allowed.join("loop")).is_err());
+
+        let looping = Permissions {
+            read: vec![allowed.join("loop").to_string_lossy().into_owned()],
+            ..Permissions::default()
+        };
+        assert!(looping.check_read(&outside).is_err());
+        assert!(looping.check_read(Path::new("/")).is_err());
+    }
+
+    #[test]
+    fn checks_hosts() {
+        let permissions =
+            permissions(r#"net = ["api.github.com", "*.phylum.io", "localhost:8080"]"#);
+        let check = |url: &str| permissions.check_net(&Url::parse(url).unwrap()).is_ok();
+        assert!(check("https://api.github.com/repos"));
+        assert!(check("https://api.phylum.io/"));
+        assert!(check("http://localhost:8080/"));
+        assert!(!check("https://phylum.io/"));
+        assert!(!check("https://github.com/"));
+        assert!(!check("http://localhost:9090/"));
+    }
+
+    #[test]
+    fn denies_by_default() {
+        let permissions = Permissions::default();
+        assert!(permissions.is_empty());
+        assert!(permissions.check_api().is_err());
+        assert!(permissions.check_env("HOME").is_err());
+        assert!(permissions.check_run("git").is_err());
+        assert!(permissions.check_read(Path::new(".")).is_err());
+    }
+}
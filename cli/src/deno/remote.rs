@@ -0,0 +1,242 @@
+//! Remote modules imported by extensions.
+//!
+//! Modules imported over `https:` are cached on disk, and their SHA-256 hashes are pinned in the
+//! `PhylumExt.lock` file of the extension, so a module changing upstream can't alter an
+//! installed extension.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use deno_core::ModuleSpecifier;
+use reqwest::redirect::Policy;
+use reqwest::Client;
+use sha2::{Digest, Sha256};
+
+/// Name of the lock file, next to the extension manifest.
+pub const LOCKFILE_NAME: &str = "PhylumExt.lock";
+
+/// Maximum number of redirects followed when downloading a module.
+const MAX_REDIRECTS: usize = 10;
+
+/// Cache and lock file of the remote modules of an extension.
+pub struct RemoteModules {
+    cache_dir: PathBuf,
+    lockfile_path: PathBuf,
+    /// Hashes of the remote modules, by URL.
+    lock: RefCell<BTreeMap<String, String>>,
+    offline: bool,
+    client: Client,
+}
+
+impl RemoteModules {
+    /// Load the lock file of the extension at `extension_path`.
+    ///
+    /// In `offline` mode, only modules which are already cached can be imported.
+    pub fn new(
+        extension_path: &Path,
+        offline: bool,
+        timeout: Option<u64>,
+        ignore_certs: bool,
+    ) -> Result<Self> {
+        let lockfile_path = extension_path.join(LOCKFILE_NAME);
+        let lock = match fs::read_to_string(&lockfile_path) {
+            Ok(lock) => serde_json::from_str(&lock)
+                .with_context(|| format!("Invalid lock file `{}`", lockfile_path.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(err.into()),
+        };
+
+        let client = Client::builder()
+            .timeout(Duration::from_secs(timeout.unwrap_or(std::u64::MAX)))
+            .danger_accept_invalid_certs(ignore_certs)
+            .redirect(Policy::custom(|attempt| {
+                let downgrade = attempt.url().scheme() != "https"
+                    && attempt.previous().iter().any(|url| url.scheme() == "https");
+                if attempt.previous().len() >= MAX_REDIRECTS {
+                    attempt.error(anyhow!("Too many redirects"))
+                } else if downgrade {
+                    attempt.error(anyhow!("Refusing redirect from https to {}", attempt.url()))
+                } else {
+                    attempt.follow()
+                }
+            }))
+            .build()?;
+
+        Ok(Self {
+            cache_dir: modules_path()?,
+            lockfile_path,
+            lock: RefCell::new(lock),
+            offline,
+            client,
+        })
+    }
+
+    /// Get the source of a remote module, from the cache or from the network.
+    ///
+    /// The hash of modules which aren't in the lock file yet is added to it.
+    pub async fn load(&self, module_specifier: &ModuleSpecifier) -> Result<String> {
+        let url = module_specifier.as_str();
+        let cache_path = self.cache_dir.join(sha256(url.as_bytes()));
+
+        if cache_path.exists() {
+            let code = fs::read_to_string(&cache_path)?;
+            self.check_hash(url, &code)?;
+            return Ok(code);
+        } else if self.offline {
+            return Err(anyhow!(
+                "Module `{}` is not cached, it can't be imported with `--offline`",
+                url
+            ));
+        }
+
+        log::debug!("Downloading module {}", url);
+        let code = self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await
+            .with_context(|| format!("Unable to download module `{}`", url))?;
+
+        // Only cache modules matching the lock file, so a module changed upstream can't poison
+        // the cache.
+        self.check_hash(url, &code)?;
+        fs::create_dir_all(&self.cache_dir)?;
+        fs::write(&cache_path, &code)?;
+
+        Ok(code)
+    }
+
+    /// Check the hash of a module against the lock file, adding it if the module isn't locked.
+    fn check_hash(&self, url: &str, code: &str) -> Result<()> {
+        let hash = sha256(code.as_bytes());
+        let expected = self.lock.borrow().get(url).cloned();
+        match expected {
+            Some(expected) if expected != hash => Err(anyhow!(
+                "Integrity check failed for module `{}`: expected hash {}, found {}",
+                url,
+                expected,
+                hash
+            )),
+            Some(_) => Ok(()),
+            None => {
+                self.lock.borrow_mut().insert(url.to_owned(), hash);
+                self.save_lock()
+            }
+        }
+    }
+
+    fn save_lock(&self) -> Result<()> {
+        let lock = serde_json::to_string_pretty(&*self.lock.borrow())?;
+        fs::write(&self.lockfile_path, lock + "\n").with_context(|| {
+            format!(
+                "Failed to save lock file to `{}`",
+                self.lockfile_path.display()
+            )
+        })
+    }
+}
+
+/// Directory of the remote module cache: $XDG_DATA_HOME/phylum/modules
+pub fn modules_path() -> Result<PathBuf> {
+    Ok(crate::config::data_dir()?.join("phylum").join("modules"))
+}
+
+/// Hex-encoded SHA-256 hash.
+fn sha256(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    fn remote_modules(dir: &Path, offline: bool) -> RemoteModules {
+        let mut modules = RemoteModules::new(dir, offline, None, false).unwrap();
+        modules.cache_dir = dir.join("modules");
+        modules
+    }
+
+    #[tokio::test]
+    async fn caches_and_locks_modules() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/utils.ts"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("export const a = 1;"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let url = ModuleSpecifier::parse(&format!("{}/utils.ts", mock_server.uri())).unwrap();
+
+        let modules = remote_modules(dir.path(), false);
+        assert_eq!(modules.load(&url).await.unwrap(), "export const a = 1;");
+
+        let lock = fs::read_to_string(dir.path().join(LOCKFILE_NAME)).unwrap();
+        let lock: BTreeMap<String, String> = serde_json::from_str(&lock).unwrap();
+        assert_eq!(lock[url.as_str()], sha256(b"export const a = 1;"));
+
+        // Cached modules are used offline, without hitting the server again.
+        let modules = remote_modules(dir.path(), true);
+        assert_eq!(modules.load(&url).await.unwrap(), "export const a = 1;");
+
+        // Modified modules are rejected.
+        let cache_path = dir
+            .path()
+            .join("modules")
+            .join(sha256(url.as_str().as_bytes()));
+        fs::write(cache_path, "export const a = 2;").unwrap();
+        let err = modules.load(&url).await.unwrap_err();
+        assert!(err.to_string().contains("Integrity check failed"));
+    }
+
+    #[tokio::test]
+    async fn mismatched_modules_are_not_cached() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/utils.ts"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("export const a = 2;"))
+            .mount(&mock_server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let url = ModuleSpecifier::parse(&format!("{}/utils.ts", mock_server.uri())).unwrap();
+        let mut lock = BTreeMap::new();
+        lock.insert(url.to_string(), sha256(b"export const a = 1;"));
+        fs::write(
+            dir.path().join(LOCKFILE_NAME),
+            serde_json::to_string(&lock).unwrap(),
+        )
+        .unwrap();
+
+        let modules = remote_modules(dir.path(), false);
+        let err = modules.load(&url).await.unwrap_err();
+        assert!(err.to_string().contains("Integrity check failed"));
+
+        let cache_path = dir
+            .path()
+            .join("modules")
+            .join(sha256(url.as_str().as_bytes()));
+        assert!(!cache_path.exists());
+    }
+
+    #[tokio::test]
+    async fn offline_requires_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = ModuleSpecifier::parse("https://example.com/utils.ts").unwrap();
+
+        let modules = remote_modules(dir.path(), true);
+        let err = modules.load(&url).await.unwrap_err();
+        assert!(err.to_string().contains("--offline"));
+    }
+}
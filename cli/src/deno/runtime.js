@@ -0,0 +1,17 @@
+// Bootstrap of the extension runtime, called with the `ExtensionContext` of the extension.
+function bootstrap(context) {
+  const Phylum = {
+    args: Object.freeze(context.args),
+    options: Object.freeze(context.options),
+    cwd: context.cwd,
+    env: Object.freeze(context.env),
+    exit: (code = 0) => Deno.core.opSync("op_exit", code),
+  };
+
+  Object.defineProperty(globalThis, "Phylum", {
+    value: Object.freeze(Phylum),
+    enumerable: true,
+    configurable: false,
+    writable: false,
+  });
+}
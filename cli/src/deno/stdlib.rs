@@ -0,0 +1,351 @@
+//! Filesystem, network, process and environment ops for extensions.
+//!
+//! These ops back the `phylum:std` module. Every op checks the permissions of the extension
+//! before accessing any resource.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use deno_core::error::AnyError;
+use deno_core::{op, OpDecl, OpState};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::redirect::Policy;
+use reqwest::{Client, Method};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use url::Url;
+
+use crate::deno::api::ApiState;
+use crate::deno::permissions::Permissions;
+
+/// Maximum number of redirects followed by `fetch`.
+const MAX_REDIRECTS: usize = 10;
+
+/// Get the permissions of the extension.
+fn permissions(op_state: &Rc<RefCell<OpState>>) -> Permissions {
+    op_state.borrow().borrow::<Permissions>().clone()
+}
+
+/// Read a file as UTF-8 text.
+#[op]
+async fn op_read_file(op_state: Rc<RefCell<OpState>>, path: String) -> Result<String, AnyError> {
+    let path = PathBuf::from(path);
+    permissions(&op_state).check_read(&path)?;
+
+    let contents = fs::read_to_string(&path)
+        .await
+        .with_context(|| format!("Unable to read `{}`", path.display()))?;
+    Ok(contents)
+}
+
+/// Write text to a file, replacing its contents.
+#[op]
+async fn op_write_file(
+    op_state: Rc<RefCell<OpState>>,
+    path: String,
+    contents: String,
+) -> Result<(), AnyError> {
+    let path = PathBuf::from(path);
+    permissions(&op_state).check_write(&path)?;
+
+    fs::write(&path, contents)
+        .await
+        .with_context(|| format!("Unable to write `{}`", path.display()))?;
+    Ok(())
+}
+
+/// Options of a `fetch` request.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct FetchOptions {
+    method: Option<String>,
+    headers: BTreeMap<String, String>,
+    body: Option<String>,
+}
+
+/// Response to a `fetch` request.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchResponse {
+    url: String,
+    status: u16,
+    status_text: String,
+    headers: BTreeMap<String, String>,
+    body: String,
+}
+
+/// Send an HTTP request.
+///
+/// Redirects are only followed to hosts the extension is allowed to access.
+#[op]
+async fn op_fetch(
+    op_state: Rc<RefCell<OpState>>,
+    url: String,
+    options: Option<FetchOptions>,
+) -> Result<FetchResponse, AnyError> {
+    let permissions = permissions(&op_state);
+    let api_state = op_state.borrow().borrow::<Rc<ApiState>>().clone();
+    let options = options.unwrap_or_default();
+
+    let url = Url::parse(&url).with_context(|| format!("Invalid URL: {}", url))?;
+    permissions.check_net(&url)?;
+
+    let redirect_permissions = permissions.clone();
+    let client = Client::builder()
+        .timeout(Duration::from_secs(
+            api_state.timeout().unwrap_or(std::u64::MAX),
+        ))
+        .danger_accept_invalid_certs(api_state.ignore_certs())
+        .redirect(Policy::custom(move |attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error(anyhow!("Too many redirects"))
+            } else if let Err(err) = redirect_permissions.check_net(attempt.url()) {
+                attempt.error(err)
+            } else {
+                attempt.follow()
+            }
+        }))
+        .build()?;
+
+    let method = match options.method {
+        Some(method) => Method::from_str(&method.to_uppercase())
+            .map_err(|_| anyhow!("Invalid HTTP method: {}", method))?,
+        None => Method::GET,
+    };
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in &options.headers {
+        headers.insert(
+            HeaderName::from_str(name).map_err(|_| anyhow!("Invalid header name: {}", name))?,
+            HeaderValue::from_str(value).map_err(|_| anyhow!("Invalid header value: {}", value))?,
+        );
+    }
+
+    let mut request = client.request(method, url).headers(headers);
+    if let Some(body) = options.body {
+        request = request.body(body);
+    }
+
+    let response = request.send().await?;
+    let status = response.status();
+    let response_headers = response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_owned())))
+        .collect();
+
+    Ok(FetchResponse {
+        url: response.url().to_string(),
+        status: status.as_u16(),
+        status_text: status.canonical_reason().unwrap_or_default().to_owned(),
+        headers: response_headers,
+        body: response.text().await?,
+    })
+}
+
+/// Options of a subprocess.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct RunOptions {
+    cwd: Option<String>,
+    env: BTreeMap<String, String>,
+    stdin: Option<String>,
+}
+
+/// Result of a finished subprocess.
+#[derive(Debug, Serialize)]
+pub struct RunOutput {
+    success: bool,
+    code: Option<i32>,
+    stdout: String,
+    stderr: String,
+}
+
+/// Run a command until it exits, capturing its output.
+///
+/// The command only inherits the environment variables the extension can read, and `PATH`.
+/// Variables set by the extension must be readable by it as well.
+#[op]
+async fn op_run(
+    op_state: Rc<RefCell<OpState>>,
+    command: String,
+    args: Vec<String>,
+    options: Option<RunOptions>,
+) -> Result<RunOutput, AnyError> {
+    let permissions = permissions(&op_state);
+    permissions.check_run(&command)?;
+    let options = options.unwrap_or_default();
+    if let Some(cwd) = &options.cwd {
+        permissions.check_read(Path::new(cwd))?;
+    }
+
+    let env = run_env(&permissions, &options.env)?;
+
+    let mut child = Command::new(find_program(&command));
+    child
+        .args(&args)
+        .env_clear()
+        .envs(env)
+        .stdin(if options.stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(cwd) = &options.cwd {
+        child.current_dir(cwd);
+    }
+
+    let mut child = child
+        .spawn()
+        .with_context(|| format!("Unable to run `{}`", command))?;
+    if let (Some(input), Some(mut stdin)) = (options.stdin, child.stdin.take()) {
+        stdin.write_all(input.as_bytes()).await?;
+    }
+
+    let output = child.wait_with_output().await?;
+    Ok(RunOutput {
+        success: output.status.success(),
+        code: output.status.code(),
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+    })
+}
+
+/// Get the environment of a subprocess.
+///
+/// This is made of the variables the extension can read and `PATH`, followed by the
+/// `overrides` of the extension.
+fn run_env(
+    permissions: &Permissions,
+    overrides: &BTreeMap<String, String>,
+) -> Result<Vec<(OsString, OsString)>> {
+    for name in overrides.keys() {
+        permissions.check_env(name)?;
+    }
+
+    let mut env = env::vars_os()
+        .filter(|(name, _)| {
+            name.to_str().map_or(false, |name| {
+                name == "PATH" || permissions.check_env(name).is_ok()
+            })
+        })
+        .collect::<Vec<_>>();
+    env.extend(
+        overrides
+            .iter()
+            .map(|(name, value)| (name.into(), value.into())),
+    );
+    Ok(env)
+}
+
+/// Find a command in the `PATH` of the CLI.
+///
+/// Commands are resolved before the environment of the subprocess is applied, so it can't
+/// change which program is run. Paths to commands are returned unchanged.
+fn find_program(command: &str) -> PathBuf {
+    let path = Path::new(command);
+    if path.components().count() > 1 {
+        return path.to_path_buf();
+    }
+
+    let names = [
+        command.to_owned(),
+        format!("{}{}", command, env::consts::EXE_SUFFIX),
+    ];
+    env::var_os("PATH")
+        .and_then(|paths| {
+            env::split_paths(&paths)
+                .filter(|dir| dir.is_absolute())
+                .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+                .find(|program| is_executable(program))
+        })
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::metadata(path).map_or(false, |metadata| {
+        metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+    })
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Read an environment variable.
+#[op]
+fn op_get_env(op_state: &mut OpState, name: String) -> Result<Option<String>, AnyError> {
+    op_state.borrow::<Permissions>().check_env(&name)?;
+    Ok(env::var(name).ok())
+}
+
+/// The standard library ops.
+pub(crate) fn ops() -> Vec<OpDecl> {
+    vec![
+        op_read_file::decl(),
+        op_write_file::decl(),
+        op_fetch::decl(),
+        op_run::decl(),
+        op_get_env::decl(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_env_requires_permissions() {
+        let permissions = Permissions {
+            env: vec!["PHYLUM_RUN_TEST".into()],
+            ..Permissions::default()
+        };
+        let overrides = |name: &str| {
+            let mut overrides = BTreeMap::new();
+            overrides.insert(name.to_owned(), "value".to_owned());
+            overrides
+        };
+
+        let env = run_env(&permissions, &overrides("PHYLUM_RUN_TEST")).unwrap();
+        let last = env.last().unwrap();
+        assert_eq!(last, &("PHYLUM_RUN_TEST".into(), "value".into()));
+        assert!(env
+            .iter()
+            .all(|(name, _)| name == "PATH" || name == "PHYLUM_RUN_TEST"));
+
+        assert!(run_env(&permissions, &overrides("PATH")).is_err());
+        assert!(run_env(&permissions, &overrides("LD_PRELOAD")).is_err());
+        assert!(run_env(&permissions, &overrides("GIT_SSH_COMMAND")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn finds_programs_in_path() {
+        let program = find_program("sh");
+        assert!(program.is_absolute());
+        assert!(is_executable(&program));
+
+        assert_eq!(find_program("./script.sh"), PathBuf::from("./script.sh"));
+        assert_eq!(
+            find_program("missing-program"),
+            PathBuf::from("missing-program")
+        );
+    }
+}
@@ -0,0 +1,116 @@
+// Filesystem, network, process and environment access for extensions.
+//
+// Each function requires the matching permission in the `[permissions]` section of
+// `PhylumExt.toml`, and throws without it.
+//
+// ```ts
+// import { fetch, readFile, run } from "phylum:std";
+//
+// const lockfile = await readFile("package-lock.json");
+// const response = await fetch("https://api.github.com/repos/phylum-dev/cli");
+// const { stdout } = await run("git", ["rev-parse", "HEAD"]);
+// ```
+
+declare const Deno: {
+  core: {
+    opAsync<T>(name: string, ...args: unknown[]): Promise<T>;
+    opSync<T>(name: string, ...args: unknown[]): T;
+  };
+};
+
+/** Read a file as UTF-8 text. Requires the `read` permission. */
+export function readFile(path: string): Promise<string> {
+  return Deno.core.opAsync("op_read_file", path);
+}
+
+/** Write text to a file, replacing its contents. Requires the `write` permission. */
+export function writeFile(path: string, contents: string): Promise<void> {
+  return Deno.core.opAsync("op_write_file", path, contents);
+}
+
+export interface FetchOptions {
+  method?: string;
+  headers?: Record<string, string>;
+  body?: string;
+}
+
+/** Response to a `fetch` request, with its whole body. */
+export class FetchResponse {
+  readonly url: string;
+  readonly status: number;
+  readonly statusText: string;
+  readonly headers: Readonly<Record<string, string>>;
+  readonly body: string;
+
+  constructor(response: {
+    url: string;
+    status: number;
+    statusText: string;
+    headers: Record<string, string>;
+    body: string;
+  }) {
+    this.url = response.url;
+    this.status = response.status;
+    this.statusText = response.statusText;
+    this.headers = response.headers;
+    this.body = response.body;
+  }
+
+  /** Whether the status is in the 200-299 range. */
+  get ok(): boolean {
+    return this.status >= 200 && this.status < 300;
+  }
+
+  text(): Promise<string> {
+    return Promise.resolve(this.body);
+  }
+
+  json<T = unknown>(): Promise<T> {
+    return Promise.resolve(JSON.parse(this.body));
+  }
+}
+
+/**
+ * Send an HTTP request. Requires the `net` permission for the host, and for the host of
+ * every redirect.
+ */
+export async function fetch(
+  url: string,
+  options?: FetchOptions,
+): Promise<FetchResponse> {
+  const response = await Deno.core.opAsync<
+    ConstructorParameters<typeof FetchResponse>[0]
+  >("op_fetch", url, options ?? null);
+  return new FetchResponse(response);
+}
+
+export interface RunOptions {
+  /** Working directory of the command. */
+  cwd?: string;
+  /** Environment variables added to those of the CLI. */
+  env?: Record<string, string>;
+  /** Text written to the standard input of the command. */
+  stdin?: string;
+}
+
+export interface RunOutput {
+  success: boolean;
+  /** Exit code, or `null` when the command was terminated by a signal. */
+  code: number | null;
+  stdout: string;
+  stderr: string;
+}
+
+/** Run a command until it exits, capturing its output. Requires the `run` permission. */
+export function run(
+  command: string,
+  args: string[] = [],
+  options?: RunOptions,
+): Promise<RunOutput> {
+  return Deno.core.opAsync("op_run", command, args, options ?? null);
+}
+
+/** Read an environment variable. Requires the `env` permission. */
+export function getEnv(name: string): string | undefined {
+  return Deno.core.opSync<string | null>("op_get_env", name) ?? undefined;
+}
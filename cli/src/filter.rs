@@ -1,62 +1,177 @@
+//! Filters limiting the packages and issues shown for analysis results.
+//!
+//! Two syntaxes are accepted by `--filter`:
+//!
+//! * a comma separated list of a minimum severity, risk domains and package URLs,
+//!   e.g. `crit,aut,eng` or `high,pkg:npm/lodash`;
+//! * an expression, e.g. `severity>=high and domain in (mal,vuln)` or
+//!   `ecosystem=npm and not name=@types/*`.
+
 use std::collections::HashSet;
 use std::iter::FromIterator;
 use std::str::FromStr;
 
-use phylum_types::types::package::{RiskLevel, RiskType};
+use anyhow::anyhow;
+use nom::branch::alt;
+use nom::bytes::complete::{tag, tag_no_case, take_while1};
+use nom::character::complete::{char, multispace0, none_of};
+use nom::combinator::{all_consuming, map, not, peek, recognize, value};
+use nom::error::{ErrorKind, ParseError};
+use nom::multi::{many0, separated_list1};
+use nom::number::complete::double;
+use nom::sequence::{delimited, preceded, terminated};
+use nom::IResult;
+use phylum_types::types::package::{
+    Issue, IssuesListItem, Package, PackageStatusExtended, PackageType, RiskLevel, RiskType,
+};
+
+use crate::purl::{self, Purl};
 
+#[derive(Clone, Debug)]
 pub struct Filter {
     pub level: Option<RiskLevel>,
     pub domains: Option<Vec<RiskType>>,
+    pub packages: Option<Vec<Purl>>,
+    pub expression: Option<Expression>,
+}
+
+/// A filter expression.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expression {
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    Not(Box<Expression>),
+    /// Compare the severity of an issue.
+    Severity(Comparison, RiskLevel),
+    /// Match issues in any of the risk domains.
+    Domain(Vec<RiskType>),
+    /// Match packages with a name matching any of the globs.
+    Name(Vec<String>),
+    /// Match packages of any of the ecosystems.
+    Ecosystem(Vec<PackageType>),
+    /// Compare the total score of a package.
+    Score(Comparison, f64),
+    /// Match packages by package URL.
+    Package(Purl),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    Lt,
+    Le,
+    Eq,
+    Ne,
+    Ge,
+    Gt,
+}
+
+impl Comparison {
+    fn holds<T: PartialOrd>(self, left: &T, right: &T) -> bool {
+        match self {
+            Comparison::Lt => left < right,
+            Comparison::Le => left <= right,
+            Comparison::Eq => left == right,
+            Comparison::Ne => left != right,
+            Comparison::Ge => left >= right,
+            Comparison::Gt => left > right,
+        }
+    }
+}
+
+/// The properties of a package a filter can match.
+pub struct FilterPackage<'a> {
+    pub package_type: &'a PackageType,
+    pub name: &'a str,
+    pub version: &'a str,
+    pub score: Option<f64>,
+}
+
+impl<'a> From<&'a PackageStatusExtended> for FilterPackage<'a> {
+    fn from(package: &'a PackageStatusExtended) -> Self {
+        let status = &package.basic_status;
+        FilterPackage {
+            package_type: &package.package_type,
+            name: &status.name,
+            version: &status.version,
+            score: status.package_score,
+        }
+    }
+}
+
+impl<'a> From<&'a Package> for FilterPackage<'a> {
+    fn from(package: &'a Package) -> Self {
+        FilterPackage {
+            package_type: &package.registry,
+            name: &package.name,
+            version: &package.version,
+            score: Some(package.risk_scores.total as f64),
+        }
+    }
+}
+
+/// The properties of an issue a filter can match.
+pub struct FilterIssue {
+    pub severity: RiskLevel,
+    pub domain: RiskType,
+}
+
+impl From<&Issue> for FilterIssue {
+    fn from(issue: &Issue) -> Self {
+        FilterIssue {
+            severity: issue.severity,
+            domain: issue.domain.into(),
+        }
+    }
+}
+
+impl From<&IssuesListItem> for FilterIssue {
+    fn from(issue: &IssuesListItem) -> Self {
+        FilterIssue {
+            severity: issue.impact,
+            domain: issue.risk_type,
+        }
+    }
 }
 
 impl FromStr for Filter {
-    type Err = ();
+    type Err = anyhow::Error;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
+        if let Some(filter) = Filter::from_list(input) {
+            return Ok(filter);
+        }
+
+        let expression = parse_expression(input)?;
+        Ok(Filter {
+            level: None,
+            domains: None,
+            packages: None,
+            expression: Some(expression),
+        })
+    }
+}
+
+impl Filter {
+    /// Parse the comma separated list form of a filter.
+    ///
+    /// Returns `None` if any of the tokens is not a severity, risk domain or package URL.
+    fn from_list(input: &str) -> Option<Self> {
         let mut tokens = input.split(',').collect::<Vec<&str>>();
 
         tokens.sort_unstable();
         tokens.dedup();
 
-        let level = tokens
-            .iter()
-            .filter_map(|t| match *t {
-                "crit" => Some(RiskLevel::Critical),
-                "critical" => Some(RiskLevel::Critical),
-                "high" => Some(RiskLevel::High),
-                "hi" => Some(RiskLevel::High),
-                "med" => Some(RiskLevel::Medium),
-                "medium" => Some(RiskLevel::Medium),
-                "info" => Some(RiskLevel::Info),
-                "low" => Some(RiskLevel::Low),
-                "lo" => Some(RiskLevel::Low),
-                _ => None,
-            })
-            .min();
+        let known =
+            |t: &&str| risk_level(t).is_some() || risk_domain(t).is_some() || purl::is_purl(t);
+        if !tokens.iter().all(known) {
+            return None;
+        }
+
+        let level = tokens.iter().filter_map(|t| risk_level(t)).min();
 
         let domains = tokens
             .iter()
-            .filter_map(|t| match *t {
-                "aut" => Some(RiskType::AuthorsRisk),
-                "AUT" => Some(RiskType::AuthorsRisk),
-                "auth" => Some(RiskType::AuthorsRisk),
-                "author" => Some(RiskType::AuthorsRisk),
-                "eng" => Some(RiskType::EngineeringRisk),
-                "ENG" => Some(RiskType::EngineeringRisk),
-                "engineering" => Some(RiskType::EngineeringRisk),
-                "code" => Some(RiskType::MaliciousCodeRisk),
-                "malicious_code" => Some(RiskType::MaliciousCodeRisk),
-                "mal" => Some(RiskType::MaliciousCodeRisk),
-                "MAL" => Some(RiskType::MaliciousCodeRisk),
-                "vuln" => Some(RiskType::Vulnerabilities),
-                "vulnerability" => Some(RiskType::Vulnerabilities),
-                "VLN" => Some(RiskType::Vulnerabilities),
-                "vln" => Some(RiskType::Vulnerabilities),
-                "license" => Some(RiskType::LicenseRisk),
-                "lic" => Some(RiskType::LicenseRisk),
-                "LIC" => Some(RiskType::LicenseRisk),
-                _ => None,
-            })
+            .filter_map(|t| risk_domain(t))
             .collect::<HashSet<RiskType>>();
 
         let domains = if domains.is_empty() {
@@ -65,10 +180,424 @@ impl FromStr for Filter {
             Some(Vec::from_iter(domains))
         };
 
-        if level.is_none() && domains.is_none() {
-            Err(())
+        let packages = tokens
+            .iter()
+            .filter(|t| purl::is_purl(t))
+            .map(|t| Purl::from_str(t).ok())
+            .collect::<Option<Vec<Purl>>>()?;
+
+        let packages = if packages.is_empty() {
+            None
         } else {
-            Ok(Filter { level, domains })
+            Some(packages)
+        };
+
+        Some(Filter {
+            level,
+            domains,
+            packages,
+            expression: None,
+        })
+    }
+
+    /// Check if a package is included by this filter.
+    ///
+    /// Conditions on issues are ignored, so a package is included unless the filter
+    /// excludes it regardless of its issues.
+    pub fn matches_package(&self, package: &FilterPackage) -> bool {
+        self.includes_package(package)
+            && self.expression.as_ref().map_or(true, |expression| {
+                expression.eval(package, None) != Some(false)
+            })
+    }
+
+    /// Check if an issue of a package is included by this filter.
+    pub fn matches_issue(&self, package: &FilterPackage, issue: &FilterIssue) -> bool {
+        if let Some(ref level) = self.level {
+            if issue.severity < *level {
+                return false;
+            }
+        }
+        if let Some(ref domains) = self.domains {
+            if !domains.contains(&issue.domain) {
+                return false;
+            }
+        }
+        self.includes_package(package)
+            && self.expression.as_ref().map_or(true, |expression| {
+                expression.eval(package, Some(issue)) == Some(true)
+            })
+    }
+
+    /// Check if a package is included by the package URLs of this filter.
+    fn includes_package(&self, package: &FilterPackage) -> bool {
+        self.packages.as_ref().map_or(true, |packages| {
+            packages
+                .iter()
+                .any(|purl| purl.matches(package.package_type, package.name, package.version))
+        })
+    }
+}
+
+impl Expression {
+    /// Evaluate the expression.
+    ///
+    /// Conditions on issues are unknown without an issue, and conditions on the score are
+    /// unknown for packages without a score. Unknown results are `None`, and are combined
+    /// using three-valued logic.
+    fn eval(&self, package: &FilterPackage, issue: Option<&FilterIssue>) -> Option<bool> {
+        match self {
+            Expression::And(left, right) => {
+                match (left.eval(package, issue), right.eval(package, issue)) {
+                    (Some(false), _) | (_, Some(false)) => Some(false),
+                    (Some(true), Some(true)) => Some(true),
+                    _ => None,
+                }
+            }
+            Expression::Or(left, right) => {
+                match (left.eval(package, issue), right.eval(package, issue)) {
+                    (Some(true), _) | (_, Some(true)) => Some(true),
+                    (Some(false), Some(false)) => Some(false),
+                    _ => None,
+                }
+            }
+            Expression::Not(expression) => expression.eval(package, issue).map(|value| !value),
+            Expression::Severity(comparison, level) => {
+                issue.map(|issue| comparison.holds(&issue.severity, level))
+            }
+            Expression::Domain(domains) => issue.map(|issue| domains.contains(&issue.domain)),
+            Expression::Name(globs) => {
+                Some(globs.iter().any(|glob| glob_matches(glob, package.name)))
+            }
+            Expression::Ecosystem(types) => Some(types.contains(package.package_type)),
+            Expression::Score(comparison, score) => package
+                .score
+                .map(|package_score| comparison.holds(&package_score, score)),
+            Expression::Package(purl) => {
+                Some(purl.matches(package.package_type, package.name, package.version))
+            }
+        }
+    }
+}
+
+fn risk_level(token: &str) -> Option<RiskLevel> {
+    match token {
+        "crit" => Some(RiskLevel::Critical),
+        "critical" => Some(RiskLevel::Critical),
+        "high" => Some(RiskLevel::High),
+        "hi" => Some(RiskLevel::High),
+        "med" => Some(RiskLevel::Medium),
+        "medium" => Some(RiskLevel::Medium),
+        "info" => Some(RiskLevel::Info),
+        "low" => Some(RiskLevel::Low),
+        "lo" => Some(RiskLevel::Low),
+        _ => None,
+    }
+}
+
+fn risk_domain(token: &str) -> Option<RiskType> {
+    match token {
+        "aut" => Some(RiskType::AuthorsRisk),
+        "AUT" => Some(RiskType::AuthorsRisk),
+        "auth" => Some(RiskType::AuthorsRisk),
+        "author" => Some(RiskType::AuthorsRisk),
+        "eng" => Some(RiskType::EngineeringRisk),
+        "ENG" => Some(RiskType::EngineeringRisk),
+        "engineering" => Some(RiskType::EngineeringRisk),
+        "code" => Some(RiskType::MaliciousCodeRisk),
+        "malicious_code" => Some(RiskType::MaliciousCodeRisk),
+        "mal" => Some(RiskType::MaliciousCodeRisk),
+        "MAL" => Some(RiskType::MaliciousCodeRisk),
+        "vuln" => Some(RiskType::Vulnerabilities),
+        "vulnerability" => Some(RiskType::Vulnerabilities),
+        "VLN" => Some(RiskType::Vulnerabilities),
+        "vln" => Some(RiskType::Vulnerabilities),
+        "license" => Some(RiskType::LicenseRisk),
+        "lic" => Some(RiskType::LicenseRisk),
+        "LIC" => Some(RiskType::LicenseRisk),
+        _ => None,
+    }
+}
+
+fn ecosystem(token: &str) -> Option<PackageType> {
+    match token.to_lowercase().as_str() {
+        "npm" => Some(PackageType::Npm),
+        "pypi" | "python" => Some(PackageType::PyPi),
+        "rubygems" | "gem" | "ruby" => Some(PackageType::RubyGems),
+        "maven" | "mvn" => Some(PackageType::Maven),
+        "nuget" => Some(PackageType::Nuget),
+        _ => None,
+    }
+}
+
+/// Check if a name matches a glob, where `*` matches any sequence of characters and `?`
+/// any single character. Names are compared case-insensitively.
+pub(crate) fn glob_matches(glob: &str, name: &str) -> bool {
+    let glob = glob.to_lowercase().chars().collect::<Vec<_>>();
+    let name = name.to_lowercase().chars().collect::<Vec<_>>();
+
+    let (mut g, mut n) = (0, 0);
+    // Position of the last `*` in the glob, and of the name when it was reached.
+    let mut backtrack = None;
+    while n < name.len() {
+        match glob.get(g) {
+            Some('*') => {
+                backtrack = Some((g, n));
+                g += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                g += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, start)) => {
+                    g = star + 1;
+                    n = start + 1;
+                    backtrack = Some((star, start + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+/// A filter expression syntax error.
+#[derive(Debug, PartialEq)]
+struct SyntaxError<'a> {
+    input: &'a str,
+    message: String,
+}
+
+impl<'a> ParseError<&'a str> for SyntaxError<'a> {
+    fn from_error_kind(input: &'a str, kind: ErrorKind) -> Self {
+        let message = match kind {
+            ErrorKind::Eof if input.trim().is_empty() => "unexpected end of filter".to_owned(),
+            ErrorKind::Eof => format!("unexpected `{}`", input.trim()),
+            _ => "invalid filter expression".to_owned(),
+        };
+        SyntaxError { input, message }
+    }
+
+    fn append(_input: &'a str, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+type Res<'a, T> = IResult<&'a str, T, SyntaxError<'a>>;
+
+/// Fail with an error which is not recovered from by alternatives.
+fn failure<'a, T>(input: &'a str, message: String) -> Res<'a, T> {
+    Err(nom::Err::Failure(SyntaxError { input, message }))
+}
+
+/// Replace the error of a parser with a message, and stop looking for alternatives.
+fn expect<'a, T>(
+    mut parser: impl FnMut(&'a str) -> Res<'a, T>,
+    message: &'static str,
+) -> impl FnMut(&'a str) -> Res<'a, T> {
+    move |input| match parser(input) {
+        Err(nom::Err::Error(_)) => failure(input.trim_start(), message.to_owned()),
+        result => result,
+    }
+}
+
+fn ws<'a, T>(parser: impl FnMut(&'a str) -> Res<'a, T>) -> impl FnMut(&'a str) -> Res<'a, T> {
+    preceded(multispace0, parser)
+}
+
+/// Parse a filter expression.
+fn parse_expression(input: &str) -> anyhow::Result<Expression> {
+    let parser = expect(expression, "expected a condition");
+    match all_consuming(terminated(parser, multispace0))(input) {
+        Ok((_, expression)) => Ok(expression),
+        Err(nom::Err::Error(error)) | Err(nom::Err::Failure(error)) => {
+            let column = input.len() - error.input.len() + 1;
+            Err(anyhow!(
+                "Invalid filter `{}`: {} (at column {})",
+                input,
+                error.message,
+                column
+            ))
+        }
+        Err(nom::Err::Incomplete(_)) => Err(anyhow!("Invalid filter `{}`", input)),
+    }
+}
+
+fn expression(input: &str) -> Res<'_, Expression> {
+    let (input, first) = conjunction(input)?;
+    let operator = ws(alt((keyword("or"), tag("||"))));
+    let (input, rest) = many0(preceded(
+        operator,
+        expect(conjunction, "expected a condition"),
+    ))(input)?;
+    let expression = rest.into_iter().fold(first, |left, right| {
+        Expression::Or(Box::new(left), Box::new(right))
+    });
+    Ok((input, expression))
+}
+
+fn conjunction(input: &str) -> Res<'_, Expression> {
+    let (input, first) = unary(input)?;
+    let operator = ws(alt((keyword("and"), tag("&&"))));
+    let (input, rest) = many0(preceded(operator, expect(unary, "expected a condition")))(input)?;
+    let expression = rest.into_iter().fold(first, |left, right| {
+        Expression::And(Box::new(left), Box::new(right))
+    });
+    Ok((input, expression))
+}
+
+fn unary(input: &str) -> Res<'_, Expression> {
+    let negation = ws(alt((keyword("not"), terminated(tag("!"), not(char('='))))));
+    alt((
+        map(
+            preceded(negation, expect(unary, "expected a condition")),
+            |expression| Expression::Not(Box::new(expression)),
+        ),
+        primary,
+    ))(input)
+}
+
+fn primary(input: &str) -> Res<'_, Expression> {
+    alt((
+        delimited(
+            ws(char('(')),
+            expect(expression, "expected a condition"),
+            expect(ws(char(')')), "expected `)`"),
+        ),
+        condition,
+    ))(input)
+}
+
+/// Match a keyword, which must not be followed by other identifier characters.
+fn keyword<'a>(name: &'static str) -> impl FnMut(&'a str) -> Res<'a, &'a str> {
+    terminated(
+        tag_no_case(name),
+        not(peek(take_while1(is_identifier_char))),
+    )
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Parse a value, either quoted or up to the next whitespace, parenthesis or comma.
+fn word(input: &str) -> Res<'_, &str> {
+    alt((
+        delimited(char('"'), recognize(many0(none_of("\""))), char('"')),
+        delimited(char('\''), recognize(many0(none_of("'"))), char('\'')),
+        take_while1(|c: char| !c.is_whitespace() && !"(),".contains(c)),
+    ))(input)
+}
+
+fn comparison(input: &str) -> Res<'_, Comparison> {
+    alt((
+        value(Comparison::Ge, tag(">=")),
+        value(Comparison::Le, tag("<=")),
+        value(Comparison::Ne, tag("!=")),
+        value(Comparison::Eq, tag("==")),
+        value(Comparison::Eq, tag("=")),
+        value(Comparison::Gt, tag(">")),
+        value(Comparison::Lt, tag("<")),
+    ))(input)
+}
+
+/// Parse a condition, such as `severity>=high`, `domain in (mal,vuln)` or a package URL.
+fn condition(input: &str) -> Res<'_, Expression> {
+    let (input, _) = multispace0(input)?;
+
+    if input.starts_with("pkg:") {
+        let (rest, purl) = word(input)?;
+        return match Purl::from_str(purl) {
+            Ok(purl) => Ok((rest, Expression::Package(purl))),
+            Err(err) => failure(input, format!("invalid package URL: {}", err)),
+        };
+    }
+
+    let (rest, field) = take_while1(is_identifier_char)(input)?;
+    match field.to_lowercase().as_str() {
+        "severity" | "sev" => {
+            let (rest, comparison) = expect(ws(comparison), "expected a comparison")(rest)?;
+            let (rest, level) = ws(parsed(risk_level, "severity"))(rest)?;
+            Ok((rest, Expression::Severity(comparison, level)))
+        }
+        "domain" => membership(rest, parsed(risk_domain, "risk domain"), Expression::Domain),
+        "name" => membership(rest, glob, Expression::Name),
+        "ecosystem" | "type" => {
+            membership(rest, parsed(ecosystem, "ecosystem"), Expression::Ecosystem)
+        }
+        "score" => {
+            let (rest, comparison) = expect(ws(comparison), "expected a comparison")(rest)?;
+            let (rest, score) = expect(ws(double), "expected a score")(rest)?;
+            Ok((rest, Expression::Score(comparison, score)))
+        }
+        // Severities and risk domains on their own are shorthands, like in the list form.
+        _ if risk_level(field).is_some() && !starts_with_operator(rest) => {
+            let level = risk_level(field).unwrap();
+            Ok((rest, Expression::Severity(Comparison::Ge, level)))
+        }
+        _ if risk_domain(field).is_some() && !starts_with_operator(rest) => {
+            let domain = risk_domain(field).unwrap();
+            Ok((rest, Expression::Domain(vec![domain])))
+        }
+        _ => failure(input, format!("unknown filter term `{}`", field)),
+    }
+}
+
+fn starts_with_operator(input: &str) -> bool {
+    ws(comparison)(input).is_ok()
+}
+
+/// Parse the values of `field = value`, `field != value` or `field in (value, ...)`.
+fn membership<'a, T>(
+    input: &'a str,
+    parser: impl FnMut(&'a str) -> Res<'a, T> + Copy,
+    expression: impl Fn(Vec<T>) -> Expression,
+) -> Res<'a, Expression> {
+    let list = preceded(
+        ws(keyword("in")),
+        delimited(
+            expect(ws(char('(')), "expected `(`"),
+            separated_list1(ws(char(',')), ws(parser)),
+            expect(ws(char(')')), "expected `)`"),
+        ),
+    );
+    let single = |input: &'a str| -> Res<'a, (bool, Vec<T>)> {
+        let (input, comparison) = ws(comparison)(input)?;
+        let (input, value) = ws(parser)(input)?;
+        match comparison {
+            Comparison::Eq => Ok((input, (false, vec![value]))),
+            Comparison::Ne => Ok((input, (true, vec![value]))),
+            _ => failure(input, "expected `=`, `!=` or `in`".to_owned()),
+        }
+    };
+
+    let (input, (negated, values)) = expect(
+        alt((map(list, |values| (false, values)), single)),
+        "expected `=`, `!=` or `in`",
+    )(input)?;
+    let expression = expression(values);
+    if negated {
+        Ok((input, Expression::Not(Box::new(expression))))
+    } else {
+        Ok((input, expression))
+    }
+}
+
+fn glob(input: &str) -> Res<'_, String> {
+    map(expect(word, "expected a name"), str::to_owned)(input)
+}
+
+/// Parse a word with a conversion function, failing with a clear error for unknown words.
+fn parsed<'a, T>(
+    convert: fn(&str) -> Option<T>,
+    name: &'static str,
+) -> impl FnMut(&'a str) -> Res<'a, T> + Copy {
+    move |input| {
+        let (rest, token) = expect(word, "expected a value")(input)?;
+        match convert(token) {
+            Some(value) => Ok((rest, value)),
+            None => failure(input, format!("unknown {} `{}`", name, token)),
         }
     }
 }
@@ -85,7 +614,7 @@ mod tests {
 
         assert_eq!(filter.level, Some(RiskLevel::Medium));
 
-        let filter_string = "foo,crit,author,engineering";
+        let filter_string = "crit,author,engineering";
 
         let filter = Filter::from_str(filter_string).expect("Failed to parse filter string: {}");
 
@@ -106,7 +635,7 @@ mod tests {
         assert!(domains.contains(&RiskType::AuthorsRisk));
         assert!(domains.contains(&RiskType::EngineeringRisk));
 
-        let filter_string = "crit,author,AUT,med,ENG,foo,engineering,VLN";
+        let filter_string = "crit,author,AUT,med,ENG,engineering,VLN";
 
         let filter = Filter::from_str(filter_string).expect("Failed to parse filter string: {}");
 
@@ -119,4 +648,145 @@ mod tests {
         assert!(domains.contains(&RiskType::EngineeringRisk));
         assert!(domains.contains(&RiskType::Vulnerabilities));
     }
+
+    #[test]
+    fn it_can_parse_filter_packages() {
+        let filter_string = "high,pkg:npm/%40angular/core,pkg:pypi/requests@2.27.1";
+
+        let filter = Filter::from_str(filter_string).expect("Failed to parse filter string: {}");
+
+        assert_eq!(filter.level, Some(RiskLevel::High));
+        let packages = filter
+            .packages
+            .expect("No packages parsed from filter string");
+
+        assert_eq!(packages.len(), 2);
+        assert!(packages.iter().any(|purl| purl.matches(
+            &PackageType::Npm,
+            "@angular/core",
+            "12.1.0"
+        )));
+        assert!(packages
+            .iter()
+            .any(|purl| purl.matches(&PackageType::PyPi, "requests", "2.27.1")));
+    }
+
+    #[test]
+    fn it_rejects_unknown_tokens() {
+        let err = Filter::from_str("foo,crit").unwrap_err().to_string();
+        assert!(err.contains("unknown filter term `foo`"), "{}", err);
+
+        let err = Filter::from_str("severity>=urgent")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("unknown severity `urgent`"), "{}", err);
+
+        let err = Filter::from_str("domain in (mal,typo)")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("unknown risk domain `typo`"), "{}", err);
+
+        let err = Filter::from_str("score<0.4 score>0.1")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("unexpected `score>0.1`"), "{}", err);
+
+        let err = Filter::from_str("(high and mal").unwrap_err().to_string();
+        assert!(err.contains("expected `)`"), "{}", err);
+
+        assert!(Filter::from_str("").is_err());
+    }
+
+    #[test]
+    fn it_can_parse_filter_expressions() {
+        let filter = Filter::from_str("severity>=high and domain in (mal, vuln)").unwrap();
+        assert_eq!(
+            filter.expression,
+            Some(Expression::And(
+                Box::new(Expression::Severity(Comparison::Ge, RiskLevel::High)),
+                Box::new(Expression::Domain(vec![
+                    RiskType::MaliciousCodeRisk,
+                    RiskType::Vulnerabilities
+                ])),
+            ))
+        );
+
+        let filter =
+            Filter::from_str("not name = \"@types/*\" or score < 0.4 and ecosystem=npm").unwrap();
+        assert_eq!(
+            filter.expression,
+            Some(Expression::Or(
+                Box::new(Expression::Not(Box::new(Expression::Name(vec![
+                    "@types/*".to_owned()
+                ])))),
+                Box::new(Expression::And(
+                    Box::new(Expression::Score(Comparison::Lt, 0.4)),
+                    Box::new(Expression::Ecosystem(vec![PackageType::Npm])),
+                )),
+            ))
+        );
+
+        let filter = Filter::from_str("!(crit || ecosystem != pypi)").unwrap();
+        assert_eq!(
+            filter.expression,
+            Some(Expression::Not(Box::new(Expression::Or(
+                Box::new(Expression::Severity(Comparison::Ge, RiskLevel::Critical)),
+                Box::new(Expression::Not(Box::new(Expression::Ecosystem(vec![
+                    PackageType::PyPi
+                ])))),
+            ))))
+        );
+    }
+
+    #[test]
+    fn it_can_match_filter_expressions() {
+        let lodash = FilterPackage {
+            package_type: &PackageType::Npm,
+            name: "lodash",
+            version: "4.17.21",
+            score: Some(0.3),
+        };
+        let high_vuln = FilterIssue {
+            severity: RiskLevel::High,
+            domain: RiskType::Vulnerabilities,
+        };
+        let low_license = FilterIssue {
+            severity: RiskLevel::Low,
+            domain: RiskType::LicenseRisk,
+        };
+
+        let filter = Filter::from_str("severity>=high and domain in (mal,vuln)").unwrap();
+        assert!(filter.matches_package(&lodash));
+        assert!(filter.matches_issue(&lodash, &high_vuln));
+        assert!(!filter.matches_issue(&lodash, &low_license));
+
+        let filter = Filter::from_str("name=lo*sh and score<0.4").unwrap();
+        assert!(filter.matches_package(&lodash));
+        assert!(filter.matches_issue(&lodash, &low_license));
+
+        let filter = Filter::from_str("ecosystem=pypi or score>=0.4").unwrap();
+        assert!(!filter.matches_package(&lodash));
+        assert!(!filter.matches_issue(&lodash, &high_vuln));
+
+        let filter = Filter::from_str("not pkg:npm/lodash and high").unwrap();
+        assert!(!filter.matches_package(&lodash));
+
+        let unscored = FilterPackage {
+            score: None,
+            ..lodash
+        };
+        let filter = Filter::from_str("score<0.4").unwrap();
+        assert!(filter.matches_package(&unscored));
+        assert!(!filter.matches_issue(&unscored, &high_vuln));
+    }
+
+    #[test]
+    fn it_can_match_globs() {
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("@angular/*", "@angular/core"));
+        assert!(glob_matches("lo?ash", "Lodash"));
+        assert!(glob_matches("*-*-*", "a-b-c"));
+        assert!(!glob_matches("@angular/*", "@types/node"));
+        assert!(!glob_matches("lodash?", "lodash"));
+    }
 }
@@ -0,0 +1,473 @@
+//! Output formats for command responses.
+//!
+//! Every response printed by a command implements [`Output`], which renders it in the
+//! format selected with the global `--format` argument. The table format is backed by
+//! [`Summarize`], the other formats by the response's `Serialize` implementation.
+
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use phylum_types::types::group::ListUserGroupsResponse;
+use phylum_types::types::job::{AllJobsStatusResponse, CancelJobResponse, JobStatusResponse};
+use phylum_types::types::package::{Package, PackageStatus, PackageStatusExtended, PackageType};
+use phylum_types::types::project::ProjectDetailsResponse;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::filter::{Filter, FilterPackage};
+use crate::report::ReportFormat;
+use crate::summarize::Summarize;
+use crate::types::PackageList;
+
+/// Formats for printing command responses.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Ndjson,
+    Yaml,
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            "yaml" => Ok(OutputFormat::Yaml),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => Err(anyhow!("Unsupported output format `{}`", s)),
+        }
+    }
+}
+
+/// The format selected with `--format`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Output(OutputFormat),
+    Report(ReportFormat),
+}
+
+impl Format {
+    /// Get the format selected with `--format`, or `default` if none was selected.
+    ///
+    /// The deprecated `--json` flag selects the JSON output format.
+    pub fn from_matches(matches: &clap::ArgMatches, default: Format) -> Result<Self> {
+        match matches.value_of("format") {
+            Some(format) => match OutputFormat::from_str(format) {
+                Ok(format) => Ok(Format::Output(format)),
+                Err(_) => Ok(Format::Report(ReportFormat::from_str(format)?)),
+            },
+            None if matches.is_present("json") => Ok(Format::Output(OutputFormat::Json)),
+            None => Ok(default),
+        }
+    }
+
+    /// Get the output format of a command which does not produce reports.
+    pub fn output(self) -> Result<OutputFormat> {
+        match self {
+            Format::Output(format) => Ok(format),
+            Format::Report(format) => Err(anyhow!(
+                "The `{}` format is not supported by this command",
+                format
+            )),
+        }
+    }
+}
+
+/// Get the output format of a command which does not produce reports, defaulting to a table.
+pub fn output_format(matches: &clap::ArgMatches) -> Result<OutputFormat> {
+    Format::from_matches(matches, Format::Output(OutputFormat::Table))?.output()
+}
+
+/// A command response which can be printed in every output format.
+pub trait Output: Serialize + Summarize {
+    /// The field holding the records of the response, which are printed one per line by the
+    /// line based formats.
+    ///
+    /// Lists have one record per item, other responses without a records field are a single
+    /// record.
+    const RECORDS: Option<&'static str> = None;
+
+    /// Serialize the response, leaving out the packages and issues excluded by `filter`.
+    fn to_value(&self, _filter: Option<&Filter>) -> Result<Value> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    /// Get the records of the serialized response.
+    fn records(&self, filter: Option<&Filter>) -> Result<Vec<Value>> {
+        let value = self.to_value(filter)?;
+        let records = match Self::RECORDS {
+            Some(field) => value.get(field).cloned().unwrap_or(Value::Null),
+            None => value,
+        };
+        match records {
+            Value::Array(records) => Ok(records),
+            Value::Null if Self::RECORDS.is_some() => Ok(Vec::new()),
+            record => Ok(vec![record]),
+        }
+    }
+
+    /// Write the response to stdout.
+    fn write_output(&self, format: OutputFormat, filter: Option<Filter>) -> Result<()> {
+        // Use write! as a workaround to avoid https://github.com/rust-lang/rust/issues/46016
+        //  when piping output to an external program
+        let mut stdout = io::stdout();
+        match format {
+            OutputFormat::Table => self.summarize(filter),
+            OutputFormat::Json => {
+                let value = self.to_value(filter.as_ref())?;
+                writeln!(stdout, "{}", serde_json::to_string_pretty(&value)?)?;
+            }
+            OutputFormat::Ndjson => {
+                for record in self.records(filter.as_ref())? {
+                    writeln!(stdout, "{}", serde_json::to_string(&record)?)?;
+                }
+            }
+            OutputFormat::Yaml => {
+                let value = self.to_value(filter.as_ref())?;
+                write!(stdout, "{}", serde_yaml::to_string(&value)?)?;
+            }
+            OutputFormat::Csv => write!(stdout, "{}", to_csv(&self.records(filter.as_ref())?))?,
+        }
+        Ok(())
+    }
+}
+
+impl<T> Output for Vec<T>
+where
+    T: Serialize,
+    Vec<T>: Summarize,
+{
+}
+
+impl Output for JobStatusResponse<PackageStatus> {
+    const RECORDS: Option<&'static str> = Some("packages");
+
+    fn to_value(&self, filter: Option<&Filter>) -> Result<Value> {
+        let mut value = serde_json::to_value(self)?;
+        let package_type = PackageType::from_str(&self.ecosystem).ok();
+        if let (Some(filter), Some(package_type), Some(packages)) =
+            (filter, &package_type, value.get_mut("packages"))
+        {
+            let job_packages = self
+                .packages
+                .iter()
+                .map(|status| JobPackage {
+                    package_type,
+                    status,
+                })
+                .collect::<Vec<_>>();
+            retain_packages(packages, filter, &job_packages);
+        }
+        Ok(value)
+    }
+}
+
+impl Output for JobStatusResponse<PackageStatusExtended> {
+    const RECORDS: Option<&'static str> = Some("packages");
+
+    fn to_value(&self, filter: Option<&Filter>) -> Result<Value> {
+        let mut value = serde_json::to_value(self)?;
+        if let (Some(filter), Some(packages)) = (filter, value.get_mut("packages")) {
+            retain_packages(packages, filter, &self.packages);
+        }
+        Ok(value)
+    }
+}
+
+impl Output for ProjectDetailsResponse {
+    const RECORDS: Option<&'static str> = Some("jobs");
+}
+
+impl Output for AllJobsStatusResponse {
+    const RECORDS: Option<&'static str> = Some("jobs");
+}
+
+impl Output for ListUserGroupsResponse {
+    const RECORDS: Option<&'static str> = Some("groups");
+}
+
+impl Output for PackageStatusExtended {
+    fn to_value(&self, filter: Option<&Filter>) -> Result<Value> {
+        let mut value = serde_json::to_value(self)?;
+        if let Some(filter) = filter {
+            retain_issues(&mut value, filter, self);
+        }
+        Ok(value)
+    }
+}
+
+impl Output for Package {
+    fn to_value(&self, filter: Option<&Filter>) -> Result<Value> {
+        let mut value = serde_json::to_value(self)?;
+        if let Some(filter) = filter {
+            retain_issues(&mut value, filter, self);
+        }
+        Ok(value)
+    }
+}
+
+impl Output for PackageList {
+    fn to_value(&self, filter: Option<&Filter>) -> Result<Value> {
+        let mut value = serde_json::to_value(self)?;
+        if let Some(filter) = filter {
+            retain_packages(&mut value, filter, &self.packages);
+        }
+        Ok(value)
+    }
+}
+
+impl Output for String {}
+impl Output for CancelJobResponse {}
+impl Output for PackageStatus {}
+
+/// A package which can be left out of serialized responses by a filter.
+trait FilteredPackage {
+    /// Check if the package is included by `filter`.
+    fn is_included(&self, filter: &Filter) -> bool;
+
+    /// Check which of the package's issues are included by `filter`, in order.
+    fn included_issues(&self, filter: &Filter) -> Vec<bool>;
+}
+
+impl FilteredPackage for PackageStatusExtended {
+    fn is_included(&self, filter: &Filter) -> bool {
+        filter.matches_package(&self.into())
+    }
+
+    fn included_issues(&self, filter: &Filter) -> Vec<bool> {
+        let package = FilterPackage::from(self);
+        self.issues
+            .iter()
+            .map(|issue| filter.matches_issue(&package, &issue.into()))
+            .collect()
+    }
+}
+
+impl FilteredPackage for Package {
+    fn is_included(&self, filter: &Filter) -> bool {
+        filter.matches_package(&self.into())
+    }
+
+    fn included_issues(&self, filter: &Filter) -> Vec<bool> {
+        let package = FilterPackage::from(self);
+        self.issues
+            .iter()
+            .map(|issue| filter.matches_issue(&package, &issue.into()))
+            .collect()
+    }
+}
+
+/// A package of a job without issues, whose ecosystem is only known by the job.
+struct JobPackage<'a> {
+    package_type: &'a PackageType,
+    status: &'a PackageStatus,
+}
+
+impl FilteredPackage for JobPackage<'_> {
+    fn is_included(&self, filter: &Filter) -> bool {
+        filter.matches_package(&FilterPackage {
+            package_type: self.package_type,
+            name: &self.status.name,
+            version: &self.status.version,
+            score: self.status.package_score,
+        })
+    }
+
+    fn included_issues(&self, _filter: &Filter) -> Vec<bool> {
+        Vec::new()
+    }
+}
+
+/// Remove the packages excluded by `filter` from a serialized list of `packages`, and the
+/// excluded issues from the remaining packages.
+fn retain_packages<P: FilteredPackage>(value: &mut Value, filter: &Filter, packages: &[P]) {
+    if let Value::Array(values) = value {
+        *values = std::mem::take(values)
+            .into_iter()
+            .zip(packages)
+            .filter(|(_, package)| package.is_included(filter))
+            .map(|(mut value, package)| {
+                retain_issues(&mut value, filter, package);
+                value
+            })
+            .collect();
+    }
+}
+
+/// Remove the issues excluded by `filter` from a serialized package.
+fn retain_issues(value: &mut Value, filter: &Filter, package: &impl FilteredPackage) {
+    if let Some(Value::Array(issues)) = value.get_mut("issues") {
+        let mut included = package.included_issues(filter).into_iter();
+        issues.retain(|_| included.next().unwrap_or(true));
+    }
+}
+
+/// Convert records to CSV.
+///
+/// Nested objects are flattened into columns with dotted names, and lists are written as
+/// JSON. The columns are the union of the fields of all records.
+fn to_csv(records: &[Value]) -> String {
+    let rows = records
+        .iter()
+        .map(|record| {
+            let mut row = Vec::new();
+            flatten(None, record, &mut row);
+            row
+        })
+        .collect::<Vec<_>>();
+
+    let mut columns: Vec<&str> = Vec::new();
+    for (column, _) in rows.iter().flatten() {
+        if !columns.contains(&column.as_str()) {
+            columns.push(column);
+        }
+    }
+
+    let mut csv = csv_line(columns.iter().copied());
+    for row in &rows {
+        let cells = columns.iter().map(|column| {
+            row.iter()
+                .find(|(name, _)| name == column)
+                .map_or("", |(_, value)| value.as_str())
+        });
+        csv.push_str(&csv_line(cells));
+    }
+    csv
+}
+
+/// Flatten a JSON value into pairs of column names and cell values.
+fn flatten(prefix: Option<&str>, value: &Value, row: &mut Vec<(String, String)>) {
+    let column = || prefix.unwrap_or("value").to_owned();
+    match value {
+        Value::Object(object) => flatten_object(prefix, object, row),
+        Value::Null => row.push((column(), String::new())),
+        Value::String(string) => row.push((column(), string.clone())),
+        Value::Array(_) | Value::Bool(_) | Value::Number(_) => {
+            row.push((column(), value.to_string()))
+        }
+    }
+}
+
+fn flatten_object(
+    prefix: Option<&str>,
+    object: &Map<String, Value>,
+    row: &mut Vec<(String, String)>,
+) {
+    for (key, value) in object {
+        let column = match prefix {
+            Some(prefix) => format!("{}.{}", prefix, key),
+            None => key.clone(),
+        };
+        flatten(Some(&column), value, row);
+    }
+}
+
+/// Format a line of CSV, quoting cells where necessary.
+fn csv_line<'a>(cells: impl Iterator<Item = &'a str>) -> String {
+    let cells = cells
+        .map(|cell| {
+            if cell.contains(&[',', '"', '\n', '\r'][..]) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.to_owned()
+            }
+        })
+        .collect::<Vec<_>>();
+    format!("{}\n", cells.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn csv_flattens_records() {
+        let records = vec![
+            json!({"name": "lodash", "scores": {"total": 0.9}, "tags": ["a", "b"]}),
+            json!({"name": "left-pad, \"old\"", "license": null}),
+        ];
+
+        assert_eq!(
+            to_csv(&records),
+            "name,scores.total,tags,license\n\
+            lodash,0.9,\"[\"\"a\"\",\"\"b\"\"]\",\n\
+            \"left-pad, \"\"old\"\"\",,,\n"
+        );
+    }
+
+    #[test]
+    fn list_records() {
+        let list = vec![String::from("a"), String::from("b")];
+        assert_eq!(list.records(None).unwrap(), vec![json!("a"), json!("b")]);
+        assert_eq!(
+            String::from("pong").records(None).unwrap(),
+            vec![json!("pong")]
+        );
+    }
+
+    #[test]
+    fn serialization_applies_filter() {
+        let job = crate::report::tests::job_fixture();
+        let filter = Filter::from_str("score<0.5 and domain=vuln").unwrap();
+
+        let records = job.records(Some(&filter)).unwrap();
+        let packages = records
+            .iter()
+            .map(|package| {
+                let issues = package["issues"].as_array().unwrap();
+                (package["name"].as_str().unwrap(), issues.len())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(packages, vec![("accepts", 1), ("body-parser", 0)]);
+        assert_eq!(records[0]["issues"][0]["domain"], "vulnerability");
+
+        let package = job.packages[0].to_value(Some(&filter)).unwrap();
+        assert_eq!(package["issues"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn serialization_filters_job_packages() {
+        let job = crate::report::tests::job_fixture();
+        let job: JobStatusResponse<PackageStatus> =
+            serde_json::from_value(serde_json::to_value(&job).unwrap()).unwrap();
+
+        let filter = Filter::from_str("score<0.5 and ecosystem=npm").unwrap();
+        let records = job.records(Some(&filter)).unwrap();
+        let names = records
+            .iter()
+            .map(|package| package["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["accepts", "body-parser"]);
+
+        let filter = Filter::from_str("ecosystem=pypi").unwrap();
+        assert!(job.records(Some(&filter)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_formats() {
+        let app = crate::app::app();
+
+        let matches = app
+            .clone()
+            .get_matches_from(&["phylum", "project", "--format", "yaml"]);
+        let matches = matches.subcommand_matches("project").unwrap();
+        assert_eq!(output_format(matches).unwrap(), OutputFormat::Yaml);
+
+        let matches = app.clone().get_matches_from(&["phylum", "history", "-j"]);
+        let matches = matches.subcommand_matches("history").unwrap();
+        assert_eq!(output_format(matches).unwrap(), OutputFormat::Json);
+
+        let matches = app.get_matches_from(&["phylum", "group", "list", "--format", "sarif"]);
+        let matches = matches.subcommand_matches("group").unwrap();
+        let matches = matches.subcommand_matches("list").unwrap();
+        assert!(output_format(matches).is_err());
+    }
+}
@@ -0,0 +1,344 @@
+//! Suppression of accepted issues with a `.phylumignore` file.
+//!
+//! The file is looked up next to the `.phylum_project` file, and lists the suppressed issues:
+//!
+//! ```yaml
+//! # Suppress one issue of a package version
+//! - package: accepts
+//!   version: 1.3.8
+//!   issue: Prototype pollution in accepts@1.3.8
+//!   reason: Not reachable from our code
+//!   expires: 2022-12-31
+//! # Suppress every issue of any version of a package
+//! - package: pkg:npm/left-pad
+//!   reason: Vendored and reviewed
+//! ```
+
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::NaiveDate;
+use phylum_types::types::job::{Action, JobStatusResponse};
+use phylum_types::types::package::{Issue, PackageStatusExtended, RiskType};
+use serde::Deserialize;
+
+use crate::purl::{self, Purl};
+
+pub const IGNORE_FILE: &str = ".phylumignore";
+
+/// An entry of the ignore file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IgnoreEntry {
+    /// The package name, or a package URL.
+    pub package: String,
+    pub version: Option<String>,
+    /// The issue title. Every issue of the package is suppressed without one.
+    pub issue: Option<String>,
+    pub reason: String,
+    pub expires: Option<NaiveDate>,
+}
+
+impl IgnoreEntry {
+    fn is_expired(&self, today: NaiveDate) -> bool {
+        self.expires.map_or(false, |expires| expires < today)
+    }
+
+    fn matches(&self, package: &PackageStatusExtended, issue: &Issue) -> bool {
+        let status = &package.basic_status;
+        let package_matches = if purl::is_purl(&self.package) {
+            Purl::from_str(&self.package).map_or(false, |purl| {
+                purl.matches(&package.package_type, &status.name, &status.version)
+            })
+        } else {
+            self.package == status.name
+        };
+
+        package_matches
+            && self.version.as_ref().map_or(true, |v| *v == status.version)
+            && self
+                .issue
+                .as_ref()
+                .map_or(true, |title| *title == issue.title)
+    }
+
+    fn describe(&self) -> String {
+        let mut description = self.package.clone();
+        if let Some(version) = &self.version {
+            description = format!("{}@{}", description, version);
+        }
+        if let Some(issue) = &self.issue {
+            description = format!("{} ({})", description, issue);
+        }
+        description
+    }
+}
+
+/// The contents of an ignore file.
+#[derive(Debug, Deserialize)]
+#[serde(transparent)]
+pub struct Ignore {
+    pub entries: Vec<IgnoreEntry>,
+}
+
+/// An issue hidden by the ignore file.
+#[derive(Debug)]
+pub struct SuppressedIssue {
+    /// The package, as `name@version`.
+    pub package: String,
+    pub issue: Issue,
+    pub reason: String,
+    pub expires: Option<NaiveDate>,
+}
+
+/// The issues removed from a job by the ignore file.
+#[derive(Debug, Default)]
+pub struct Suppression {
+    pub suppressed: Vec<SuppressedIssue>,
+    /// Entries which have expired, and no longer suppress issues.
+    pub expired: Vec<String>,
+    /// The risk domains with suppressed issues of each package, by package index.
+    domains: Vec<(usize, RiskType)>,
+}
+
+impl Ignore {
+    /// Load an ignore file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Unable to read ignore file `{}`", path.display()))?;
+
+        // A file with only comments has no entries.
+        let is_empty = contents
+            .lines()
+            .all(|line| line.trim().is_empty() || line.trim_start().starts_with('#'));
+        if is_empty {
+            return Ok(Ignore {
+                entries: Vec::new(),
+            });
+        }
+
+        let ignore: Ignore = serde_yaml::from_str(&contents)
+            .with_context(|| format!("Invalid ignore file `{}`", path.display()))?;
+
+        for entry in &ignore.entries {
+            if purl::is_purl(&entry.package) {
+                Purl::from_str(&entry.package).with_context(|| {
+                    format!("Invalid package in ignore file: {}", entry.package)
+                })?;
+            }
+            if entry.reason.trim().is_empty() {
+                return Err(anyhow!(
+                    "Missing reason for suppressing {}",
+                    entry.describe()
+                ));
+            }
+        }
+
+        Ok(ignore)
+    }
+
+    /// Remove the suppressed issues from a job.
+    pub fn suppress(
+        &self,
+        job: &mut JobStatusResponse<PackageStatusExtended>,
+        today: NaiveDate,
+    ) -> Suppression {
+        let mut suppression = Suppression {
+            expired: self.expired(today),
+            ..Suppression::default()
+        };
+
+        for (index, package) in job.packages.iter_mut().enumerate() {
+            let issues = std::mem::take(&mut package.issues);
+            for issue in issues {
+                let entry = self
+                    .entries
+                    .iter()
+                    .filter(|entry| !entry.is_expired(today))
+                    .find(|entry| entry.matches(package, &issue));
+
+                match entry {
+                    Some(entry) => {
+                        suppression.domains.push((index, issue.domain.into()));
+                        suppression.suppressed.push(SuppressedIssue {
+                            package: format!(
+                                "{}@{}",
+                                package.basic_status.name, package.basic_status.version
+                            ),
+                            issue,
+                            reason: entry.reason.clone(),
+                            expires: entry.expires,
+                        });
+                    }
+                    None => package.issues.push(issue),
+                }
+            }
+        }
+
+        suppression
+    }
+
+    /// Describe the entries which have expired, and no longer suppress issues.
+    pub fn expired(&self, today: NaiveDate) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.is_expired(today))
+            .map(|entry| {
+                let expires = entry.expires.map(|date| date.to_string());
+                format!(
+                    "{}, expired {}",
+                    entry.describe(),
+                    expires.unwrap_or_default()
+                )
+            })
+            .collect()
+    }
+}
+
+impl Suppression {
+    pub fn is_empty(&self) -> bool {
+        self.suppressed.is_empty()
+    }
+
+    /// Get the action of a job once its suppressed issues are removed.
+    ///
+    /// A failing job passes if every score below the project thresholds belongs to a package
+    /// whose issues in that risk domain are all suppressed.
+    pub fn action(&self, job: &JobStatusResponse<PackageStatusExtended>) -> Action {
+        if job.pass {
+            return Action::None;
+        }
+
+        let thresholds = &job.thresholds;
+        let domains = [
+            (RiskType::AuthorsRisk, "author", thresholds.author),
+            (
+                RiskType::EngineeringRisk,
+                "engineering",
+                thresholds.engineering,
+            ),
+            (RiskType::LicenseRisk, "license", thresholds.license),
+            (
+                RiskType::MaliciousCodeRisk,
+                "malicious_code",
+                thresholds.malicious,
+            ),
+            (
+                RiskType::Vulnerabilities,
+                "vulnerability",
+                thresholds.vulnerability,
+            ),
+        ];
+
+        let mut failures = 0;
+        for (index, package) in job.packages.iter().enumerate() {
+            let suppressed_in = |domain: Option<&RiskType>| {
+                self.domains
+                    .iter()
+                    .any(|(i, d)| *i == index && domain.map_or(true, |domain| d == domain))
+            };
+            let remaining_in = |domain: Option<&RiskType>| {
+                package.issues.iter().any(|issue| {
+                    let issue_domain: RiskType = issue.domain.into();
+                    domain.map_or(true, |domain| issue_domain == *domain)
+                })
+            };
+
+            let score = package.basic_status.package_score.unwrap_or(1.0);
+            if score < f64::from(thresholds.total) {
+                failures += 1;
+                if !suppressed_in(None) || remaining_in(None) {
+                    return job.action.clone();
+                }
+            }
+
+            for (domain, key, threshold) in &domains {
+                let score = package.risk_vectors.get(*key).copied().unwrap_or(1.0);
+                if score < f64::from(*threshold) {
+                    failures += 1;
+                    if !suppressed_in(Some(domain)) || remaining_in(Some(domain)) {
+                        return job.action.clone();
+                    }
+                }
+            }
+        }
+
+        // Keep the server's verdict if it can't be explained by the package scores.
+        if failures == 0 {
+            job.action.clone()
+        } else {
+            Action::None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::tests::job_fixture;
+
+    fn ignore(yaml: &str) -> Ignore {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn suppresses_matching_issues() {
+        let ignore = ignore(
+            r#"
+            - package: accepts
+              version: 1.3.8
+              issue: Prototype pollution in accepts@1.3.8
+              reason: Not reachable
+              expires: 2022-12-31
+            - package: pkg:npm/express
+              reason: Reviewed
+              expires: 2022-01-01
+            "#,
+        );
+        let mut job = job_fixture();
+
+        let suppression = ignore.suppress(&mut job, date("2022-06-01"));
+        assert_eq!(suppression.suppressed.len(), 1);
+        assert_eq!(suppression.suppressed[0].package, "accepts@1.3.8");
+        assert_eq!(suppression.suppressed[0].reason, "Not reachable");
+        assert_eq!(job.packages[0].issues.len(), 1);
+        assert_eq!(job.packages[1].issues.len(), 1);
+        assert_eq!(
+            suppression.expired,
+            vec!["pkg:npm/express, expired 2022-01-01"]
+        );
+    }
+
+    #[test]
+    fn suppressed_failures_pass() {
+        // The fixture fails on the total score and vulnerability risk of `accepts`.
+        let mut job = job_fixture();
+        let suppression = ignore(
+            "[{package: accepts, issue: Commercial license risk in accepts@1.3.8, reason: Ok}]",
+        )
+        .suppress(&mut job, date("2022-06-01"));
+        assert!(matches!(suppression.action(&job), Action::Break));
+
+        let mut job = job_fixture();
+        let suppression =
+            ignore("[{package: accepts, reason: Ok}]").suppress(&mut job, date("2022-06-01"));
+        assert_eq!(suppression.suppressed.len(), 2);
+        assert!(matches!(suppression.action(&job), Action::None));
+    }
+
+    #[test]
+    fn requires_reasons() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(IGNORE_FILE);
+        std::fs::write(&path, "[{package: accepts, reason: ' '}]").unwrap();
+        assert!(Ignore::load(&path).is_err());
+        std::fs::write(&path, "[{package: accepts, reason: Accepted}]").unwrap();
+        assert!(Ignore::load(&path).is_ok());
+    }
+}
@@ -5,10 +5,16 @@ pub mod commands;
 pub mod config;
 pub mod deno;
 pub mod filter;
+pub mod format;
+pub mod ignore;
 pub mod lockfiles;
+pub mod policy;
 pub mod print;
 pub mod prompt;
+pub mod purl;
 pub mod render;
+pub mod report;
+pub mod sbom;
 pub mod summarize;
 #[cfg(test)]
 mod test;
@@ -69,8 +69,8 @@ impl Parse for CSProj {
         Ok(parsed.into())
     }
 
-    fn package_type(&self) -> PackageType {
-        PackageType::Nuget
+    fn package_type(&self) -> Option<PackageType> {
+        Some(PackageType::Nuget)
     }
 }
 
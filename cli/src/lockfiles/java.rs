@@ -20,8 +20,8 @@ impl Parse for GradleLock {
         Ok(entries)
     }
 
-    fn package_type(&self) -> PackageType {
-        PackageType::Maven
+    fn package_type(&self) -> Option<PackageType> {
+        Some(PackageType::Maven)
     }
 }
 
@@ -122,15 +122,15 @@ impl Parse for Pom {
                             &dep.artifact_id.clone().unwrap_or_default()
                         ),
                         version: s.into(),
-                        package_type: self.package_type(),
+                        package_type: PackageType::Maven,
                     })
                 })
             })
             .collect::<Result<Vec<_>, _>>()
     }
 
-    fn package_type(&self) -> PackageType {
-        PackageType::Maven
+    fn package_type(&self) -> Option<PackageType> {
+        Some(PackageType::Maven)
     }
 }
 
@@ -2,11 +2,11 @@ use anyhow::{anyhow, Context};
 use nom::error::convert_error;
 use nom::Finish;
 use phylum_types::types::package::{PackageDescriptor, PackageType};
-use serde_json::Value as JsonValue;
+use serde_json::{Map, Value as JsonValue};
 use serde_yaml::Value as YamlValue;
 
 use super::parsers::yarn;
-use crate::lockfiles::{Parse, ParseResult};
+use crate::lockfiles::{DependencyResult, Parse, ParseResult};
 
 pub struct PackageLock;
 pub struct YarnLock;
@@ -26,7 +26,7 @@ impl Parse for PackageLock {
             let pkg = PackageDescriptor {
                 name,
                 version,
-                package_type: self.package_type(),
+                package_type: PackageType::Npm,
             };
             Ok(pkg)
         };
@@ -52,8 +52,79 @@ impl Parse for PackageLock {
         }
     }
 
-    fn package_type(&self) -> PackageType {
-        PackageType::Npm
+    /// Parses the dependencies of each package from `package-lock.json` files
+    ///
+    /// Only the `packages` field of lockfile v2 and later records resolved dependencies.
+    fn parse_dependencies(&self, data: &str) -> DependencyResult {
+        let parsed: JsonValue = serde_json::from_str(data)?;
+        let packages = match parsed.get("packages").and_then(|v| v.as_object()) {
+            Some(packages) => packages,
+            None => return Ok(Vec::new()),
+        };
+
+        let descriptor = |path: &str| {
+            let version = packages.get(path)?.get("version")?.as_str()?;
+            let module = path
+                .rsplit_once("node_modules/")
+                .map(|(_, k)| k)
+                .unwrap_or(path);
+            Some(PackageDescriptor {
+                name: module.to_owned(),
+                version: version.to_owned(),
+                package_type: PackageType::Npm,
+            })
+        };
+
+        let mut relationships = Vec::new();
+        for (path, package) in packages.iter().filter(|(k, _v)| !k.is_empty()) {
+            let parent = match descriptor(path) {
+                Some(parent) => parent,
+                None => continue,
+            };
+
+            let dependencies = package.get("dependencies").and_then(|v| v.as_object());
+            for name in dependencies.into_iter().flat_map(|deps| deps.keys()) {
+                let dependency = resolve_module(packages, path, name).and_then(descriptor);
+                if let Some(dependency) = dependency {
+                    relationships.push((parent.clone(), dependency));
+                }
+            }
+        }
+
+        Ok(relationships)
+    }
+
+    fn package_type(&self) -> Option<PackageType> {
+        Some(PackageType::Npm)
+    }
+}
+
+/// Find the path of the module `name` required from the module at `path`.
+///
+/// Like node, this looks for the closest `node_modules` directory containing the module.
+fn resolve_module<'a>(
+    packages: &'a Map<String, JsonValue>,
+    path: &str,
+    name: &str,
+) -> Option<&'a str> {
+    let mut base = path;
+    loop {
+        let candidate = if base.is_empty() {
+            format!("node_modules/{}", name)
+        } else {
+            format!("{}/node_modules/{}", base, name)
+        };
+
+        if let Some((key, _)) = packages.get_key_value(&candidate) {
+            return Some(key);
+        }
+
+        if base.is_empty() {
+            return None;
+        }
+        base = base
+            .rsplit_once("/node_modules/")
+            .map_or("", |(parent, _)| parent);
     }
 }
 
@@ -151,7 +222,7 @@ impl Parse for YarnLock {
             };
 
             packages.push(PackageDescriptor {
-                package_type: self.package_type(),
+                package_type: PackageType::Npm,
                 name: name.to_owned(),
                 version,
             });
@@ -160,8 +231,8 @@ impl Parse for YarnLock {
         Ok(packages)
     }
 
-    fn package_type(&self) -> PackageType {
-        PackageType::Npm
+    fn package_type(&self) -> Option<PackageType> {
+        Some(PackageType::Npm)
     }
 }
 
@@ -211,6 +282,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn lock_parse_dependencies() {
+        let data = std::fs::read_to_string("tests/fixtures/package-lock.json").unwrap();
+        let relationships = PackageLock.parse_dependencies(&data).unwrap();
+
+        let express = PackageDescriptor {
+            name: "express".into(),
+            version: "4.17.3".into(),
+            package_type: PackageType::Npm,
+        };
+        let accepts = PackageDescriptor {
+            name: "accepts".into(),
+            version: "1.3.8".into(),
+            package_type: PackageType::Npm,
+        };
+        assert!(relationships.contains(&(express, accepts)));
+
+        let data = std::fs::read_to_string("tests/fixtures/package-lock-v6.json").unwrap();
+        assert!(PackageLock.parse_dependencies(&data).unwrap().is_empty());
+    }
+
     #[test]
     fn lock_parse_yarn_v1_simple() {
         // This file contains only one package and that package has no dependencies.
@@ -10,15 +10,20 @@ mod javascript;
 mod parsers;
 mod python;
 mod ruby;
+mod sbom;
 
 pub use csharp::CSProj;
 pub use java::{GradleLock, Pom};
 pub use javascript::{PackageLock, YarnLock};
 pub use python::{PipFile, Poetry, PyRequirements};
 pub use ruby::GemLock;
+pub use sbom::{CycloneDx, Spdx};
 
 pub type ParseResult = anyhow::Result<Vec<PackageDescriptor>>;
 
+/// Dependency relationships, as pairs of a package and one of its direct dependencies.
+pub type DependencyResult = anyhow::Result<Vec<(PackageDescriptor, PackageDescriptor)>>;
+
 pub trait Parse {
     /// Parse from a string
     fn parse(&self, data: &str) -> ParseResult;
@@ -32,6 +37,16 @@ pub trait Parse {
         self.parse(&data)
     }
 
+    /// Parse the relationships between packages from a string
+    ///
+    /// Lockfiles which do not record them have no relationships.
+    fn parse_dependencies(&self, _data: &str) -> DependencyResult {
+        Ok(Vec::new())
+    }
+
     /// Indicate the type of packages parsed by this parser
-    fn package_type(&self) -> PackageType;
+    ///
+    /// Parsers which aren't limited to one ecosystem, like those of SBOMs, have no type and
+    /// set the type of each package instead.
+    fn package_type(&self) -> Option<PackageType>;
 }
@@ -24,8 +24,8 @@ impl Parse for PyRequirements {
         Ok(entries)
     }
 
-    fn package_type(&self) -> PackageType {
-        PackageType::PyPi
+    fn package_type(&self) -> Option<PackageType> {
+        Some(PackageType::PyPi)
     }
 }
 
@@ -70,7 +70,7 @@ impl Parse for PipFile {
                         Ok(PackageDescriptor {
                             name: k.as_str().to_string().to_lowercase(),
                             version: v.replace("==", "").trim().to_string(),
-                            package_type: self.package_type(),
+                            package_type: PackageType::PyPi,
                         })
                     }),
                     None => {
@@ -82,8 +82,8 @@ impl Parse for PipFile {
             .collect::<Result<Vec<_>, _>>()
     }
 
-    fn package_type(&self) -> PackageType {
-        PackageType::PyPi
+    fn package_type(&self) -> Option<PackageType> {
+        Some(PackageType::PyPi)
     }
 }
 
@@ -114,8 +114,8 @@ impl Parse for Poetry {
             .collect())
     }
 
-    fn package_type(&self) -> PackageType {
-        PackageType::PyPi
+    fn package_type(&self) -> Option<PackageType> {
+        Some(PackageType::PyPi)
     }
 }
 
@@ -18,8 +18,8 @@ impl Parse for GemLock {
         Ok(entries)
     }
 
-    fn package_type(&self) -> PackageType {
-        PackageType::RubyGems
+    fn package_type(&self) -> Option<PackageType> {
+        Some(PackageType::RubyGems)
     }
 }
 
@@ -0,0 +1,236 @@
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use phylum_types::types::package::{PackageDescriptor, PackageType};
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+
+use crate::lockfiles::{Parse, ParseResult};
+use crate::purl::Purl;
+
+pub struct CycloneDx;
+pub struct Spdx;
+
+#[derive(Debug, Deserialize)]
+struct XmlComponents {
+    #[serde(rename = "component", default)]
+    components: Vec<XmlComponent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct XmlComponent {
+    #[serde(default)]
+    purl: Option<String>,
+    #[serde(default)]
+    components: Option<XmlComponents>,
+}
+
+#[derive(Debug, Deserialize)]
+struct XmlBom {
+    #[serde(default)]
+    components: Option<XmlComponents>,
+}
+
+impl Parse for CycloneDx {
+    /// Parses CycloneDX JSON or XML SBOMs into a vec of packages
+    fn parse(&self, data: &str) -> ParseResult {
+        let mut purls = Vec::new();
+
+        if data.trim_start().starts_with('<') {
+            if !data.contains("http://cyclonedx.org/schema/bom/") {
+                return Err(anyhow!("Failed to find CycloneDX namespace"));
+            }
+
+            fn collect(components: &XmlComponents, purls: &mut Vec<String>) {
+                for component in &components.components {
+                    purls.extend(component.purl.iter().map(|purl| purl.trim().to_owned()));
+                    if let Some(components) = &component.components {
+                        collect(components, purls);
+                    }
+                }
+            }
+
+            let bom: XmlBom = serde_xml_rs::from_str(data)?;
+            if let Some(components) = &bom.components {
+                collect(components, &mut purls);
+            }
+        } else {
+            let bom: JsonValue = serde_json::from_str(data)?;
+            if bom.get("bomFormat").and_then(|v| v.as_str()) != Some("CycloneDX") {
+                return Err(anyhow!("Failed to find CycloneDX bomFormat"));
+            }
+
+            fn collect(components: &JsonValue, purls: &mut Vec<String>) {
+                for component in components.as_array().into_iter().flatten() {
+                    if let Some(purl) = component.get("purl").and_then(|v| v.as_str()) {
+                        purls.push(purl.to_owned());
+                    }
+                    if let Some(components) = component.get("components") {
+                        collect(components, purls);
+                    }
+                }
+            }
+
+            if let Some(components) = bom.get("components") {
+                collect(components, &mut purls);
+            }
+        }
+
+        Ok(packages_from_purls(purls))
+    }
+
+    /// SBOMs are not limited to one ecosystem, the type of each package is taken from its purl
+    fn package_type(&self) -> Option<PackageType> {
+        None
+    }
+}
+
+impl Parse for Spdx {
+    /// Parses SPDX JSON or tag-value SBOMs into a vec of packages
+    fn parse(&self, data: &str) -> ParseResult {
+        let mut purls = Vec::new();
+
+        if data.trim_start().starts_with('{') {
+            let document: JsonValue = serde_json::from_str(data)?;
+            if document.get("spdxVersion").is_none() {
+                return Err(anyhow!("Failed to find SPDX version"));
+            }
+
+            let packages = document.get("packages").and_then(|v| v.as_array());
+            for package in packages.into_iter().flatten() {
+                let external_refs = package.get("externalRefs").and_then(|v| v.as_array());
+                purls.extend(
+                    external_refs
+                        .into_iter()
+                        .flatten()
+                        .filter(|r| r.get("referenceType").and_then(|v| v.as_str()) == Some("purl"))
+                        .filter_map(|r| r.get("referenceLocator").and_then(|v| v.as_str()))
+                        .map(String::from),
+                );
+            }
+        } else {
+            if !data.lines().any(|line| line.starts_with("SPDXVersion:")) {
+                return Err(anyhow!("Failed to find SPDX version"));
+            }
+
+            // External references look like `ExternalRef: PACKAGE-MANAGER purl <purl>`.
+            for line in data.lines() {
+                let reference = match line.trim().strip_prefix("ExternalRef:") {
+                    Some(reference) => reference,
+                    None => continue,
+                };
+
+                let mut fields = reference.split_whitespace();
+                if let (Some(_category), Some("purl"), Some(locator)) =
+                    (fields.next(), fields.next(), fields.next())
+                {
+                    purls.push(locator.to_owned());
+                }
+            }
+        }
+
+        Ok(packages_from_purls(purls))
+    }
+
+    /// SBOMs are not limited to one ecosystem, the type of each package is taken from its purl
+    fn package_type(&self) -> Option<PackageType> {
+        None
+    }
+}
+
+/// Convert package URLs to packages, skipping those of unsupported ecosystems.
+fn packages_from_purls(purls: Vec<String>) -> Vec<PackageDescriptor> {
+    purls
+        .into_iter()
+        .filter_map(
+            |purl| match Purl::from_str(&purl).and_then(PackageDescriptor::try_from) {
+                Ok(package) => Some(package),
+                Err(err) => {
+                    log::warn!("Skipping SBOM component: {}", err);
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(pkgs: &[PackageDescriptor]) -> Vec<(&str, &str, &PackageType)> {
+        pkgs.iter()
+            .map(|pkg| (pkg.name.as_str(), pkg.version.as_str(), &pkg.package_type))
+            .collect()
+    }
+
+    #[test]
+    fn parse_cyclonedx_json() {
+        let pkgs = CycloneDx
+            .parse_file("tests/fixtures/vendor.cdx.json")
+            .unwrap();
+
+        assert_eq!(
+            summary(&pkgs),
+            vec![
+                ("@angular/core", "12.1.0", &PackageType::Npm),
+                ("rxjs", "7.5.5", &PackageType::Npm),
+                ("requests", "2.27.1", &PackageType::PyPi),
+                ("org.yaml:snakeyaml", "1.30", &PackageType::Maven),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_cyclonedx_xml() {
+        let pkgs = CycloneDx
+            .parse_file("tests/fixtures/vendor.cdx.xml")
+            .unwrap();
+
+        assert_eq!(
+            summary(&pkgs),
+            vec![
+                ("@angular/core", "12.1.0", &PackageType::Npm),
+                ("rxjs", "7.5.5", &PackageType::Npm),
+                ("requests", "2.27.1", &PackageType::PyPi),
+                ("org.yaml:snakeyaml", "1.30", &PackageType::Maven),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_spdx_json() {
+        let pkgs = Spdx.parse_file("tests/fixtures/vendor.spdx.json").unwrap();
+
+        assert_eq!(
+            summary(&pkgs),
+            vec![
+                ("requests", "2.27.1", &PackageType::PyPi),
+                ("idna", "3.3", &PackageType::PyPi),
+                ("rails", "7.0.3", &PackageType::RubyGems),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_spdx_tag_value() {
+        let pkgs = Spdx.parse_file("tests/fixtures/vendor.spdx").unwrap();
+
+        assert_eq!(
+            summary(&pkgs),
+            vec![
+                ("requests", "2.27.1", &PackageType::PyPi),
+                ("idna", "3.3", &PackageType::PyPi),
+                ("rails", "7.0.3", &PackageType::RubyGems),
+            ]
+        );
+    }
+
+    #[test]
+    fn reject_other_formats() {
+        let data = std::fs::read_to_string("tests/fixtures/package-lock.json").unwrap();
+        assert!(CycloneDx.parse(&data).is_err());
+        assert!(Spdx.parse(&data).is_err());
+    }
+}
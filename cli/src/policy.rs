@@ -0,0 +1,506 @@
+//! Local policies evaluated against job results.
+//!
+//! A policy is read from a `.phylum-policy.yaml` file next to the `.phylum_project` file:
+//!
+//! ```yaml
+//! rules:
+//!   # Fail on any malicious code issue of at least medium severity
+//!   - issues: domain=mal and severity>=medium
+//!   # Fail on strong copyleft licenses
+//!   - licenses: [GPL-3.0*, AGPL*]
+//!   # Warn about packages published less than 30 days ago
+//!   - min_age_days: 30
+//!     action: warn
+//! allow:
+//!   - package: pkg:npm/lodash@4.17.20
+//!     issue: Prototype Pollution in lodash
+//!     expires: 2022-12-31
+//!     reason: Not reachable from our code
+//! ```
+//!
+//! Rules without an `action` break the build.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::NaiveDate;
+use phylum_types::types::job::{Action, JobStatusResponse};
+use phylum_types::types::package::PackageStatusExtended;
+use serde::Deserialize;
+
+use crate::config::parse_config;
+use crate::filter::{glob_matches, Filter, FilterIssue, FilterPackage};
+use crate::purl::Purl;
+
+pub const POLICY_FILE: &str = ".phylum-policy.yaml";
+
+/// The contents of a policy file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyConfig {
+    #[serde(default)]
+    pub rules: Vec<RuleConfig>,
+    #[serde(default)]
+    pub allow: Vec<AllowConfig>,
+}
+
+/// A rule of a policy file. Exactly one of `issues`, `licenses` and `min_age_days` is set.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleConfig {
+    pub name: Option<String>,
+    pub issues: Option<String>,
+    pub licenses: Option<Vec<String>>,
+    pub min_age_days: Option<u32>,
+    #[serde(default = "default_action")]
+    pub action: Action,
+}
+
+fn default_action() -> Action {
+    Action::Break
+}
+
+/// An allowlist entry of a policy file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AllowConfig {
+    pub package: String,
+    pub issue: Option<String>,
+    pub expires: Option<NaiveDate>,
+    pub reason: Option<String>,
+}
+
+/// A validated policy.
+#[derive(Debug)]
+pub struct Policy {
+    rules: Vec<Rule>,
+    allow: Vec<Allow>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    name: String,
+    condition: Condition,
+    action: Action,
+}
+
+#[derive(Debug)]
+enum Condition {
+    /// Issues matching a filter.
+    Issues(Filter),
+    /// Licenses matching any of the globs.
+    Licenses(Vec<String>),
+    /// Packages published less than this many days ago.
+    MinAge(u32),
+}
+
+/// Violations of a package, or of one of its issues, which are accepted.
+#[derive(Debug)]
+pub struct Allow {
+    package: Purl,
+    issue: Option<String>,
+    expires: Option<NaiveDate>,
+    reason: Option<String>,
+}
+
+impl Allow {
+    fn is_expired(&self, today: NaiveDate) -> bool {
+        self.expires.map_or(false, |expires| expires < today)
+    }
+}
+
+impl fmt::Display for Allow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.package)?;
+        if let Some(issue) = &self.issue {
+            write!(f, " ({})", issue)?;
+        }
+        if let Some(expires) = &self.expires {
+            write!(f, ", expires {}", expires)?;
+        }
+        Ok(())
+    }
+}
+
+/// A rule violation.
+#[derive(Debug)]
+pub struct Violation {
+    /// The package, as `name@version`.
+    pub package: String,
+    /// The name of the violated rule.
+    pub rule: String,
+    /// What violated the rule.
+    pub detail: String,
+    pub action: Action,
+    /// Why the violation is accepted, if it is allowlisted.
+    pub allowed: Option<String>,
+}
+
+/// The result of evaluating a policy against a job.
+#[derive(Debug, Default)]
+pub struct Evaluation {
+    pub violations: Vec<Violation>,
+    /// Allowlist entries which have expired.
+    pub expired: Vec<String>,
+}
+
+impl Evaluation {
+    /// Get the most severe action of the violations which are not allowlisted.
+    pub fn action(&self) -> Action {
+        self.violations
+            .iter()
+            .filter(|violation| violation.allowed.is_none())
+            .fold(Action::None, |action, violation| {
+                most_severe(action, violation.action.clone())
+            })
+    }
+}
+
+/// Get the more severe of two actions.
+pub fn most_severe(a: Action, b: Action) -> Action {
+    match (a, b) {
+        (Action::Break, _) | (_, Action::Break) => Action::Break,
+        (Action::Warn, _) | (_, Action::Warn) => Action::Warn,
+        _ => Action::None,
+    }
+}
+
+impl TryFrom<PolicyConfig> for Policy {
+    type Error = anyhow::Error;
+
+    fn try_from(config: PolicyConfig) -> Result<Self> {
+        let rules = config
+            .rules
+            .into_iter()
+            .enumerate()
+            .map(|(index, rule)| {
+                Rule::try_from(rule).with_context(|| format!("Invalid policy rule {}", index + 1))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let allow = config
+            .allow
+            .into_iter()
+            .map(|allow| {
+                let package = Purl::from_str(&allow.package).with_context(|| {
+                    format!("Invalid package in policy allowlist: {}", allow.package)
+                })?;
+                Ok(Allow {
+                    package,
+                    issue: allow.issue,
+                    expires: allow.expires,
+                    reason: allow.reason,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Policy { rules, allow })
+    }
+}
+
+impl TryFrom<RuleConfig> for Rule {
+    type Error = anyhow::Error;
+
+    fn try_from(rule: RuleConfig) -> Result<Self> {
+        let (condition, default_name) = match (rule.issues, rule.licenses, rule.min_age_days) {
+            (Some(issues), None, None) => {
+                let name = format!("issues matching `{}`", issues);
+                (Condition::Issues(Filter::from_str(&issues)?), name)
+            }
+            (None, Some(licenses), None) => {
+                let name = format!("blocked licenses {}", licenses.join(", "));
+                (Condition::Licenses(licenses), name)
+            }
+            (None, None, Some(days)) => {
+                let name = format!("packages younger than {} days", days);
+                (Condition::MinAge(days), name)
+            }
+            _ => {
+                return Err(anyhow!(
+                    "Expected exactly one of `issues`, `licenses` and `min_age_days`"
+                ))
+            }
+        };
+
+        Ok(Rule {
+            name: rule.name.unwrap_or(default_name),
+            condition,
+            action: rule.action,
+        })
+    }
+}
+
+impl Policy {
+    /// Load a policy file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let config: PolicyConfig = parse_config(path)
+            .with_context(|| format!("Unable to read policy file `{}`", path.display()))?;
+        Policy::try_from(config)
+            .with_context(|| format!("Invalid policy file `{}`", path.display()))
+    }
+
+    /// Get the packages of a job whose publication date is needed by the age rules.
+    ///
+    /// Packages allowlisted as a whole can't break age rules, so their dates are not needed.
+    pub fn age_checked_packages<'a>(
+        &'a self,
+        job: &'a JobStatusResponse<PackageStatusExtended>,
+        today: NaiveDate,
+    ) -> impl Iterator<Item = &'a PackageStatusExtended> + 'a {
+        let has_age_rules = self
+            .rules
+            .iter()
+            .any(|rule| matches!(rule.condition, Condition::MinAge(_)));
+
+        job.packages
+            .iter()
+            .filter(move |_| has_age_rules)
+            .filter(move |package| {
+                let status = &package.basic_status;
+                !self.allow.iter().any(|allow| {
+                    allow.issue.is_none()
+                        && !allow.is_expired(today)
+                        && allow.package.matches(
+                            &package.package_type,
+                            &status.name,
+                            &status.version,
+                        )
+                })
+            })
+    }
+
+    /// Evaluate the policy against a job.
+    ///
+    /// `published` maps the `(name, version)` of packages to their publication date. Packages
+    /// without a publication date are not checked by age rules.
+    pub fn evaluate(
+        &self,
+        job: &JobStatusResponse<PackageStatusExtended>,
+        published: &HashMap<(String, String), NaiveDate>,
+        today: NaiveDate,
+    ) -> Evaluation {
+        let mut evaluation = Evaluation::default();
+
+        for allow in &self.allow {
+            if allow.is_expired(today) {
+                evaluation.expired.push(allow.to_string());
+            }
+        }
+
+        for package in &job.packages {
+            let status = &package.basic_status;
+            let filter_package = FilterPackage::from(package);
+            let allowed = |issue: Option<&str>| {
+                self.allow
+                    .iter()
+                    .filter(|allow| !allow.is_expired(today))
+                    .find(|allow| {
+                        allow
+                            .package
+                            .matches(&package.package_type, &status.name, &status.version)
+                            && allow
+                                .issue
+                                .as_deref()
+                                .map_or(true, |title| Some(title) == issue)
+                    })
+                    .map(|allow| allow.reason.clone().unwrap_or_else(|| "allowlisted".into()))
+            };
+            let mut violation = |rule: &Rule, detail: String, issue: Option<&str>| {
+                evaluation.violations.push(Violation {
+                    package: format!("{}@{}", status.name, status.version),
+                    rule: rule.name.clone(),
+                    detail,
+                    action: rule.action.clone(),
+                    allowed: allowed(issue),
+                });
+            };
+
+            for rule in &self.rules {
+                match &rule.condition {
+                    Condition::Issues(filter) => {
+                        for issue in &package.issues {
+                            if filter.matches_issue(&filter_package, &FilterIssue::from(issue)) {
+                                let detail = format!("[{}] {}", issue.severity, issue.title);
+                                violation(rule, detail, Some(&issue.title));
+                            }
+                        }
+                    }
+                    Condition::Licenses(globs) => {
+                        if let Some(license) = status
+                            .license
+                            .as_deref()
+                            .filter(|license| license_matches(globs, license))
+                        {
+                            violation(rule, format!("license {}", license), None);
+                        }
+                    }
+                    Condition::MinAge(days) => {
+                        let key = (status.name.clone(), status.version.clone());
+                        if let Some(date) = published.get(&key) {
+                            let age = (today - *date).num_days();
+                            if age < i64::from(*days) {
+                                violation(rule, format!("published {} days ago", age), None);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        evaluation
+    }
+}
+
+/// Check if any license of an SPDX license expression matches any of the globs.
+fn license_matches(globs: &[String], license: &str) -> bool {
+    license
+        .split(|c: char| c.is_whitespace() || c == '(' || c == ')')
+        .filter(|id| !id.is_empty() && !matches!(*id, "AND" | "OR" | "WITH"))
+        .any(|id| globs.iter().any(|glob| glob_matches(glob, id)))
+}
+
+/// Parse the publication date of a package, as returned by the package details.
+pub fn parse_publication_date(date: &str) -> Option<NaiveDate> {
+    date.get(..10)
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::tests::job_fixture;
+
+    fn policy(yaml: &str) -> Policy {
+        let config: PolicyConfig = serde_yaml::from_str(yaml).unwrap();
+        Policy::try_from(config).unwrap()
+    }
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn issue_and_license_rules() {
+        let policy = policy(
+            r#"
+            rules:
+              - issues: domain=vuln and severity>=medium
+              - licenses: [GPL-3.0*, AGPL*]
+                action: warn
+            "#,
+        );
+        let mut job = job_fixture();
+        job.packages[1].basic_status.license = Some("MIT OR GPL-3.0-only".into());
+
+        let evaluation = policy.evaluate(&job, &HashMap::new(), date("2022-06-01"));
+        let violations = evaluation
+            .violations
+            .iter()
+            .map(|violation| (violation.package.as_str(), violation.action.clone()))
+            .collect::<Vec<_>>();
+        assert!(matches!(
+            violations.as_slice(),
+            [
+                ("accepts@1.3.8", Action::Break),
+                ("express@4.17.3", Action::Warn)
+            ]
+        ));
+        assert!(matches!(evaluation.action(), Action::Break));
+    }
+
+    #[test]
+    fn age_rules() {
+        let policy = policy("rules: [{min_age_days: 30, action: warn}]");
+        let job = job_fixture();
+        let mut published = HashMap::new();
+        published.insert(("accepts".into(), "1.3.8".into()), date("2022-05-20"));
+        published.insert(("express".into(), "4.17.3".into()), date("2022-01-01"));
+
+        let evaluation = policy.evaluate(&job, &published, date("2022-06-01"));
+        assert_eq!(evaluation.violations.len(), 1);
+        assert_eq!(evaluation.violations[0].package, "accepts@1.3.8");
+        assert_eq!(evaluation.violations[0].detail, "published 12 days ago");
+        assert!(matches!(evaluation.action(), Action::Warn));
+
+        let checked = policy.age_checked_packages(&job, date("2022-06-01"));
+        assert_eq!(checked.count(), job.packages.len());
+    }
+
+    #[test]
+    fn age_checks_skip_allowlisted_packages() {
+        let allowlisted = policy(
+            r#"
+            rules:
+              - min_age_days: 30
+            allow:
+              - package: pkg:npm/accepts
+                reason: Reviewed
+            "#,
+        );
+        let job = job_fixture();
+
+        let checked = allowlisted
+            .age_checked_packages(&job, date("2022-06-01"))
+            .map(|package| package.basic_status.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(checked, vec!["express", "body-parser"]);
+
+        let issues_only = policy("rules: [{issues: domain=vuln}]");
+        let checked = issues_only.age_checked_packages(&job, date("2022-06-01"));
+        assert_eq!(checked.count(), 0);
+    }
+
+    #[test]
+    fn allowlist_with_expiry() {
+        let policy = policy(
+            r#"
+            rules:
+              - issues: domain=vuln
+            allow:
+              - package: pkg:npm/accepts@1.3.8
+                expires: 2022-12-31
+                reason: Not reachable
+            "#,
+        );
+        let job = job_fixture();
+
+        let evaluation = policy.evaluate(&job, &HashMap::new(), date("2022-06-01"));
+        assert_eq!(evaluation.violations.len(), 1);
+        assert_eq!(
+            evaluation.violations[0].allowed.as_deref(),
+            Some("Not reachable")
+        );
+        assert!(evaluation.expired.is_empty());
+        assert!(matches!(evaluation.action(), Action::None));
+
+        let evaluation = policy.evaluate(&job, &HashMap::new(), date("2023-01-01"));
+        assert!(evaluation.violations[0].allowed.is_none());
+        assert_eq!(evaluation.expired.len(), 1);
+        assert!(matches!(evaluation.action(), Action::Break));
+    }
+
+    #[test]
+    fn invalid_rules() {
+        let config: PolicyConfig = serde_yaml::from_str("rules: [{action: warn}]").unwrap();
+        assert!(Policy::try_from(config).is_err());
+
+        let config: PolicyConfig =
+            serde_yaml::from_str("rules: [{issues: \"domain=typo\"}]").unwrap();
+        assert!(Policy::try_from(config).is_err());
+
+        assert!(serde_yaml::from_str::<PolicyConfig>("rule: []").is_err());
+    }
+
+    #[test]
+    fn publication_dates() {
+        assert_eq!(
+            parse_publication_date("2022-05-20T12:30:00Z"),
+            Some(date("2022-05-20"))
+        );
+        assert_eq!(parse_publication_date("unknown"), None);
+    }
+}
@@ -1,20 +1,28 @@
 use std::borrow::Cow;
-use std::io::{self, Write};
+use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use ansi_term::Color::{Blue, Cyan};
+use ansi_term::{ANSIString, Style};
 use clap::Command;
-use prettytable::format;
-use serde::Serialize;
+use prettytable::{format, Table};
 use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use crate::api::PhylumApiError;
 use crate::filter::Filter;
-use crate::summarize::Summarize;
+use crate::format::{Output, OutputFormat};
+
+/// Terminal width used when it can't be detected, e.g. when output is redirected.
+const DEFAULT_TERMINAL_WIDTH: usize = 120;
+
+static COLOR: AtomicBool = AtomicBool::new(true);
 
 #[macro_export]
 macro_rules! print_user_success {
     ($($tts:tt)*) => {{
-        eprint!("✅ ");
+        if $crate::print::color_enabled() {
+            eprint!("✅ ");
+        }
         eprintln!($($tts)*);
     }}
 }
@@ -22,7 +30,9 @@ macro_rules! print_user_success {
 #[macro_export]
 macro_rules! print_user_warning {
     ($($tts:tt)*) => {{
-        eprint!("⚠️  ");
+        if $crate::print::color_enabled() {
+            eprint!("⚠️  ");
+        }
         eprintln!($($tts)*);
     }}
 }
@@ -30,38 +40,81 @@ macro_rules! print_user_warning {
 #[macro_export]
 macro_rules! print_user_failure {
     ($($tts:tt)*) => {{
-        eprint!("❗ ");
+        if $crate::print::color_enabled() {
+            eprint!("❗ ");
+        }
         eprintln!($($tts)*);
     }}
 }
 
+/// Decide whether colors and emoji are used for output.
+///
+/// Colors are disabled by `--no-color`, by a non-empty `NO_COLOR` environment variable
+/// (<https://no-color.org>), or when stdout or stderr is not a terminal.
+pub fn init_color(no_color: bool) {
+    let no_color_env = env::var_os("NO_COLOR").map_or(false, |value| !value.is_empty());
+    let is_tty = atty::is(atty::Stream::Stdout) && atty::is(atty::Stream::Stderr);
+    set_color(!no_color && !no_color_env && is_tty);
+}
+
+pub fn set_color(enabled: bool) {
+    COLOR.store(enabled, Ordering::Relaxed);
+}
+
+pub fn color_enabled() -> bool {
+    COLOR.load(Ordering::Relaxed)
+}
+
+/// Apply a style to some text, unless colors are disabled.
+pub fn paint<'a, I>(style: impl Into<Style>, text: I) -> ANSIString<'a>
+where
+    I: Into<Cow<'a, str>>,
+{
+    if color_enabled() {
+        style.into().paint(text)
+    } else {
+        Style::new().paint(text)
+    }
+}
+
+/// Print a table to stdout, with its cell styles only if colors are enabled.
+pub fn print_table(table: &Table) {
+    if color_enabled() {
+        table.printstd();
+    } else {
+        print!("{}", table);
+    }
+}
+
+/// Get the width of the terminal in columns.
+///
+/// Falls back to the `COLUMNS` environment variable, and then to a fixed width, when stdout
+/// is not a terminal.
+pub fn terminal_width() -> usize {
+    if let Some((terminal_size::Width(width), _)) = terminal_size::terminal_size() {
+        return width.into();
+    }
+
+    env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .filter(|columns| *columns > 0)
+        .unwrap_or(DEFAULT_TERMINAL_WIDTH)
+}
+
 pub fn print_response<T>(
     resp: &Result<T, PhylumApiError>,
-    pretty_print: bool,
+    format: OutputFormat,
     filter: Option<Filter>,
 ) where
-    T: std::fmt::Debug + Serialize + Summarize,
+    T: std::fmt::Debug + Output,
 {
     log::debug!("==> {:?}", resp);
 
     match resp {
         Ok(resp) => {
-            if pretty_print {
-                resp.summarize(filter);
-            } else {
-                // Use write! as a workaround to avoid https://github.com/rust-lang/rust/issues/46016
-                //  when piping output to an external program
-                let mut stdout = io::stdout();
-                write!(
-                    &mut stdout,
-                    "{}",
-                    serde_json::to_string_pretty(&resp).unwrap_or_else(|e| {
-                        log::error!("Failed to serialize json response: {}", e);
-                        "".to_string()
-                    })
-                )
+            resp.write_output(format, filter)
                 .unwrap_or_else(|e| log::debug!("Failed writing to stdout: {}", e));
-            }
         }
         Err(err) => {
             print_user_failure!("Response error:\n{}", err);
@@ -73,12 +126,12 @@ pub fn print_response<T>(
 pub fn print_update_message() {
     eprintln!(
         "---------------- {} ----------------\n",
-        Cyan.paint("Update Available")
+        paint(Cyan, "Update Available")
     );
     eprintln!("A new version of the Phylum CLI is available. Run");
     eprintln!(
         "\n\t{}\n\nto update to the latest version!\n",
-        Blue.paint("phylum update")
+        paint(Blue, "phylum update")
     );
     eprintln!("{:-^50}\n\n", "");
 }
@@ -4,6 +4,8 @@ use dialoguer::{Input, Select};
 
 use phylum_types::types::user_settings::Threshold;
 
+use crate::print::paint;
+
 /// Project thresholds which cannot be disabled.
 const ALWAYS_ENABLED_THRESHOLDS: [&str; 1] = ["total project"];
 
@@ -13,7 +15,7 @@ pub fn prompt_threshold(name: &str) -> Result<Threshold, std::io::Error> {
     let threshold = Input::with_theme(&ColorfulTheme::default())
         .with_prompt(format!(
             "{} Threshold",
-            format_args!("{}", White.paint(name.to_uppercase()))
+            format_args!("{}", paint(White, name.to_uppercase()))
         ))
         .validate_with(|input: &String| -> Result<(), String> {
             if input.eq_ignore_ascii_case("disabled") {
@@ -39,7 +41,7 @@ pub fn prompt_threshold(name: &str) -> Result<Threshold, std::io::Error> {
     if threshold.eq_ignore_ascii_case("disabled") {
         println!(
             "\nDisabling {} risk domain",
-            format_args!("{}", White.paint(name))
+            format_args!("{}", paint(White, name))
         );
         println!("\n-----\n");
 
@@ -52,7 +54,7 @@ pub fn prompt_threshold(name: &str) -> Result<Threshold, std::io::Error> {
 
     println!(
         "\nWhat should happen if a score falls below the {} threshold?\n",
-        format_args!("{}", White.paint(name))
+        format_args!("{}", paint(White, name))
     );
 
     let items = vec![
@@ -68,7 +70,11 @@ pub fn prompt_threshold(name: &str) -> Result<Threshold, std::io::Error> {
         .interact()
         .unwrap();
     let action = items[selection];
-    println!("✔ {} Action · {}", White.paint(name.to_uppercase()), action);
+    println!(
+        "✔ {} Action · {}",
+        paint(White, name.to_uppercase()),
+        action
+    );
     println!("\n-----\n");
 
     let threshold = threshold.parse::<i32>().unwrap() as f32 / 100.;
@@ -0,0 +1,359 @@
+//! Package URL (purl) support.
+//!
+//! See https://github.com/package-url/purl-spec for the specification.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use phylum_types::types::package::{PackageDescriptor, PackageType};
+
+/// Characters which are percent-encoded in purl components.
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'.')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'~')
+    .remove(b':')
+    .remove(b'+');
+
+/// Characters which are percent-encoded in Phylum API path segments.
+const PATH_SEGMENT: &AsciiSet = &COMPONENT.remove(b'@');
+
+/// A package URL, limited to the ecosystems supported by Phylum.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Purl {
+    pub package_type: PackageType,
+    pub namespace: Option<String>,
+    pub name: String,
+    pub version: Option<String>,
+}
+
+/// Check if a string looks like a package URL.
+pub fn is_purl(input: &str) -> bool {
+    input
+        .get(..4)
+        .map_or(false, |scheme| scheme.eq_ignore_ascii_case("pkg:"))
+}
+
+impl FromStr for Purl {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let purl = input.trim();
+        if !is_purl(purl) {
+            return Err(anyhow!("{}: package URL must start with `pkg:`", input));
+        }
+        let purl = &purl[4..];
+
+        // Qualifiers and subpaths are not relevant for package identification.
+        let purl = purl.trim_start_matches('/');
+        let purl = purl.split_once('#').map_or(purl, |(purl, _)| purl);
+        let purl = purl.split_once('?').map_or(purl, |(purl, _)| purl);
+
+        let (purl_type, path) = purl
+            .split_once('/')
+            .ok_or_else(|| anyhow!("{}: package URL is missing a name", input))?;
+        let package_type = package_type(purl_type)
+            .ok_or_else(|| anyhow!("{}: unsupported package type `{}`", input, purl_type))?;
+
+        // The version separator is the last `@` after the last `/`, which allows
+        // unencoded npm scopes like `pkg:npm/@scope/name`.
+        let path = path.trim_end_matches('/');
+        let name_start = path.rfind('/').map_or(0, |index| index + 1);
+        let (path, version) = match path[name_start..].rfind('@') {
+            Some(index) => {
+                let index = name_start + index;
+                (&path[..index], Some(decode(&path[index + 1..])?))
+            }
+            None => (path, None),
+        };
+
+        let (namespace, mut name) = match path.rsplit_once('/') {
+            Some((namespace, name)) => (Some(decode(namespace)?), decode(name)?),
+            None => (None, decode(path)?),
+        };
+
+        // PyPI names are case-insensitive and treat `_` and `-` as equivalent.
+        if package_type == PackageType::PyPi {
+            name = normalize_pypi(&name);
+        }
+
+        if name.is_empty() {
+            return Err(anyhow!("{}: package URL is missing a name", input));
+        }
+
+        Ok(Purl {
+            package_type,
+            namespace: namespace.filter(|namespace| !namespace.is_empty()),
+            name,
+            version: version.filter(|version| !version.is_empty()),
+        })
+    }
+}
+
+impl Purl {
+    /// Check if a package matches this purl.
+    ///
+    /// Purls without a version match every version of the package.
+    pub fn matches(&self, package_type: &PackageType, name: &str, version: &str) -> bool {
+        let purl = Purl::from(&PackageDescriptor {
+            name: name.to_owned(),
+            version: version.to_owned(),
+            package_type: package_type.to_owned(),
+        });
+
+        self.package_type == purl.package_type
+            && self.namespace == purl.namespace
+            && self.name == purl.name
+            && self
+                .version
+                .as_ref()
+                .map_or(true, |v| Some(v) == purl.version.as_ref())
+    }
+}
+
+impl fmt::Display for Purl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "pkg:{}/", purl_type(&self.package_type))?;
+
+        if let Some(namespace) = &self.namespace {
+            for segment in namespace.split('/') {
+                write!(f, "{}/", utf8_percent_encode(segment, COMPONENT))?;
+            }
+        }
+
+        write!(f, "{}", utf8_percent_encode(&self.name, COMPONENT))?;
+
+        if let Some(version) = &self.version {
+            write!(f, "@{}", utf8_percent_encode(version, COMPONENT))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<&PackageDescriptor> for Purl {
+    fn from(pkg: &PackageDescriptor) -> Self {
+        let (namespace, name) = match pkg.package_type {
+            PackageType::Npm if pkg.name.starts_with('@') => match pkg.name.split_once('/') {
+                Some((namespace, name)) => (Some(namespace.to_owned()), name.to_owned()),
+                None => (None, pkg.name.clone()),
+            },
+            PackageType::Maven => match pkg.name.split_once(':') {
+                Some((group, artifact)) => (Some(group.to_owned()), artifact.to_owned()),
+                None => (None, pkg.name.clone()),
+            },
+            PackageType::PyPi => (None, normalize_pypi(&pkg.name)),
+            _ => (None, pkg.name.clone()),
+        };
+
+        Purl {
+            package_type: pkg.package_type.to_owned(),
+            namespace,
+            name,
+            version: Some(pkg.version.clone()),
+        }
+    }
+}
+
+impl TryFrom<Purl> for PackageDescriptor {
+    type Error = anyhow::Error;
+
+    fn try_from(purl: Purl) -> Result<Self> {
+        let version = purl
+            .version
+            .ok_or_else(|| anyhow!("package URL for `{}` is missing a version", purl.name))?;
+
+        let name = match (&purl.package_type, purl.namespace) {
+            (PackageType::Npm, Some(namespace)) => format!("{}/{}", namespace, purl.name),
+            (PackageType::Maven, Some(namespace)) => format!("{}:{}", namespace, purl.name),
+            (_, _) => purl.name,
+        };
+
+        Ok(PackageDescriptor {
+            name,
+            version,
+            package_type: purl.package_type,
+        })
+    }
+}
+
+/// Get the package type for a purl type.
+fn package_type(purl_type: &str) -> Option<PackageType> {
+    match purl_type.to_lowercase().as_str() {
+        "npm" => Some(PackageType::Npm),
+        "gem" => Some(PackageType::RubyGems),
+        "pypi" => Some(PackageType::PyPi),
+        "maven" => Some(PackageType::Maven),
+        "nuget" => Some(PackageType::Nuget),
+        _ => None,
+    }
+}
+
+/// Get the purl type for a package type.
+fn purl_type(package_type: &PackageType) -> &'static str {
+    match package_type {
+        PackageType::Npm => "npm",
+        PackageType::RubyGems => "gem",
+        PackageType::PyPi => "pypi",
+        PackageType::Maven => "maven",
+        PackageType::Nuget => "nuget",
+    }
+}
+
+/// Normalize a PyPI package name.
+fn normalize_pypi(name: &str) -> String {
+    name.to_lowercase().replace('_', "-")
+}
+
+/// Encode a package name or version as a segment of a Phylum API path.
+///
+/// The API expects `/` in names, like the separator of npm scopes, to be replaced by `~`.
+pub fn encode_path_segment(segment: &str) -> String {
+    utf8_percent_encode(&segment.replace('/', "~"), PATH_SEGMENT).to_string()
+}
+
+/// Percent-decode a purl component.
+fn decode(component: &str) -> Result<String> {
+    Ok(percent_decode_str(component).decode_utf8()?.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_scoped_npm() {
+        let purl = Purl::from_str("pkg:npm/%40angular/core@12.1.0").unwrap();
+        assert_eq!(purl.package_type, PackageType::Npm);
+        assert_eq!(purl.namespace.as_deref(), Some("@angular"));
+        assert_eq!(purl.name, "core");
+        assert_eq!(purl.version.as_deref(), Some("12.1.0"));
+
+        let unencoded = Purl::from_str("pkg:npm/@angular/core@12.1.0").unwrap();
+        assert_eq!(purl, unencoded);
+    }
+
+    #[test]
+    fn parse_without_version() {
+        let purl = Purl::from_str("pkg:npm/@angular/core").unwrap();
+        assert_eq!(purl.namespace.as_deref(), Some("@angular"));
+        assert_eq!(purl.name, "core");
+        assert_eq!(purl.version, None);
+    }
+
+    #[test]
+    fn parse_ignores_qualifiers() {
+        let purl =
+            Purl::from_str("pkg:maven/org.apache.commons/io@1.3.4?packaging=jar#src").unwrap();
+        let pkg = PackageDescriptor::try_from(purl).unwrap();
+        assert_eq!(pkg.name, "org.apache.commons:io");
+        assert_eq!(pkg.version, "1.3.4");
+        assert_eq!(pkg.package_type, PackageType::Maven);
+    }
+
+    #[test]
+    fn format_scoped_npm() {
+        let pkg = PackageDescriptor {
+            name: "@angular/core".into(),
+            version: "12.1.0".into(),
+            package_type: PackageType::Npm,
+        };
+        assert_eq!(
+            Purl::from(&pkg).to_string(),
+            "pkg:npm/%40angular/core@12.1.0"
+        );
+    }
+
+    #[test]
+    fn roundtrip_all_ecosystems() {
+        let packages = [
+            (
+                "lodash",
+                "4.17.21",
+                PackageType::Npm,
+                "pkg:npm/lodash@4.17.21",
+            ),
+            (
+                "@types/node",
+                "17.0.0",
+                PackageType::Npm,
+                "pkg:npm/%40types/node@17.0.0",
+            ),
+            (
+                "rails",
+                "7.0.3",
+                PackageType::RubyGems,
+                "pkg:gem/rails@7.0.3",
+            ),
+            (
+                "requests",
+                "2.27.1",
+                PackageType::PyPi,
+                "pkg:pypi/requests@2.27.1",
+            ),
+            (
+                "org.apache.commons:commons-lang3",
+                "3.12.0",
+                PackageType::Maven,
+                "pkg:maven/org.apache.commons/commons-lang3@3.12.0",
+            ),
+            (
+                "Newtonsoft.Json",
+                "13.0.1",
+                PackageType::Nuget,
+                "pkg:nuget/Newtonsoft.Json@13.0.1",
+            ),
+        ];
+
+        for (name, version, package_type, expected) in packages {
+            let pkg = PackageDescriptor {
+                name: name.into(),
+                version: version.into(),
+                package_type,
+            };
+
+            let purl = Purl::from(&pkg);
+            assert_eq!(purl.to_string(), expected);
+
+            let parsed = PackageDescriptor::try_from(Purl::from_str(expected).unwrap()).unwrap();
+            assert_eq!(parsed.name, pkg.name);
+            assert_eq!(parsed.version, pkg.version);
+            assert_eq!(parsed.package_type, pkg.package_type);
+        }
+    }
+
+    #[test]
+    fn matches_package() {
+        let purl = Purl::from_str("pkg:npm/%40angular/core").unwrap();
+        assert!(purl.matches(&PackageType::Npm, "@angular/core", "12.1.0"));
+        assert!(!purl.matches(&PackageType::Npm, "@angular/cli", "12.1.0"));
+
+        let purl = Purl::from_str("pkg:pypi/Django@4.0.5").unwrap();
+        assert!(purl.matches(&PackageType::PyPi, "django", "4.0.5"));
+        assert!(!purl.matches(&PackageType::PyPi, "django", "4.0.4"));
+    }
+
+    #[test]
+    fn parse_rejects_invalid() {
+        assert!(Purl::from_str("npm/react@16.13.1").is_err());
+        assert!(Purl::from_str("pkg:cargo/serde@1.0.0").is_err());
+        assert!(Purl::from_str("pkg:npm/").is_err());
+
+        let purl = Purl::from_str("pkg:gem/rails").unwrap();
+        assert!(PackageDescriptor::try_from(purl).is_err());
+    }
+
+    #[test]
+    fn encodes_path_segments() {
+        assert_eq!(encode_path_segment("@angular/core"), "@angular~core");
+        assert_eq!(
+            encode_path_segment("org.yaml:snakeyaml"),
+            "org.yaml:snakeyaml"
+        );
+        assert_eq!(encode_path_segment("a b?#"), "a%20b%3F%23");
+    }
+}
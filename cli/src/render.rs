@@ -7,8 +7,14 @@ use phylum_types::types::job::*;
 use phylum_types::types::package::*;
 use phylum_types::types::project::*;
 use prettytable::*;
+use unicode_width::UnicodeWidthStr;
 
-use crate::print::{self, table_format};
+use crate::ignore::Suppression;
+use crate::print::{self, paint, table_format};
+use crate::types::{PackageColumn, PackageList};
+
+/// Narrowest width a column is truncated to, however small the terminal is.
+pub(crate) const MIN_COLUMN_WIDTH: usize = 16;
 
 pub trait Renderable {
     fn render(&self) -> String;
@@ -40,8 +46,8 @@ impl Renderable for String {
 
 impl Renderable for ProjectSummaryResponse {
     fn render(&self) -> String {
-        let name = print::truncate(&self.name, 28);
-        format!("{:<37} {}", White.paint(name).to_string(), self.id)
+        let name = format!("{:<28}", print::truncate(&self.name, 28));
+        format!("{} {}", paint(White, name), self.id)
     }
 }
 
@@ -66,11 +72,11 @@ fn threshold_to_str(n: f32) -> String {
 
 impl Renderable for ProjectDetailsResponse {
     fn render(&self) -> String {
-        let title_score = format!("{}", Blue.paint("Score"));
-        let title_passfail = format!("{}", Blue.paint("P/F"));
-        let title_label = format!("{}", Blue.paint("Label"));
-        let title_job_id = format!("{}", Blue.paint("Job ID"));
-        let title_datetime = format!("{}", Blue.paint("Datetime"));
+        let title_score = paint(Blue, format!("{:<7}", "Score"));
+        let title_passfail = paint(Blue, format!("{:<11}", "P/F"));
+        let title_label = paint(Blue, format!("{:<41}", "Label"));
+        let title_job_id = paint(Blue, format!("{:<36}", "Job ID"));
+        let title_datetime = paint(Blue, "Datetime");
 
         let threshold_total = threshold_to_str(self.thresholds.total);
         let threshold_malicious = threshold_to_str(self.thresholds.malicious);
@@ -123,7 +129,7 @@ impl Renderable for ProjectDetailsResponse {
         renderer.push_str(format!("Last {} jobs from project history\n", self.jobs.len()).as_str());
         renderer.push_str(
             format!(
-                "{:<16}{:<20}{:<50}{:<45}   {}\n",
+                "{}{}{}{}   {}\n",
                 title_score, title_passfail, title_label, title_job_id, title_datetime
             )
             .as_str(),
@@ -131,23 +137,20 @@ impl Renderable for ProjectDetailsResponse {
 
         for job in self.jobs.iter() {
             let score = format!("{}", (job.score * 100.0) as u32);
-            let mut colored_score = format!("{}", Green.paint(&score));
-            let mut msg = format!("{}", Green.paint("PASS"));
-
-            if job.num_incomplete > 0 {
-                msg = format!("{}", Yellow.paint("INCOMPLETE"));
-                colored_score = format!("{}", Red.paint(&score));
+            let (score_color, state_color, state) = if job.num_incomplete > 0 {
+                (Red, Yellow, "INCOMPLETE")
             } else if !job.pass {
-                msg = format!("{}", Red.paint("FAIL"));
-                colored_score = format!("{}", Red.paint(&score));
-            }
+                (Red, Red, "FAIL")
+            } else {
+                (Green, Green, "PASS")
+            };
+            // Pad before coloring, since the color codes have no width.
+            let colored_score = paint(score_color, format!("{:<7}", score));
+            let msg = paint(state_color, format!("{:<11}", state));
 
             renderer.push_str(
                 format!(
-                    // Differs from the title format slightly. The colored values
-                    // add control characters, which introduce a base offset of 9
-                    // zero-width chracters.
-                    "{:<16}{:<20}{:<41}{:<40}   {}\n",
+                    "{}{}{:<41}{:<40}   {}\n",
                     colored_score, msg, job.label, job.job_id, job.date,
                 )
                 .as_str(),
@@ -174,24 +177,24 @@ impl Renderable for Vec<JobDescriptor> {
     fn render(&self) -> String {
         let mut jobs = String::new();
         for (i, job) in self.iter().enumerate() {
-            let mut state = Green.paint("PASS").to_string();
+            let mut state = paint(Green, "PASS").to_string();
             let score = format!("{:>3}", (job.score * 100.0) as u32);
-            let mut colored_score = Green.paint(&score).to_string();
+            let mut colored_score = paint(Green, &score).to_string();
             let project_name = print::truncate(&job.project, 39);
-            let colored_project_name = White.bold().paint(project_name).to_string();
+            let colored_project_name = paint(White.bold(), format!("{:<39}", project_name));
 
             if job.num_incomplete > 0 {
-                colored_score = format!("{}", Yellow.paint(&score));
-                state = format!("{}", Yellow.paint("INCOMPLETE"));
+                colored_score = format!("{}", paint(Yellow, &score));
+                state = format!("{}", paint(Yellow, "INCOMPLETE"));
             } else if !job.pass {
-                colored_score = format!("{}", Red.paint(&score));
-                state = format!("{}", Red.paint("FAIL"));
+                colored_score = format!("{}", paint(Red, &score));
+                state = format!("{}", paint(Red, "FAIL"));
             }
 
             let first_line = format!(
                 "{}",
                 format_args!(
-                    "{:<3} {} {} {:<50} {:<30} {:<40} {:>32}\n",
+                    "{:<3} {} {} {} {:<30} {:<40} {:>32}\n",
                     (i + 1),
                     colored_score,
                     state,
@@ -298,6 +301,101 @@ impl Renderable for Package {
     }
 }
 
+impl Renderable for PackageList {
+    fn render(&self) -> String {
+        let rows = self
+            .packages
+            .iter()
+            .map(|package| {
+                self.columns
+                    .iter()
+                    .map(|column| package_cell(package, *column))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        // Give the package names whatever space the other columns leave.
+        const PADDING: usize = 3;
+        let other_width: usize = self
+            .columns
+            .iter()
+            .enumerate()
+            .filter(|(_, column)| **column != PackageColumn::Name)
+            .map(|(i, column)| {
+                let width = rows.iter().map(|row| row[i].width()).max().unwrap_or(0);
+                width.max(column.title().len()) + PADDING
+            })
+            .sum();
+        let name_width = print::terminal_width()
+            .saturating_sub(other_width + PADDING)
+            .max(MIN_COLUMN_WIDTH);
+
+        let mut table = Table::new();
+        table.set_titles(Row::new(
+            self.columns
+                .iter()
+                .map(|column| Cell::new(column.title()).with_style(Attr::Bold))
+                .collect(),
+        ));
+        for row in rows {
+            let cells = self
+                .columns
+                .iter()
+                .zip(row)
+                .map(|(column, text)| match column {
+                    PackageColumn::Name => Cell::new(&print::truncate(&text, name_width)),
+                    PackageColumn::Version | PackageColumn::Ecosystem | PackageColumn::License => {
+                        Cell::new(&text)
+                    }
+                    _ => Cell::new_align(&text, format::Alignment::RIGHT),
+                })
+                .collect();
+            table.add_row(Row::new(cells));
+        }
+        table.set_format(table_format(0, PADDING));
+        table.to_string()
+    }
+}
+
+/// Get the text of a package list cell.
+fn package_cell(package: &Package, column: PackageColumn) -> String {
+    let score = |score: f32| (100.0 * score).round().to_string();
+    match column {
+        PackageColumn::Name => package.name.clone(),
+        PackageColumn::Version => package.version.clone(),
+        PackageColumn::Ecosystem => package.registry.render(),
+        PackageColumn::Score => score(package.risk_scores.total),
+        PackageColumn::Author => score(package.risk_scores.author),
+        PackageColumn::Engineering => score(package.risk_scores.engineering),
+        PackageColumn::LicenseRisk => score(package.risk_scores.license),
+        PackageColumn::Malicious => score(package.risk_scores.malicious_code),
+        PackageColumn::Vulnerability => score(package.risk_scores.vulnerability),
+        PackageColumn::License => package
+            .license
+            .clone()
+            .unwrap_or_else(|| String::from("Unknown")),
+        PackageColumn::Issues => package.issues.len().to_string(),
+    }
+}
+
+impl Renderable for Suppression {
+    fn render(&self) -> String {
+        let package_width = (print::terminal_width() / 4).max(MIN_COLUMN_WIDTH);
+        let mut table = table!([b => "Package", "Issue", "Reason", "Expires"]);
+        for suppressed in &self.suppressed {
+            let expires = suppressed.expires.map(|date| date.to_string());
+            table.add_row(row![
+                print::truncate(&suppressed.package, package_width),
+                format!("[{}] {}", suppressed.issue.severity, suppressed.issue.title),
+                suppressed.reason,
+                expires.as_deref().unwrap_or("Never")
+            ]);
+        }
+        table.set_format(table_format(3, 1));
+        format!(" Suppressed Issues:\n{}", table)
+    }
+}
+
 impl Renderable for CancelJobResponse {
     fn render(&self) -> String {
         format!("Request canceled: {}", self.msg)
@@ -323,11 +421,11 @@ impl Renderable for ProjectThresholds {
 
 impl Renderable for ListUserGroupsResponse {
     fn render(&self) -> String {
-        let mut table = Blue
-            .paint(
-                "Group Name                 Owner                                Creation Time\n",
-            )
-            .to_string();
+        let mut table = paint(
+            Blue,
+            "Group Name                 Owner                                Creation Time\n",
+        )
+        .to_string();
         for group in &self.groups {
             let _ = write!(table, "{:<25}  ", print::truncate(&group.group_name, 25));
             let _ = write!(table, "{:<35}  ", print::truncate(&group.owner_email, 35));
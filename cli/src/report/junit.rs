@@ -0,0 +1,206 @@
+//! JUnit XML report output.
+//!
+//! Each package of a job is a test case, which fails for every issue it has. Packages which
+//! have not been analyzed yet are skipped.
+
+use std::fmt::Write;
+
+use phylum_types::types::job::JobStatusResponse;
+use phylum_types::types::package::PackageStatusExtended;
+
+use crate::filter::{Filter, FilterIssue, FilterPackage};
+use crate::report::escape_xml;
+
+/// A JUnit test suite with the packages of a single job.
+#[derive(Debug)]
+pub struct Junit {
+    name: String,
+    test_cases: Vec<TestCase>,
+}
+
+#[derive(Debug)]
+struct TestCase {
+    class_name: String,
+    name: String,
+    skipped: bool,
+    failures: Vec<Failure>,
+}
+
+#[derive(Debug)]
+struct Failure {
+    message: String,
+    failure_type: String,
+    text: String,
+}
+
+impl Junit {
+    /// Build a JUnit test suite for a job.
+    ///
+    /// Packages and issues excluded by `filter` are left out, like in the table output.
+    pub fn new(job: &JobStatusResponse<PackageStatusExtended>, filter: Option<&Filter>) -> Self {
+        let mut test_cases = Vec::new();
+
+        for package in &job.packages {
+            let status = &package.basic_status;
+            if let Some(filter) = filter {
+                if !filter.matches_package(&FilterPackage::from(package)) {
+                    continue;
+                }
+            }
+
+            let failures = package
+                .issues
+                .iter()
+                .filter(|issue| {
+                    filter.map_or(true, |filter| {
+                        filter.matches_issue(
+                            &FilterPackage::from(package),
+                            &FilterIssue::from(*issue),
+                        )
+                    })
+                })
+                .map(|issue| Failure {
+                    message: issue.description.clone(),
+                    failure_type: issue.domain.to_string(),
+                    text: format!("[{}] {}", issue.severity, issue.title),
+                })
+                .collect();
+
+            test_cases.push(TestCase {
+                class_name: package.package_type.to_string(),
+                name: format!("{}@{}", status.name, status.version),
+                skipped: is_incomplete(package),
+                failures,
+            });
+        }
+
+        Junit {
+            name: job.project_name.clone(),
+            test_cases,
+        }
+    }
+
+    /// Serialize the test suite as JUnit XML.
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::new();
+        let _ = self.write_xml(&mut xml);
+        xml
+    }
+
+    fn write_xml(&self, xml: &mut String) -> std::fmt::Result {
+        let tests = self.test_cases.len();
+        let failures = self
+            .test_cases
+            .iter()
+            .filter(|c| !c.failures.is_empty())
+            .count();
+        let skipped = self.test_cases.iter().filter(|c| c.skipped).count();
+
+        writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            xml,
+            r#"<testsuites name="phylum" tests="{}" failures="{}" skipped="{}">"#,
+            tests, failures, skipped
+        )?;
+        writeln!(
+            xml,
+            r#"  <testsuite name="{}" tests="{}" failures="{}" skipped="{}">"#,
+            escape_xml(&self.name),
+            tests,
+            failures,
+            skipped
+        )?;
+
+        for test_case in &self.test_cases {
+            let attributes = format!(
+                r#"classname="{}" name="{}""#,
+                escape_xml(&test_case.class_name),
+                escape_xml(&test_case.name)
+            );
+
+            if !test_case.skipped && test_case.failures.is_empty() {
+                writeln!(xml, "    <testcase {}/>", attributes)?;
+                continue;
+            }
+
+            writeln!(xml, "    <testcase {}>", attributes)?;
+            if test_case.skipped {
+                writeln!(
+                    xml,
+                    r#"      <skipped message="Package analysis is incomplete"/>"#
+                )?;
+            }
+            for failure in &test_case.failures {
+                writeln!(
+                    xml,
+                    r#"      <failure message="{}" type="{}">{}</failure>"#,
+                    escape_xml(&failure.message),
+                    escape_xml(&failure.failure_type),
+                    escape_xml(&failure.text)
+                )?;
+            }
+            writeln!(xml, "    </testcase>")?;
+        }
+
+        writeln!(xml, "  </testsuite>")?;
+        writeln!(xml, "</testsuites>")
+    }
+}
+
+/// Check if the analysis of a package is incomplete.
+fn is_incomplete(package: &PackageStatusExtended) -> bool {
+    serde_json::to_value(&package.basic_status.status)
+        .ok()
+        .map_or(false, |status| status == "incomplete")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::report::tests::job_fixture;
+
+    #[test]
+    fn junit_test_cases() {
+        let job = job_fixture();
+        let junit = Junit::new(&job, None);
+
+        let names = junit
+            .test_cases
+            .iter()
+            .map(|c| (c.name.as_str(), c.failures.len(), c.skipped))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                ("accepts@1.3.8", 2, false),
+                ("express@4.17.3", 1, false),
+                ("body-parser@1.19.2", 0, true),
+            ]
+        );
+        assert_eq!(
+            junit.test_cases[0].failures[0].message,
+            "accepts is vulnerable to prototype pollution"
+        );
+
+        let xml = junit.to_xml();
+        assert!(xml.contains(r#"<testsuite name="testing" tests="3" failures="2" skipped="1">"#));
+        assert!(xml.contains(r#"<skipped message="Package analysis is incomplete"/>"#));
+    }
+
+    #[test]
+    fn junit_applies_filter() {
+        let job = job_fixture();
+        let filter = Filter::from_str("high").unwrap();
+        let junit = Junit::new(&job, Some(&filter));
+
+        let failures = junit
+            .test_cases
+            .iter()
+            .map(|c| c.failures.len())
+            .collect::<Vec<_>>();
+        assert_eq!(failures, vec![1, 0, 0]);
+        assert!(junit.to_xml().contains(r#"name="express@4.17.3"/>"#));
+    }
+}
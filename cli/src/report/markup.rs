@@ -0,0 +1,454 @@
+//! Markdown and HTML reports.
+//!
+//! Reports are built as a [`Document`], which can be rendered in either format. Markdown
+//! reports use the HTML `<details>` element for collapsible sections, which is supported by
+//! the code hosting platforms that render Markdown comments.
+
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+use phylum_types::types::job::JobStatusResponse;
+use phylum_types::types::package::{
+    Issue, IssuesListItem, Package, PackageStatusExtended, PackageType,
+};
+use phylum_types::types::project::ProjectThresholds;
+
+use crate::filter::{Filter, FilterIssue, FilterPackage};
+use crate::render::Renderable;
+use crate::report::escape_xml;
+use crate::summarize::{Histogram, Scored};
+
+/// A report which can be rendered as Markdown or HTML.
+#[derive(Debug, Default)]
+pub struct Document {
+    blocks: Vec<Block>,
+}
+
+#[derive(Debug)]
+enum Block {
+    Heading(usize, String),
+    Paragraph(String),
+    Preformatted(String),
+    Table {
+        header: Vec<String>,
+        rows: Vec<Vec<String>>,
+    },
+    Details {
+        summary: String,
+        blocks: Vec<Block>,
+    },
+}
+
+/// An issue, in the columns of the issue tables.
+struct IssueRow<'a> {
+    severity: String,
+    domain: String,
+    title: &'a str,
+    description: &'a str,
+}
+
+impl<'a> From<&'a Issue> for IssueRow<'a> {
+    fn from(issue: &'a Issue) -> Self {
+        IssueRow {
+            severity: issue.severity.to_string(),
+            domain: issue.domain.to_string(),
+            title: &issue.title,
+            description: &issue.description,
+        }
+    }
+}
+
+impl<'a> From<&'a IssuesListItem> for IssueRow<'a> {
+    fn from(issue: &'a IssuesListItem) -> Self {
+        IssueRow {
+            severity: issue.impact.to_string(),
+            domain: issue.risk_type.to_string(),
+            title: &issue.title,
+            description: &issue.description,
+        }
+    }
+}
+
+impl Document {
+    /// Build a report of a job's results.
+    ///
+    /// Packages and issues excluded by `filter` are left out, like in the table output.
+    pub fn from_job(
+        job: &JobStatusResponse<PackageStatusExtended>,
+        filter: Option<&Filter>,
+    ) -> Self {
+        let ecosystem = PackageType::from_str(&job.ecosystem).unwrap_or(PackageType::Npm);
+        let date_time = NaiveDateTime::from_timestamp(job.created_at / 1000, 0);
+        let status = if job.num_incomplete > 0 {
+            "INCOMPLETE"
+        } else if job.pass {
+            "PASS"
+        } else {
+            "FAIL"
+        };
+
+        let mut details = vec![
+            ("Project", job.project_name.clone()),
+            ("Label", job.label.clone().unwrap_or_default()),
+            ("Job ID", job.job_id.to_string()),
+            ("Date", format!("{} UTC", date_time)),
+            ("Ecosystem", ecosystem.render()),
+            ("Num Deps", job.packages.len().to_string()),
+            ("Project Score", percent(job.score).to_string()),
+            ("Status", status.to_owned()),
+        ];
+        if !job.pass {
+            details.push(("Reason", job.msg.clone()));
+        }
+
+        let mut document = Document::default();
+        document.heading(2, format!("Phylum analysis of {}", job.project_name));
+        document.key_values(&details);
+
+        if job.num_incomplete > 0 {
+            document.paragraph(format!(
+                "{:.2}% of submitted packages are currently being processed. Scores may change \
+                once processing completes.",
+                (job.num_incomplete as f32 / job.packages.len() as f32) * 100.0
+            ));
+        }
+
+        let scores = job.packages.iter().map(|p| p.score()).collect::<Vec<_>>();
+        document.heading(3, "Score distribution");
+        document.preformatted(Histogram::new(&scores, 0.0, 1.0, 10).to_string());
+
+        document.heading(3, "Thresholds");
+        document.thresholds(&job.thresholds);
+
+        document.heading(3, "Issues");
+        let mut any_issues = false;
+        for package in &job.packages {
+            let status = &package.basic_status;
+            if let Some(filter) = filter {
+                if !filter.matches_package(&FilterPackage::from(package)) {
+                    continue;
+                }
+            }
+
+            let issues = package
+                .issues
+                .iter()
+                .filter(|issue| {
+                    filter.map_or(true, |filter| {
+                        filter.matches_issue(
+                            &FilterPackage::from(package),
+                            &FilterIssue::from(*issue),
+                        )
+                    })
+                })
+                .collect::<Vec<_>>();
+            if issues.is_empty() {
+                continue;
+            }
+
+            any_issues = true;
+            let mut rows = issues
+                .iter()
+                .map(|&issue| (&issue.severity, IssueRow::from(issue)));
+            let summary = format!("{}@{}", status.name, status.version);
+            document.blocks.push(issue_details(summary, &mut rows));
+        }
+        if !any_issues {
+            document.paragraph("No issues found.");
+        }
+
+        document
+    }
+
+    /// Build a report of a package's details.
+    pub fn from_package(package: &Package, filter: Option<&Filter>) -> Self {
+        let mut document = Document::default();
+        document.heading(2, format!("{}@{}", package.name, package.version));
+        document.key_values(&[
+            ("Ecosystem", package.registry.render()),
+            (
+                "License",
+                package.license.clone().unwrap_or_else(|| "Unknown".into()),
+            ),
+            (
+                "Last updated",
+                package
+                    .published_date
+                    .clone()
+                    .unwrap_or_else(|| "Unknown".into()),
+            ),
+            (
+                "Num Deps",
+                package
+                    .dependencies
+                    .as_ref()
+                    .map_or(0, Vec::len)
+                    .to_string(),
+            ),
+        ]);
+
+        let scores = &package.risk_scores;
+        document.heading(3, "Risk scores");
+        document.key_values(&[
+            ("Total Risk", percent(scores.total).to_string()),
+            ("Author Risk", percent(scores.author).to_string()),
+            ("Engineering Risk", percent(scores.engineering).to_string()),
+            ("License Risk", percent(scores.license).to_string()),
+            (
+                "Malicious Code Risk",
+                percent(scores.malicious_code).to_string(),
+            ),
+            (
+                "Vulnerability Risk",
+                percent(scores.vulnerability).to_string(),
+            ),
+        ]);
+
+        let mut rows = package
+            .issues
+            .iter()
+            .filter(|issue| {
+                filter.map_or(true, |filter| {
+                    filter.matches_issue(&FilterPackage::from(package), &FilterIssue::from(*issue))
+                })
+            })
+            .map(|issue| (&issue.impact, IssueRow::from(issue)));
+        document
+            .blocks
+            .push(issue_details("Issues".into(), &mut rows));
+
+        document
+    }
+
+    /// Append another document to this one.
+    pub fn append(&mut self, mut other: Document) {
+        self.blocks.append(&mut other.blocks);
+    }
+
+    /// Render the document as Markdown.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        for block in &self.blocks {
+            block.write_markdown(&mut markdown);
+        }
+        markdown
+    }
+
+    /// Render the document as an HTML fragment.
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        for block in &self.blocks {
+            block.write_html(&mut html);
+        }
+        html
+    }
+
+    fn heading(&mut self, level: usize, text: impl Into<String>) {
+        self.blocks.push(Block::Heading(level, text.into()));
+    }
+
+    fn paragraph(&mut self, text: impl Into<String>) {
+        self.blocks.push(Block::Paragraph(text.into()));
+    }
+
+    fn preformatted(&mut self, text: String) {
+        self.blocks.push(Block::Preformatted(text));
+    }
+
+    fn key_values<K: ToString, V: ToString>(&mut self, values: &[(K, V)]) {
+        let rows = values
+            .iter()
+            .map(|(key, value)| vec![key.to_string(), value.to_string()])
+            .collect();
+        self.blocks.push(Block::Table {
+            header: vec![String::new(), String::new()],
+            rows,
+        });
+    }
+
+    fn thresholds(&mut self, thresholds: &ProjectThresholds) {
+        let threshold = |t: f32| (t * 100.0).round() as u32;
+        self.key_values(&[
+            ("Project Score", threshold(thresholds.total)),
+            ("Malicious Code Risk", threshold(thresholds.malicious)),
+            ("Vulnerability Risk", threshold(thresholds.vulnerability)),
+            ("Engineering Risk", threshold(thresholds.engineering)),
+            ("Author Risk", threshold(thresholds.author)),
+            ("License Risk", threshold(thresholds.license)),
+        ]);
+    }
+}
+
+impl Block {
+    fn write_markdown(&self, out: &mut String) {
+        match self {
+            Block::Heading(level, text) => {
+                out.push_str(&format!("{} {}\n\n", "#".repeat(*level), text));
+            }
+            Block::Paragraph(text) => out.push_str(&format!("{}\n\n", text)),
+            Block::Preformatted(text) => out.push_str(&format!("```text\n{}\n```\n\n", text)),
+            Block::Table { header, rows } => {
+                let row = |cells: &[String]| {
+                    let cells = cells.iter().map(|cell| markdown_cell(cell));
+                    format!("| {} |\n", cells.collect::<Vec<_>>().join(" | "))
+                };
+                out.push_str(&row(header));
+                out.push_str(&format!("|{}\n", "---|".repeat(header.len())));
+                for cells in rows {
+                    out.push_str(&row(cells));
+                }
+                out.push('\n');
+            }
+            Block::Details { summary, blocks } => {
+                out.push_str(&format!(
+                    "<details>\n<summary>{}</summary>\n\n",
+                    escape_xml(summary)
+                ));
+                for block in blocks {
+                    block.write_markdown(out);
+                }
+                out.push_str("</details>\n\n");
+            }
+        }
+    }
+
+    fn write_html(&self, out: &mut String) {
+        match self {
+            Block::Heading(level, text) => {
+                out.push_str(&format!("<h{0}>{1}</h{0}>\n", level, escape_xml(text)));
+            }
+            Block::Paragraph(text) => out.push_str(&format!("<p>{}</p>\n", escape_xml(text))),
+            Block::Preformatted(text) => {
+                out.push_str(&format!("<pre>{}</pre>\n", escape_xml(text)));
+            }
+            Block::Table { header, rows } => {
+                out.push_str("<table>\n");
+                if header.iter().any(|cell| !cell.is_empty()) {
+                    let cells = header
+                        .iter()
+                        .map(|cell| format!("<th>{}</th>", escape_xml(cell)));
+                    out.push_str(&format!("<tr>{}</tr>\n", cells.collect::<String>()));
+                }
+                for row in rows {
+                    let cells = row
+                        .iter()
+                        .map(|cell| format!("<td>{}</td>", escape_xml(cell)));
+                    out.push_str(&format!("<tr>{}</tr>\n", cells.collect::<String>()));
+                }
+                out.push_str("</table>\n");
+            }
+            Block::Details { summary, blocks } => {
+                out.push_str(&format!(
+                    "<details>\n<summary>{}</summary>\n",
+                    escape_xml(summary)
+                ));
+                for block in blocks {
+                    block.write_html(out);
+                }
+                out.push_str("</details>\n");
+            }
+        }
+    }
+}
+
+/// Build a collapsible section listing issues, most severe first.
+fn issue_details<'a, S: PartialOrd + 'a>(
+    summary: String,
+    issues: &mut dyn Iterator<Item = (&'a S, IssueRow<'a>)>,
+) -> Block {
+    let mut issues = issues.collect::<Vec<_>>();
+    issues.sort_by(|a, b| b.0.partial_cmp(a.0).unwrap_or(Ordering::Equal));
+
+    let blocks = if issues.is_empty() {
+        vec![Block::Paragraph("No issues found.".into())]
+    } else {
+        let rows = issues
+            .into_iter()
+            .map(|(_, issue)| {
+                vec![
+                    issue.severity,
+                    issue.domain,
+                    issue.title.to_owned(),
+                    issue.description.to_owned(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let header = ["Severity", "Domain", "Title", "Description"];
+        vec![Block::Table {
+            header: header.iter().map(|cell| cell.to_string()).collect(),
+            rows,
+        }]
+    };
+
+    let count = match blocks.first() {
+        Some(Block::Table { rows, .. }) if rows.len() == 1 => "1 issue".to_owned(),
+        Some(Block::Table { rows, .. }) => format!("{} issues", rows.len()),
+        _ => "no issues".to_owned(),
+    };
+
+    Block::Details {
+        summary: format!("{} ({})", summary, count),
+        blocks,
+    }
+}
+
+/// Escape a Markdown table cell, which has to fit on a single line.
+///
+/// HTML is escaped as well, so issue text can't close the surrounding `<details>` element.
+fn markdown_cell(text: &str) -> String {
+    escape_xml(text)
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
+
+/// Convert a score to a percentage.
+fn percent<S: Into<f64>>(score: S) -> u32 {
+    (100.0 * score.into()).round() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::tests::job_fixture;
+
+    #[test]
+    fn markdown_job_report() {
+        let job = job_fixture();
+        let markdown = Document::from_job(&job, None).to_markdown();
+
+        assert!(markdown.starts_with("## Phylum analysis of testing\n"));
+        assert!(markdown.contains("| Status | INCOMPLETE |\n"));
+        assert!(markdown.contains("```text\n  Score       Count"));
+        assert!(markdown.contains("| Vulnerability Risk | 80 |\n"));
+        assert!(markdown.contains("<summary>accepts@1.3.8 (2 issues)</summary>"));
+        assert!(markdown.contains("<summary>express@4.17.3 (1 issue)</summary>"));
+        assert!(!markdown.contains("body-parser@1.19.2 ("));
+    }
+
+    #[test]
+    fn html_job_report_applies_filter() {
+        let job = job_fixture();
+        let filter = Filter::from_str("high").unwrap();
+        let html = Document::from_job(&job, Some(&filter)).to_html();
+
+        assert!(html.starts_with("<h2>Phylum analysis of testing</h2>\n"));
+        assert!(html.contains("<summary>accepts@1.3.8 (1 issue)</summary>"));
+        assert!(!html.contains("express@4.17.3"));
+        assert!(html.contains("<pre>  Score       Count"));
+    }
+
+    #[test]
+    fn markdown_cells_stay_on_one_line() {
+        assert_eq!(markdown_cell("a | b\nc"), "a \\| b<br>c");
+    }
+
+    #[test]
+    fn markdown_cells_escape_html() {
+        assert_eq!(
+            markdown_cell("</details><b>x</b> & y"),
+            "&lt;/details&gt;&lt;b&gt;x&lt;/b&gt; &amp; y"
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! Job reports for consumption by other tools.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+pub mod junit;
+pub mod markup;
+pub mod sarif;
+
+/// Supported job report formats.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReportFormat {
+    Sarif,
+    CycloneDx,
+    CycloneDxXml,
+    Spdx,
+    Junit,
+    Markdown,
+    Html,
+}
+
+/// Names of the report formats, as accepted by `--format`.
+const REPORT_FORMATS: &[(&str, ReportFormat)] = &[
+    ("sarif", ReportFormat::Sarif),
+    ("cyclonedx", ReportFormat::CycloneDx),
+    ("cyclonedx-xml", ReportFormat::CycloneDxXml),
+    ("spdx", ReportFormat::Spdx),
+    ("junit", ReportFormat::Junit),
+    ("markdown", ReportFormat::Markdown),
+    ("html", ReportFormat::Html),
+];
+
+impl FromStr for ReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        REPORT_FORMATS
+            .iter()
+            .find(|(name, _)| *name == s)
+            .map(|(_, format)| *format)
+            .ok_or_else(|| anyhow!("Unsupported report format `{}`", s))
+    }
+}
+
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = REPORT_FORMATS
+            .iter()
+            .find(|(_, format)| format == self)
+            .map_or("unknown", |(name, _)| *name);
+        f.write_str(name)
+    }
+}
+
+/// Escape text for use in XML content and attributes.
+pub(crate) fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::fs;
+
+    use phylum_types::types::job::JobStatusResponse;
+    use phylum_types::types::package::PackageStatusExtended;
+
+    /// Load the job used to test the report formats.
+    pub fn job_fixture() -> JobStatusResponse<PackageStatusExtended> {
+        let job = fs::read_to_string("tests/fixtures/job-status-ext.json").unwrap();
+        serde_json::from_str(&job).unwrap()
+    }
+}
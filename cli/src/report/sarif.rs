@@ -0,0 +1,382 @@
+//! SARIF report output.
+//!
+//! See https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html for the specification.
+
+use std::fs;
+use std::path::Path;
+
+use phylum_types::types::job::JobStatusResponse;
+use phylum_types::types::package::{Issue, PackageStatusExtended, RiskLevel};
+use serde::Serialize;
+
+use crate::filter::{Filter, FilterIssue, FilterPackage};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+/// A SARIF log with the issues of a single job.
+#[derive(Debug, Serialize)]
+pub struct Sarif {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<Run>,
+}
+
+#[derive(Debug, Serialize)]
+struct Run {
+    tool: Tool,
+    results: Vec<SarifResult>,
+}
+
+#[derive(Debug, Serialize)]
+struct Tool {
+    driver: Driver,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Driver {
+    name: &'static str,
+    information_uri: &'static str,
+    version: &'static str,
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule {
+    id: String,
+    name: String,
+    short_description: Message,
+}
+
+#[derive(Debug, Serialize)]
+struct Message {
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: String,
+    rule_index: usize,
+    level: &'static str,
+    message: Message,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    locations: Vec<Location>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    physical_location: PhysicalLocation,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<Region>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct ArtifactLocation {
+    uri: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: usize,
+}
+
+impl Sarif {
+    /// Build a SARIF log for a job.
+    ///
+    /// Results point at the line declaring their package in `lockfile`, if it is known.
+    pub fn new(
+        job: &JobStatusResponse<PackageStatusExtended>,
+        lockfile: Option<&Path>,
+        filter: Option<&Filter>,
+    ) -> Self {
+        let lockfile = lockfile.map(|path| {
+            let contents = fs::read_to_string(path).unwrap_or_default();
+            (path_to_uri(path), contents)
+        });
+
+        let mut rules: Vec<Rule> = Vec::new();
+        let mut results = Vec::new();
+
+        for package in &job.packages {
+            let status = &package.basic_status;
+            if let Some(filter) = filter {
+                if !filter.matches_package(&FilterPackage::from(package)) {
+                    continue;
+                }
+            }
+
+            let location = lockfile.as_ref().map(|(uri, contents)| Location {
+                physical_location: PhysicalLocation {
+                    artifact_location: ArtifactLocation { uri: uri.clone() },
+                    region: find_package_line(contents, &status.name)
+                        .map(|start_line| Region { start_line }),
+                },
+            });
+
+            for issue in &package.issues {
+                if filter.map_or(false, |filter| {
+                    !filter.matches_issue(&FilterPackage::from(package), &FilterIssue::from(issue))
+                }) {
+                    continue;
+                }
+
+                let kind = issue_kind(issue, &status.name, &status.version);
+                let rule_id = rule_id(issue, &kind);
+                let rule_index = match rules.iter().position(|rule| rule.id == rule_id) {
+                    Some(index) => index,
+                    None => {
+                        rules.push(Rule {
+                            id: rule_id.clone(),
+                            name: kind.clone(),
+                            short_description: Message {
+                                text: format!("{} ({} risk domain)", kind, issue.domain),
+                            },
+                        });
+                        rules.len() - 1
+                    }
+                };
+
+                results.push(SarifResult {
+                    rule_id,
+                    rule_index,
+                    level: level(&issue.severity),
+                    message: Message {
+                        text: format!("{}\n\n{}", issue.title, issue.description),
+                    },
+                    locations: location.iter().cloned().collect(),
+                });
+            }
+        }
+
+        Sarif {
+            schema: SARIF_SCHEMA,
+            version: SARIF_VERSION,
+            runs: vec![Run {
+                tool: Tool {
+                    driver: Driver {
+                        name: "phylum",
+                        information_uri: "https://phylum.io",
+                        version: env!("CARGO_PKG_VERSION"),
+                        rules,
+                    },
+                },
+                results,
+            }],
+        }
+    }
+}
+
+/// Get the rule ID for an issue, which is its risk domain and kind.
+///
+/// Issues have no ID of their own, so the kind is derived from their title, which is shared by
+/// all issues of the same kind.
+fn rule_id(issue: &Issue, kind: &str) -> String {
+    let domain = serde_json::to_value(&issue.domain)
+        .ok()
+        .and_then(|domain| domain.as_str().map(String::from))
+        .unwrap_or_else(|| issue.domain.to_string());
+
+    let mut slug = String::new();
+    for c in kind.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    format!("{}/{}", domain, slug.trim_end_matches('-'))
+}
+
+/// Get the kind of an issue, which is its title without the affected package.
+///
+/// Titles like `Prototype pollution in accepts@1.3.8` have the kind `Prototype pollution`.
+fn issue_kind(issue: &Issue, name: &str, version: &str) -> String {
+    let package = format!("{}@{}", name, version);
+    let title = issue.title.replace(&package, "");
+    let mut kind = title.trim();
+
+    for preposition in [" in", " for", " of", " on", " from"] {
+        if let Some(stripped) = kind.strip_suffix(preposition) {
+            kind = stripped.trim_end();
+            break;
+        }
+    }
+
+    if kind.is_empty() {
+        issue.title.clone()
+    } else {
+        kind.to_owned()
+    }
+}
+
+/// Get the SARIF level for an issue severity.
+fn level(severity: &RiskLevel) -> &'static str {
+    match severity {
+        RiskLevel::Critical | RiskLevel::High => "error",
+        RiskLevel::Medium => "warning",
+        RiskLevel::Low | RiskLevel::Info => "note",
+    }
+}
+
+/// Convert a path to a relative SARIF artifact URI.
+fn path_to_uri(path: &Path) -> String {
+    let uri = path.to_string_lossy().replace('\\', "/");
+    uri.trim_start_matches("./").to_owned()
+}
+
+/// Find the first line mentioning a package in a lockfile.
+///
+/// Maven packages are also looked up by their artifact ID, since manifests like `pom.xml`
+/// declare the group and artifact separately.
+fn find_package_line(contents: &str, name: &str) -> Option<usize> {
+    let mut names = vec![name];
+    if let Some((_, artifact)) = name.rsplit_once(':') {
+        names.push(artifact);
+    }
+
+    names.into_iter().find_map(|name| {
+        let name = name.to_lowercase();
+        contents
+            .lines()
+            .position(|line| contains_name(&line.to_lowercase(), &name))
+            .map(|index| index + 1)
+    })
+}
+
+/// Check if `line` contains `name`, not directly surrounded by other name characters.
+fn contains_name(line: &str, name: &str) -> bool {
+    if name.is_empty() {
+        return false;
+    }
+
+    let is_name_char = |c: char| c.is_alphanumeric() || matches!(c, '-' | '_' | '.');
+    line.match_indices(name).any(|(start, _)| {
+        let before = line[..start].chars().next_back();
+        let after = line[start + name.len()..].chars().next();
+        !before.map_or(false, is_name_char) && !after.map_or(false, is_name_char)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use serde_json::Value;
+
+    use super::*;
+    use crate::report::tests::job_fixture;
+
+    #[test]
+    fn sarif_results_for_issues() {
+        let job = job_fixture();
+        let sarif = Sarif::new(
+            &job,
+            Some(Path::new("tests/fixtures/package-lock.json")),
+            None,
+        );
+        let sarif = serde_json::to_value(&sarif).unwrap();
+
+        assert_eq!(sarif["version"], "2.1.0");
+
+        let run = &sarif["runs"][0];
+        let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+        let rule_ids = rules.iter().map(|rule| &rule["id"]).collect::<Vec<_>>();
+        assert_eq!(
+            rule_ids,
+            vec![
+                "vulnerability/prototype-pollution",
+                "license/commercial-license-risk",
+                "engineering/few-active-maintainers",
+            ]
+        );
+        assert_eq!(rules[0]["name"], "Prototype pollution");
+
+        let results = run["results"].as_array().unwrap();
+        let levels = results
+            .iter()
+            .map(|result| (&result["ruleIndex"], &result["level"]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            levels,
+            vec![
+                (&Value::from(0), &Value::from("error")),
+                (&Value::from(1), &Value::from("warning")),
+                (&Value::from(2), &Value::from("note")),
+            ]
+        );
+
+        let location = &results[0]["locations"][0]["physicalLocation"];
+        assert_eq!(
+            location["artifactLocation"]["uri"],
+            "tests/fixtures/package-lock.json"
+        );
+        assert_eq!(location["region"]["startLine"], 15);
+    }
+
+    #[test]
+    fn sarif_applies_filter() {
+        let job = job_fixture();
+        let filter = Filter::from_str("high").unwrap();
+        let sarif = Sarif::new(&job, None, Some(&filter));
+        let sarif = serde_json::to_value(&sarif).unwrap();
+
+        let results = sarif["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["ruleId"], "vulnerability/prototype-pollution");
+        assert!(results[0].get("locations").is_none());
+    }
+
+    #[test]
+    fn find_package_lines() {
+        let lockfile = "express@4.17.3\nexpress-session@1.17.2\n  org.yaml:snakeyaml:1.30\n";
+        assert_eq!(find_package_line(lockfile, "express-session"), Some(2));
+        assert_eq!(find_package_line(lockfile, "express"), Some(1));
+        assert_eq!(find_package_line(lockfile, "org.yaml:snakeyaml"), Some(3));
+        assert_eq!(find_package_line(lockfile, "session"), None);
+
+        let pom = "<groupId>org.yaml</groupId>\n<artifactId>snakeyaml</artifactId>\n";
+        assert_eq!(find_package_line(pom, "org.yaml:snakeyaml"), Some(2));
+    }
+
+    #[test]
+    fn rule_ids_ignore_packages() {
+        let issue = |title: &str| -> Issue {
+            serde_json::from_value(serde_json::json!({
+                "title": title,
+                "description": "",
+                "severity": "low",
+                "domain": "engineering",
+            }))
+            .unwrap()
+        };
+
+        let express = issue("Few active maintainers for express@4.17.3");
+        let lodash = issue("Few active maintainers for lodash@4.17.21");
+        let express_kind = issue_kind(&express, "express", "4.17.3");
+        let lodash_kind = issue_kind(&lodash, "lodash", "4.17.21");
+        assert_eq!(express_kind, "Few active maintainers");
+        assert_eq!(
+            rule_id(&express, &express_kind),
+            rule_id(&lodash, &lodash_kind)
+        );
+
+        let other = issue("Deprecated package");
+        assert_eq!(
+            issue_kind(&other, "express", "4.17.3"),
+            "Deprecated package"
+        );
+    }
+}
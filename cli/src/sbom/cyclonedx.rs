@@ -0,0 +1,546 @@
+//! CycloneDX SBOM output.
+//!
+//! See https://cyclonedx.org/docs/1.4/json/ for the specification.
+
+use std::fmt::Write;
+
+use chrono::{SecondsFormat, Utc};
+use lazy_static::lazy_static;
+use phylum_types::types::package::{Issue, PackageStatusExtended, RiskLevel, RiskType};
+use regex::Regex;
+use serde::Serialize;
+
+use super::{random_uuid, Sbom, SbomPackage};
+use crate::report::escape_xml;
+
+const SPEC_VERSION: &str = "1.4";
+const XML_NAMESPACE: &str = "http://cyclonedx.org/schema/bom/1.4";
+
+/// A CycloneDX bill of materials.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bom {
+    bom_format: &'static str,
+    spec_version: &'static str,
+    serial_number: String,
+    version: u32,
+    metadata: Metadata,
+    components: Vec<Component>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    dependencies: Vec<Dependency>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    vulnerabilities: Vec<Vulnerability>,
+}
+
+#[derive(Debug, Serialize)]
+struct Metadata {
+    timestamp: String,
+    tools: Vec<Tool>,
+    component: MetadataComponent,
+}
+
+#[derive(Debug, Serialize)]
+struct Tool {
+    vendor: &'static str,
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+struct MetadataComponent {
+    #[serde(rename = "type")]
+    component_type: &'static str,
+    name: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Component {
+    #[serde(rename = "type")]
+    component_type: &'static str,
+    #[serde(rename = "bom-ref")]
+    bom_ref: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group: Option<String>,
+    name: String,
+    version: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    licenses: Vec<LicenseChoice>,
+    purl: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    properties: Vec<Property>,
+}
+
+#[derive(Debug, Serialize)]
+struct LicenseChoice {
+    license: License,
+}
+
+#[derive(Debug, Serialize)]
+struct License {
+    name: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Property {
+    name: String,
+    value: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Dependency {
+    #[serde(rename = "ref")]
+    dependency_ref: String,
+    depends_on: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Vulnerability {
+    id: String,
+    source: Source,
+    ratings: Vec<Rating>,
+    description: String,
+    affects: Vec<Affect>,
+}
+
+#[derive(Debug, Serialize)]
+struct Source {
+    name: &'static str,
+    url: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+struct Rating {
+    source: Source,
+    severity: &'static str,
+    method: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+struct Affect {
+    #[serde(rename = "ref")]
+    affect_ref: String,
+}
+
+impl Bom {
+    /// Build a CycloneDX BOM.
+    ///
+    /// Phylum's analysis of packages is included as `phylum:` component properties, with
+    /// vulnerabilities listed separately.
+    pub fn new(sbom: &Sbom) -> Self {
+        let components = sbom.packages.iter().map(component).collect();
+
+        let dependencies = (0..sbom.packages.len())
+            .filter_map(|index| {
+                let depends_on = sbom
+                    .dependencies_of(index)
+                    .map(|dependency| dependency.purl.to_string())
+                    .collect::<Vec<_>>();
+
+                (!depends_on.is_empty()).then(|| Dependency {
+                    dependency_ref: sbom.packages[index].purl.to_string(),
+                    depends_on,
+                })
+            })
+            .collect();
+
+        let vulnerabilities = sbom
+            .packages
+            .iter()
+            .flat_map(|package| {
+                let issues = package
+                    .analysis
+                    .map_or(&[][..], |analysis| analysis.issues.as_slice());
+                issues
+                    .iter()
+                    .filter(|issue| is_vulnerability(issue))
+                    .map(move |issue| vulnerability(package, issue))
+            })
+            .collect();
+
+        Bom {
+            bom_format: "CycloneDX",
+            spec_version: SPEC_VERSION,
+            serial_number: format!("urn:uuid:{}", random_uuid()),
+            version: 1,
+            metadata: Metadata {
+                timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+                tools: vec![Tool {
+                    vendor: "Phylum, Inc.",
+                    name: "phylum",
+                    version: env!("CARGO_PKG_VERSION"),
+                }],
+                component: MetadataComponent {
+                    component_type: "application",
+                    name: sbom.name.clone(),
+                },
+            },
+            components,
+            dependencies,
+            vulnerabilities,
+        }
+    }
+
+    /// Serialize the BOM as CycloneDX XML.
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::new();
+        let _ = self.write_xml(&mut xml);
+        xml
+    }
+
+    fn write_xml(&self, xml: &mut String) -> std::fmt::Result {
+        writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            xml,
+            r#"<bom xmlns="{}" serialNumber="{}" version="{}">"#,
+            XML_NAMESPACE,
+            escape_xml(&self.serial_number),
+            self.version
+        )?;
+
+        let metadata = &self.metadata;
+        writeln!(xml, "  <metadata>")?;
+        writeln!(xml, "    <timestamp>{}</timestamp>", metadata.timestamp)?;
+        writeln!(xml, "    <tools>")?;
+        for tool in &metadata.tools {
+            writeln!(xml, "      <tool>")?;
+            writeln!(xml, "        <vendor>{}</vendor>", escape_xml(tool.vendor))?;
+            writeln!(xml, "        <name>{}</name>", escape_xml(tool.name))?;
+            writeln!(
+                xml,
+                "        <version>{}</version>",
+                escape_xml(tool.version)
+            )?;
+            writeln!(xml, "      </tool>")?;
+        }
+        writeln!(xml, "    </tools>")?;
+        writeln!(
+            xml,
+            r#"    <component type="{}">"#,
+            metadata.component.component_type
+        )?;
+        writeln!(
+            xml,
+            "      <name>{}</name>",
+            escape_xml(&metadata.component.name)
+        )?;
+        writeln!(xml, "    </component>")?;
+        writeln!(xml, "  </metadata>")?;
+
+        writeln!(xml, "  <components>")?;
+        for component in &self.components {
+            writeln!(
+                xml,
+                r#"    <component type="{}" bom-ref="{}">"#,
+                component.component_type,
+                escape_xml(&component.bom_ref)
+            )?;
+            if let Some(group) = &component.group {
+                writeln!(xml, "      <group>{}</group>", escape_xml(group))?;
+            }
+            writeln!(xml, "      <name>{}</name>", escape_xml(&component.name))?;
+            writeln!(
+                xml,
+                "      <version>{}</version>",
+                escape_xml(&component.version)
+            )?;
+            if !component.licenses.is_empty() {
+                writeln!(xml, "      <licenses>")?;
+                for license in &component.licenses {
+                    writeln!(
+                        xml,
+                        "        <license><name>{}</name></license>",
+                        escape_xml(&license.license.name)
+                    )?;
+                }
+                writeln!(xml, "      </licenses>")?;
+            }
+            writeln!(xml, "      <purl>{}</purl>", escape_xml(&component.purl))?;
+            if !component.properties.is_empty() {
+                writeln!(xml, "      <properties>")?;
+                for property in &component.properties {
+                    writeln!(
+                        xml,
+                        r#"        <property name="{}">{}</property>"#,
+                        escape_xml(&property.name),
+                        escape_xml(&property.value)
+                    )?;
+                }
+                writeln!(xml, "      </properties>")?;
+            }
+            writeln!(xml, "    </component>")?;
+        }
+        writeln!(xml, "  </components>")?;
+
+        if !self.dependencies.is_empty() {
+            writeln!(xml, "  <dependencies>")?;
+            for dependency in &self.dependencies {
+                writeln!(
+                    xml,
+                    r#"    <dependency ref="{}">"#,
+                    escape_xml(&dependency.dependency_ref)
+                )?;
+                for depends_on in &dependency.depends_on {
+                    writeln!(
+                        xml,
+                        r#"      <dependency ref="{}"/>"#,
+                        escape_xml(depends_on)
+                    )?;
+                }
+                writeln!(xml, "    </dependency>")?;
+            }
+            writeln!(xml, "  </dependencies>")?;
+        }
+
+        if !self.vulnerabilities.is_empty() {
+            writeln!(xml, "  <vulnerabilities>")?;
+            for vulnerability in &self.vulnerabilities {
+                writeln!(xml, "    <vulnerability>")?;
+                writeln!(xml, "      <id>{}</id>", escape_xml(&vulnerability.id))?;
+                write_source(xml, "      ", &vulnerability.source)?;
+                writeln!(xml, "      <ratings>")?;
+                for rating in &vulnerability.ratings {
+                    writeln!(xml, "        <rating>")?;
+                    write_source(xml, "          ", &rating.source)?;
+                    writeln!(xml, "          <severity>{}</severity>", rating.severity)?;
+                    writeln!(xml, "          <method>{}</method>", rating.method)?;
+                    writeln!(xml, "        </rating>")?;
+                }
+                writeln!(xml, "      </ratings>")?;
+                writeln!(
+                    xml,
+                    "      <description>{}</description>",
+                    escape_xml(&vulnerability.description)
+                )?;
+                writeln!(xml, "      <affects>")?;
+                for affect in &vulnerability.affects {
+                    writeln!(
+                        xml,
+                        "        <target><ref>{}</ref></target>",
+                        escape_xml(&affect.affect_ref)
+                    )?;
+                }
+                writeln!(xml, "      </affects>")?;
+                writeln!(xml, "    </vulnerability>")?;
+            }
+            writeln!(xml, "  </vulnerabilities>")?;
+        }
+
+        writeln!(xml, "</bom>")
+    }
+}
+
+/// Write the XML of a vulnerability source.
+fn write_source(xml: &mut String, indent: &str, source: &Source) -> std::fmt::Result {
+    writeln!(xml, "{}<source>", indent)?;
+    writeln!(xml, "{}  <name>{}</name>", indent, escape_xml(source.name))?;
+    writeln!(xml, "{}  <url>{}</url>", indent, escape_xml(source.url))?;
+    writeln!(xml, "{}</source>", indent)
+}
+
+/// Convert an SBOM package to a CycloneDX component.
+fn component(package: &SbomPackage) -> Component {
+    let purl = package.purl.to_string();
+
+    let licenses = package
+        .analysis
+        .and_then(|analysis| analysis.basic_status.license.clone())
+        .map(|name| LicenseChoice {
+            license: License { name },
+        })
+        .into_iter()
+        .collect();
+
+    Component {
+        component_type: "library",
+        bom_ref: purl.clone(),
+        group: package.purl.namespace.clone(),
+        name: package.purl.name.clone(),
+        version: package.package.version.clone(),
+        licenses,
+        purl,
+        properties: package.analysis.map(properties).unwrap_or_default(),
+    }
+}
+
+/// Get the properties describing Phylum's analysis of a package.
+fn properties(analysis: &PackageStatusExtended) -> Vec<Property> {
+    let mut properties = Vec::new();
+
+    if let Some(score) = analysis.basic_status.package_score {
+        properties.push(Property {
+            name: "phylum:score".into(),
+            value: format!("{:.2}", score),
+        });
+    }
+
+    let mut risk_vectors = analysis.risk_vectors.iter().collect::<Vec<_>>();
+    risk_vectors.sort_by(|a, b| a.0.cmp(b.0));
+    for (vector, score) in risk_vectors {
+        properties.push(Property {
+            name: format!("phylum:risk:{}", vector),
+            value: format!("{:.2}", score),
+        });
+    }
+
+    for issue in analysis
+        .issues
+        .iter()
+        .filter(|issue| !is_vulnerability(issue))
+    {
+        properties.push(Property {
+            name: "phylum:issue".into(),
+            value: format!("{} [{}] {}", issue.severity, issue.domain, issue.title),
+        });
+    }
+
+    properties
+}
+
+/// Check if an issue is in the vulnerability risk domain.
+fn is_vulnerability(issue: &Issue) -> bool {
+    let domain: RiskType = issue.domain.into();
+    domain == RiskType::Vulnerabilities
+}
+
+/// Convert a vulnerability issue to a CycloneDX vulnerability.
+fn vulnerability(package: &SbomPackage, issue: &Issue) -> Vulnerability {
+    lazy_static! {
+        static ref ADVISORY_ID: Regex =
+            Regex::new(r"\b(CVE-\d{4}-\d+|GHSA(-[0-9a-z]{4}){3})\b").unwrap();
+    }
+
+    // Prefer well-known advisory IDs over the issue title.
+    let id = ADVISORY_ID
+        .find(&issue.title)
+        .or_else(|| ADVISORY_ID.find(&issue.description))
+        .map_or_else(|| issue.title.clone(), |id| id.as_str().to_owned());
+
+    Vulnerability {
+        id,
+        source: phylum_source(),
+        ratings: vec![Rating {
+            source: phylum_source(),
+            severity: severity(&issue.severity),
+            method: "other",
+        }],
+        description: format!("{}\n\n{}", issue.title, issue.description),
+        affects: vec![Affect {
+            affect_ref: package.purl.to_string(),
+        }],
+    }
+}
+
+fn phylum_source() -> Source {
+    Source {
+        name: "Phylum",
+        url: "https://phylum.io",
+    }
+}
+
+/// Get the CycloneDX severity for an issue severity.
+fn severity(level: &RiskLevel) -> &'static str {
+    match level {
+        RiskLevel::Critical => "critical",
+        RiskLevel::High => "high",
+        RiskLevel::Medium => "medium",
+        RiskLevel::Low => "low",
+        RiskLevel::Info => "info",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use phylum_types::types::package::{PackageDescriptor, PackageType};
+
+    use super::*;
+    use crate::report::tests::job_fixture;
+
+    fn npm(name: &str, version: &str) -> PackageDescriptor {
+        PackageDescriptor {
+            name: name.into(),
+            version: version.into(),
+            package_type: PackageType::Npm,
+        }
+    }
+
+    #[test]
+    fn bom_from_packages() {
+        let packages = [npm("express", "4.17.3"), npm("@types/node", "17.0.0")];
+        let dependencies = [(npm("express", "4.17.3"), npm("@types/node", "17.0.0"))];
+        let sbom = Sbom::from_packages("package-lock.json", &packages, &dependencies);
+
+        let bom = serde_json::to_value(&Bom::new(&sbom)).unwrap();
+        assert_eq!(bom["bomFormat"], "CycloneDX");
+        assert_eq!(bom["specVersion"], "1.4");
+        assert_eq!(bom["metadata"]["component"]["name"], "package-lock.json");
+
+        let scoped = &bom["components"][1];
+        assert_eq!(scoped["group"], "@types");
+        assert_eq!(scoped["name"], "node");
+        assert_eq!(scoped["purl"], "pkg:npm/%40types/node@17.0.0");
+
+        assert_eq!(bom["dependencies"][0]["ref"], "pkg:npm/express@4.17.3");
+        assert_eq!(
+            bom["dependencies"][0]["dependsOn"][0],
+            "pkg:npm/%40types/node@17.0.0"
+        );
+        assert!(bom.get("vulnerabilities").is_none());
+    }
+
+    #[test]
+    fn bom_refs_are_unique() {
+        let packages = [
+            npm("express", "4.17.3"),
+            npm("accepts", "1.3.8"),
+            npm("accepts", "1.3.8"),
+        ];
+        let sbom = Sbom::from_packages("package-lock.json", &packages, &[]);
+
+        let bom = serde_json::to_value(&Bom::new(&sbom)).unwrap();
+        let components = bom["components"].as_array().unwrap();
+        assert_eq!(components.len(), 2);
+        assert_ne!(components[0]["bom-ref"], components[1]["bom-ref"]);
+    }
+
+    #[test]
+    fn bom_from_job() {
+        let job = job_fixture();
+        let sbom = Sbom::from_job(&job);
+        let bom = serde_json::to_value(&Bom::new(&sbom)).unwrap();
+
+        let accepts = &bom["components"][0];
+        assert_eq!(accepts["licenses"][0]["license"]["name"], "MIT");
+        let properties = accepts["properties"].as_array().unwrap();
+        assert!(properties
+            .iter()
+            .any(|p| p["name"] == "phylum:score" && p["value"] == "0.45"));
+        assert!(properties
+            .iter()
+            .any(|p| p["name"] == "phylum:risk:vulnerability" && p["value"] == "0.45"));
+        assert!(properties.iter().any(|p| p["name"] == "phylum:issue"));
+
+        let vulnerabilities = bom["vulnerabilities"].as_array().unwrap();
+        assert_eq!(vulnerabilities.len(), 1);
+        assert_eq!(vulnerabilities[0]["ratings"][0]["severity"], "high");
+        assert_eq!(
+            vulnerabilities[0]["affects"][0]["ref"],
+            "pkg:npm/accepts@1.3.8"
+        );
+    }
+
+    #[test]
+    fn bom_to_xml() {
+        let packages = [npm("express", "4.17.3"), npm("@types/node", "17.0.0")];
+        let dependencies = [(npm("express", "4.17.3"), npm("@types/node", "17.0.0"))];
+        let sbom = Sbom::from_packages("a&b", &packages, &dependencies);
+
+        let xml = Bom::new(&sbom).to_xml();
+        assert!(xml.starts_with("<?xml"));
+        assert!(xml.contains(r#"<bom xmlns="http://cyclonedx.org/schema/bom/1.4""#));
+        assert!(xml.contains("<name>a&amp;b</name>"));
+        assert!(xml.contains("<group>@types</group>"));
+        assert!(xml.contains(r#"<dependency ref="pkg:npm/%40types/node@17.0.0"/>"#));
+        assert!(xml.trim_end().ends_with("</bom>"));
+    }
+}
@@ -0,0 +1,224 @@
+//! Software bill of materials (SBOM) export.
+
+use std::collections::HashMap;
+
+use phylum_types::types::job::JobStatusResponse;
+use phylum_types::types::package::{PackageDescriptor, PackageStatusExtended};
+use rand::Rng;
+
+use crate::purl::Purl;
+
+pub mod cyclonedx;
+pub mod spdx;
+
+/// A package in an SBOM.
+#[derive(Debug)]
+pub struct SbomPackage<'a> {
+    pub package: PackageDescriptor,
+    pub purl: Purl,
+    /// Phylum's analysis of the package, if it is known.
+    pub analysis: Option<&'a PackageStatusExtended>,
+}
+
+/// The packages of an SBOM, along with the relationships between them.
+#[derive(Debug)]
+pub struct Sbom<'a> {
+    pub name: String,
+    pub packages: Vec<SbomPackage<'a>>,
+    /// Direct dependencies, as pairs of indices into `packages`.
+    pub dependencies: Vec<(usize, usize)>,
+}
+
+impl<'a> Sbom<'a> {
+    /// Create an SBOM from parsed lockfile packages.
+    pub fn from_packages(
+        name: &str,
+        packages: &[PackageDescriptor],
+        dependencies: &[(PackageDescriptor, PackageDescriptor)],
+    ) -> Self {
+        // Lockfiles can list the same package several times, but it's a single component.
+        let mut indices = HashMap::new();
+        let mut unique = Vec::new();
+        for package in packages {
+            let purl = Purl::from(package);
+            indices.entry(purl.to_string()).or_insert_with(|| {
+                unique.push(SbomPackage {
+                    purl,
+                    package: package.clone(),
+                    analysis: None,
+                });
+                unique.len() - 1
+            });
+        }
+
+        let index = |package: &PackageDescriptor| indices.get(&Purl::from(package).to_string());
+        let mut dependencies = dependencies
+            .iter()
+            .filter_map(|(parent, dependency)| Some((*index(parent)?, *index(dependency)?)))
+            .collect::<Vec<_>>();
+        dependencies.sort_unstable();
+        dependencies.dedup();
+
+        Sbom {
+            name: name.to_owned(),
+            packages: unique,
+            dependencies,
+        }
+    }
+
+    /// Create an SBOM from the packages analyzed in a job.
+    ///
+    /// Dependencies are only related to packages of the job if their name is unambiguous, or
+    /// the requirement is an exact version.
+    pub fn from_job(job: &'a JobStatusResponse<PackageStatusExtended>) -> Self {
+        // Index of each package of the job in `packages`, skipping duplicates.
+        let mut indices = HashMap::new();
+        let mut packages = Vec::new();
+        let parents = job
+            .packages
+            .iter()
+            .map(|status| {
+                let package = PackageDescriptor {
+                    name: status.basic_status.name.clone(),
+                    version: status.basic_status.version.clone(),
+                    package_type: status.package_type.to_owned(),
+                };
+                let purl = Purl::from(&package);
+
+                *indices.entry(purl.to_string()).or_insert_with(|| {
+                    packages.push(SbomPackage {
+                        purl,
+                        package,
+                        analysis: Some(status),
+                    });
+                    packages.len() - 1
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut dependencies = Vec::new();
+        for (&parent, status) in parents.iter().zip(&job.packages) {
+            for (name, requirement) in &status.dependencies {
+                let candidates = packages
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, p)| p.package.name == *name)
+                    .collect::<Vec<_>>();
+
+                let dependency = match candidates.as_slice() {
+                    [(index, _)] => Some(*index),
+                    _ => candidates
+                        .iter()
+                        .find(|(_, p)| p.package.version == *requirement)
+                        .map(|(index, _)| *index),
+                };
+
+                if let Some(dependency) = dependency {
+                    dependencies.push((parent, dependency));
+                }
+            }
+        }
+        dependencies.sort_unstable();
+        dependencies.dedup();
+
+        Sbom {
+            name: job.project_name.clone(),
+            packages,
+            dependencies,
+        }
+    }
+
+    /// Get the direct dependencies of a package.
+    fn dependencies_of(&self, index: usize) -> impl Iterator<Item = &SbomPackage<'a>> {
+        self.dependencies
+            .iter()
+            .filter(move |(parent, _)| *parent == index)
+            .map(move |(_, dependency)| &self.packages[*dependency])
+    }
+}
+
+/// Generate a random (version 4) UUID.
+fn random_uuid() -> String {
+    let mut bytes: [u8; 16] = rand::thread_rng().gen();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex = bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use phylum_types::types::package::PackageType;
+
+    use super::*;
+    use crate::report::tests::job_fixture;
+
+    #[test]
+    fn sbom_from_packages() {
+        let package = |name: &str, version: &str| PackageDescriptor {
+            name: name.into(),
+            version: version.into(),
+            package_type: PackageType::Npm,
+        };
+        let packages = [package("express", "4.17.3"), package("accepts", "1.3.8")];
+        let dependencies = [
+            (package("express", "4.17.3"), package("accepts", "1.3.8")),
+            (package("express", "4.17.3"), package("unknown", "1.0.0")),
+        ];
+
+        let sbom = Sbom::from_packages("package-lock.json", &packages, &dependencies);
+        assert_eq!(sbom.packages[1].purl.to_string(), "pkg:npm/accepts@1.3.8");
+        assert_eq!(sbom.dependencies, vec![(0, 1)]);
+    }
+
+    #[test]
+    fn sbom_from_duplicated_packages() {
+        let package = |name: &str, version: &str| PackageDescriptor {
+            name: name.into(),
+            version: version.into(),
+            package_type: PackageType::Npm,
+        };
+        let packages = [
+            package("express", "4.17.3"),
+            package("accepts", "1.3.8"),
+            package("express", "4.17.3"),
+        ];
+        let dependencies = [
+            (package("express", "4.17.3"), package("accepts", "1.3.8")),
+            (package("express", "4.17.3"), package("accepts", "1.3.8")),
+        ];
+
+        let sbom = Sbom::from_packages("package-lock.json", &packages, &dependencies);
+        assert_eq!(sbom.packages.len(), 2);
+        assert_eq!(sbom.dependencies, vec![(0, 1)]);
+    }
+
+    #[test]
+    fn sbom_from_job() {
+        let job = job_fixture();
+        let sbom = Sbom::from_job(&job);
+
+        assert_eq!(sbom.name, "testing");
+        assert_eq!(sbom.packages.len(), 3);
+        assert_eq!(sbom.dependencies, vec![(1, 0), (1, 2)]);
+        assert!(sbom.packages.iter().all(|p| p.analysis.is_some()));
+    }
+
+    #[test]
+    fn uuid_format() {
+        let uuid = random_uuid();
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(&uuid[14..15], "4");
+    }
+}
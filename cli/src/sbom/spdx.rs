@@ -0,0 +1,163 @@
+//! SPDX SBOM output.
+//!
+//! See https://spdx.github.io/spdx-spec/v2.3/ for the specification.
+
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+
+use super::{random_uuid, Sbom};
+
+const SPDX_VERSION: &str = "SPDX-2.3";
+const NO_ASSERTION: &str = "NOASSERTION";
+const DOCUMENT_ID: &str = "SPDXRef-DOCUMENT";
+
+/// An SPDX document.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpdxDocument {
+    spdx_version: &'static str,
+    data_license: &'static str,
+    #[serde(rename = "SPDXID")]
+    spdx_id: &'static str,
+    name: String,
+    document_namespace: String,
+    creation_info: CreationInfo,
+    packages: Vec<Package>,
+    relationships: Vec<Relationship>,
+}
+
+#[derive(Debug, Serialize)]
+struct CreationInfo {
+    created: String,
+    creators: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Package {
+    #[serde(rename = "SPDXID")]
+    spdx_id: String,
+    name: String,
+    version_info: String,
+    download_location: &'static str,
+    files_analyzed: bool,
+    license_concluded: &'static str,
+    license_declared: String,
+    copyright_text: &'static str,
+    external_refs: Vec<ExternalRef>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExternalRef {
+    reference_category: &'static str,
+    reference_type: &'static str,
+    reference_locator: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Relationship {
+    spdx_element_id: String,
+    relationship_type: &'static str,
+    related_spdx_element: String,
+}
+
+impl SpdxDocument {
+    /// Build an SPDX document.
+    ///
+    /// The document describes every package, with `DEPENDS_ON` relationships where known.
+    pub fn new(sbom: &Sbom) -> Self {
+        let package_id = |index: usize| format!("SPDXRef-Package-{}", index + 1);
+
+        let packages = sbom
+            .packages
+            .iter()
+            .enumerate()
+            .map(|(index, package)| Package {
+                spdx_id: package_id(index),
+                name: package.package.name.clone(),
+                version_info: package.package.version.clone(),
+                download_location: NO_ASSERTION,
+                files_analyzed: false,
+                license_concluded: NO_ASSERTION,
+                license_declared: package
+                    .analysis
+                    .and_then(|analysis| analysis.basic_status.license.clone())
+                    .unwrap_or_else(|| NO_ASSERTION.into()),
+                copyright_text: NO_ASSERTION,
+                external_refs: vec![ExternalRef {
+                    reference_category: "PACKAGE-MANAGER",
+                    reference_type: "purl",
+                    reference_locator: package.purl.to_string(),
+                }],
+            })
+            .collect();
+
+        let describes = (0..sbom.packages.len()).map(|index| Relationship {
+            spdx_element_id: DOCUMENT_ID.into(),
+            relationship_type: "DESCRIBES",
+            related_spdx_element: package_id(index),
+        });
+        let depends_on = sbom
+            .dependencies
+            .iter()
+            .map(|(parent, dependency)| Relationship {
+                spdx_element_id: package_id(*parent),
+                relationship_type: "DEPENDS_ON",
+                related_spdx_element: package_id(*dependency),
+            });
+
+        SpdxDocument {
+            spdx_version: SPDX_VERSION,
+            data_license: "CC0-1.0",
+            spdx_id: DOCUMENT_ID,
+            name: sbom.name.clone(),
+            document_namespace: format!("https://spdx.org/spdxdocs/phylum-{}", random_uuid()),
+            creation_info: CreationInfo {
+                created: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+                creators: vec![format!("Tool: phylum-{}", env!("CARGO_PKG_VERSION"))],
+            },
+            packages,
+            relationships: describes.chain(depends_on).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use phylum_types::types::package::{PackageDescriptor, PackageType};
+
+    use super::*;
+
+    #[test]
+    fn spdx_document() {
+        let package = |name: &str, version: &str| PackageDescriptor {
+            name: name.into(),
+            version: version.into(),
+            package_type: PackageType::PyPi,
+        };
+        let packages = [package("requests", "2.27.1"), package("idna", "3.3")];
+        let dependencies = [(package("requests", "2.27.1"), package("idna", "3.3"))];
+        let sbom = Sbom::from_packages("poetry.lock", &packages, &dependencies);
+
+        let document = serde_json::to_value(&SpdxDocument::new(&sbom)).unwrap();
+        assert_eq!(document["spdxVersion"], "SPDX-2.3");
+        assert_eq!(document["SPDXID"], "SPDXRef-DOCUMENT");
+        assert_eq!(document["name"], "poetry.lock");
+
+        let idna = &document["packages"][1];
+        assert_eq!(idna["SPDXID"], "SPDXRef-Package-2");
+        assert_eq!(idna["versionInfo"], "3.3");
+        assert_eq!(
+            idna["externalRefs"][0]["referenceLocator"],
+            "pkg:pypi/idna@3.3"
+        );
+
+        let relationships = document["relationships"].as_array().unwrap();
+        assert_eq!(relationships.len(), 3);
+        assert_eq!(relationships[2]["spdxElementId"], "SPDXRef-Package-1");
+        assert_eq!(relationships[2]["relationshipType"], "DEPENDS_ON");
+        assert_eq!(relationships[2]["relatedSpdxElement"], "SPDXRef-Package-2");
+    }
+}
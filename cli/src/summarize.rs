@@ -12,9 +12,10 @@ use phylum_types::types::package::*;
 use phylum_types::types::project::*;
 use prettytable::*;
 
-use crate::filter::Filter;
-use crate::print::{self, table_format};
-use crate::render::Renderable;
+use crate::filter::{Filter, FilterIssue, FilterPackage};
+use crate::print::{self, paint, print_table, table_format};
+use crate::render::{Renderable, MIN_COLUMN_WIDTH};
+use crate::types::PackageList;
 
 #[derive(Debug)]
 pub struct Histogram {
@@ -25,7 +26,7 @@ pub struct Histogram {
 }
 
 impl Histogram {
-    fn new(data: &[f64], min: f64, max: f64, bins: usize) -> Histogram {
+    pub(crate) fn new(data: &[f64], min: f64, max: f64, bins: usize) -> Histogram {
         let mut values: Vec<usize> = vec![0; bins];
 
         let step = (max - min) / bins as f64;
@@ -137,10 +138,14 @@ where
 
     let date_time = NaiveDateTime::from_timestamp(resp.created_at / 1000, 0);
 
+    // Split the terminal between the two columns of details. Their labels and the
+    // separator take up 45 characters.
+    let width = (print::terminal_width().saturating_sub(45) / 2).max(MIN_COLUMN_WIDTH);
+
     let details = [
         (
             "Project",
-            print::truncate(&resp.project_name, 36).to_string(),
+            print::truncate(&resp.project_name, width).to_string(),
             "Label",
             resp.label.as_ref().unwrap_or(&"".to_string()).to_owned(),
         ),
@@ -164,18 +169,26 @@ where
         ),
     ];
     let summary = details.iter().fold("".to_string(), |acc, x| {
-        format!("{}\n{:>16}: {:<36} {:>24}: {:<36}", acc, x.0, x.1, x.2, x.3)
+        format!(
+            "{}\n{:>16}: {:<width$} {:>24}: {}",
+            acc,
+            x.0,
+            x.1,
+            x.2,
+            x.3,
+            width = width
+        )
     });
 
     let status = if resp.num_incomplete > 0 {
-        format!("{:>16}: {}", "Status", Yellow.paint("INCOMPLETE"))
+        format!("{:>16}: {}", "Status", paint(Yellow, "INCOMPLETE"))
     } else if resp.pass {
-        format!("{:>16}: {}", "Status", Green.paint("PASS"))
+        format!("{:>16}: {}", "Status", paint(Green, "PASS"))
     } else {
         format!(
             "{:>16}: {}\n{:>16}: {}",
             "Status",
-            Red.paint("FAIL"),
+            paint(Red, "FAIL"),
             "Reason",
             resp.msg
         )
@@ -194,7 +207,7 @@ where
     if resp.num_incomplete > 0 {
         let notice = format!(
             "\n{}: {:.2}% of submitted packages are currently being processed. Scores may change once processing completes.\n            For more information on processing visit https://docs.phylum.io/docs/processing.",
-            Purple.paint("PROCESSING"),
+            paint(Purple, "PROCESSING"),
             (resp.num_incomplete as f32/resp.packages.len() as f32)*100.0
         );
         table.add_row(row![notice]);
@@ -209,25 +222,10 @@ where
 impl Summarize for JobStatusResponse<PackageStatus> {
     fn summarize(&self, _filter: Option<Filter>) {
         let t: Table = response_to_table(self);
-        t.printstd();
+        print_table(&t);
     }
 }
 
-fn check_filter_issue(filter: &Filter, issue: &Issue) -> bool {
-    let mut include = true;
-    if let Some(ref level) = filter.level {
-        if issue.severity < *level {
-            include = false;
-        }
-    }
-    if let Some(ref domains) = filter.domains {
-        if !domains.contains(&issue.domain.into()) {
-            include = false;
-        }
-    }
-    include
-}
-
 impl Summarize for JobStatusResponse<PackageStatusExtended> {
     fn summarize(&self, filter: Option<Filter>) {
         let table_1: Table = response_to_table(self);
@@ -238,9 +236,16 @@ impl Summarize for JobStatusResponse<PackageStatusExtended> {
         let mut issues: Vec<&Issue> = vec![];
 
         for p in &self.packages {
+            let package = FilterPackage::from(p);
+            if let Some(ref filter) = filter {
+                if !filter.matches_package(&package) {
+                    continue;
+                }
+            }
+
             for issue in &p.issues {
                 if let Some(ref filter) = filter {
-                    if check_filter_issue(filter, issue) {
+                    if filter.matches_issue(&package, &issue.into()) {
                         issues.push(issue);
                     }
                 } else {
@@ -260,8 +265,8 @@ impl Summarize for JobStatusResponse<PackageStatusExtended> {
             table_2.add_empty_row();
         }
 
-        table_1.printstd();
-        table_2.printstd();
+        print_table(&table_1);
+        print_table(&table_2);
     }
 }
 
@@ -270,29 +275,12 @@ impl Summarize for PackageStatusExtended {
         let mut issues_table = Table::new();
         issues_table.set_format(table_format(3, 0));
 
+        let package = FilterPackage::from(self);
         let issues = if let Some(ref filter) = filter {
             self.issues
                 .iter()
-                .filter_map(|i| {
-                    let mut include = true;
-
-                    if let Some(ref level) = filter.level {
-                        if i.severity < *level {
-                            include = false;
-                        }
-                    }
-
-                    if let Some(domains) = &filter.domains {
-                        if !domains.contains(&i.domain.into()) {
-                            include = false;
-                        }
-                    }
-                    if include {
-                        Some(i.to_owned())
-                    } else {
-                        None
-                    }
-                })
+                .filter(|i| filter.matches_issue(&package, &FilterIssue::from(*i)))
+                .cloned()
                 .collect::<Vec<Issue>>()
         } else {
             self.issues.to_owned()
@@ -326,11 +314,11 @@ impl Summarize for PackageStatusExtended {
         println!("{}", self.render());
 
         println!(" Risk Vectors:");
-        risks_table.printstd();
+        print_table(&risks_table);
 
         if !issues_table.is_empty() {
             println!("\n Issues:");
-            issues_table.printstd();
+            print_table(&issues_table);
         }
     }
 }
@@ -340,29 +328,12 @@ impl Summarize for Package {
         let mut issues_table = Table::new();
         issues_table.set_format(table_format(3, 0));
 
+        let package = FilterPackage::from(self);
         let issues = if let Some(ref filter) = filter {
             self.issues
                 .iter()
-                .filter_map(|i| {
-                    let mut include = true;
-
-                    if let Some(ref level) = filter.level {
-                        if i.impact < *level {
-                            include = false;
-                        }
-                    }
-
-                    if let Some(domains) = &filter.domains {
-                        if !domains.contains(&i.risk_type) {
-                            include = false;
-                        }
-                    }
-                    if include {
-                        Some(i.to_owned())
-                    } else {
-                        None
-                    }
-                })
+                .filter(|i| filter.matches_issue(&package, &FilterIssue::from(*i)))
+                .cloned()
                 .collect::<Vec<IssuesListItem>>()
         } else {
             self.issues.to_owned()
@@ -392,11 +363,11 @@ impl Summarize for Package {
         println!("{}", self.render());
 
         println!(" Risk Vectors:");
-        risks_table.printstd();
+        print_table(&risks_table);
 
         if !issues_table.is_empty() {
             println!("\n Issues:");
-            issues_table.printstd();
+            print_table(&issues_table);
         }
     }
 }
@@ -411,6 +382,7 @@ impl<T> Summarize for Vec<T> where T: Renderable {}
 
 impl Summarize for String {}
 impl Summarize for PackageStatus {}
+impl Summarize for PackageList {}
 impl Summarize for ProjectDetailsResponse {}
 impl Summarize for AllJobsStatusResponse {}
 impl Summarize for CancelJobResponse {}
@@ -432,13 +404,19 @@ mod tests {
                     "domain": "license"
                     }"#;
         let issue: Issue = serde_json::from_str(issue).unwrap();
+        let package = FilterPackage {
+            package_type: &PackageType::Npm,
+            name: "xmlrpc",
+            version: "0.3.0",
+            score: None,
+        };
 
-        let include = check_filter_issue(&filter, &issue);
+        let include = filter.matches_issue(&package, &FilterIssue::from(&issue));
         assert!(include);
 
         let filter_string = "mal";
         let filter = Filter::from_str(filter_string).expect("Failed to parse filter string: {}");
-        let include = check_filter_issue(&filter, &issue);
+        let include = filter.matches_issue(&package, &FilterIssue::from(&issue));
         assert!(!include);
     }
 }
@@ -453,6 +431,12 @@ fn risk_level_to_color(level: &RiskLevel) -> Color {
     }
 }
 
+/// Get the width issue descriptions are wrapped to.
+fn description_width() -> usize {
+    // Leave room for the severity column and table padding.
+    print::terminal_width().saturating_sub(20).clamp(40, 100)
+}
+
 fn issue_to_row(issue: &Issue) -> Vec<Row> {
     let row_1 = Row::new(vec![
         Cell::new_align(&issue.severity.to_string(), format::Alignment::LEFT)
@@ -466,7 +450,7 @@ fn issue_to_row(issue: &Issue) -> Vec<Row> {
 
     let row_2 = Row::new(vec![
         Cell::new(""),
-        Cell::new(&textwrap::fill(&issue.description, 80)),
+        Cell::new(&textwrap::fill(&issue.description, description_width())),
         Cell::new(""),
     ]);
 
@@ -486,7 +470,7 @@ fn issueslistitem_to_row(issue: &IssuesListItem) -> Vec<Row> {
 
     let row_2 = Row::new(vec![
         Cell::new(""),
-        Cell::new(&textwrap::fill(&issue.description, 80)),
+        Cell::new(&textwrap::fill(&issue.description, description_width())),
         Cell::new(""),
     ]);
 
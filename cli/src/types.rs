@@ -1,5 +1,7 @@
 use std::str::FromStr;
 
+use anyhow::anyhow;
+use phylum_types::types::package::Package;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -34,6 +36,127 @@ impl FromStr for Role {
     }
 }
 
+/// Details for a list of packages, as returned by `phylum package --from-file`.
+#[derive(Debug, Serialize)]
+#[serde(transparent)]
+pub struct PackageList {
+    pub packages: Vec<Package>,
+    /// The columns of the rendered table.
+    #[serde(skip)]
+    pub columns: Vec<PackageColumn>,
+}
+
+impl PackageList {
+    pub fn new(packages: Vec<Package>) -> Self {
+        Self {
+            packages,
+            columns: PackageColumn::DEFAULT.to_vec(),
+        }
+    }
+}
+
+/// A column of the package list table.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PackageColumn {
+    Name,
+    Version,
+    Ecosystem,
+    Score,
+    Author,
+    Engineering,
+    LicenseRisk,
+    Malicious,
+    Vulnerability,
+    License,
+    Issues,
+}
+
+impl PackageColumn {
+    pub const DEFAULT: [PackageColumn; 6] = [
+        Self::Name,
+        Self::Version,
+        Self::Ecosystem,
+        Self::Score,
+        Self::Issues,
+        Self::License,
+    ];
+
+    pub const NAMES: [&'static str; 11] = [
+        "name",
+        "version",
+        "ecosystem",
+        "score",
+        "author",
+        "engineering",
+        "license-risk",
+        "malicious",
+        "vulnerability",
+        "license",
+        "issues",
+    ];
+
+    /// Parse a comma-separated list of columns.
+    pub fn parse_list(list: &str) -> anyhow::Result<Vec<Self>> {
+        list.split(',')
+            .map(|column| column.trim())
+            .filter(|column| !column.is_empty())
+            .map(|column| {
+                Self::from_str(column).map_err(|_| {
+                    anyhow!(
+                        "Unknown column `{}`, expected one of: {}",
+                        column,
+                        Self::NAMES.join(", ")
+                    )
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()
+            .and_then(|columns| {
+                if columns.is_empty() {
+                    Err(anyhow!("No columns selected"))
+                } else {
+                    Ok(columns)
+                }
+            })
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::Name => "Package Name",
+            Self::Version => "Version",
+            Self::Ecosystem => "Ecosystem",
+            Self::Score => "Score",
+            Self::Author => "Author",
+            Self::Engineering => "Engineering",
+            Self::LicenseRisk => "License Risk",
+            Self::Malicious => "Malicious",
+            Self::Vulnerability => "Vulnerability",
+            Self::License => "License",
+            Self::Issues => "Issues",
+        }
+    }
+}
+
+impl FromStr for PackageColumn {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "name" => Ok(Self::Name),
+            "version" => Ok(Self::Version),
+            "ecosystem" | "type" => Ok(Self::Ecosystem),
+            "score" => Ok(Self::Score),
+            "author" | "aut" => Ok(Self::Author),
+            "engineering" | "eng" => Ok(Self::Engineering),
+            "license-risk" | "lic" => Ok(Self::LicenseRisk),
+            "malicious" | "malicious_code" | "mal" => Ok(Self::Malicious),
+            "vulnerability" | "vln" => Ok(Self::Vulnerability),
+            "license" => Ok(Self::License),
+            "issues" => Ok(Self::Issues),
+            _ => Err(()),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct GithubRelease {
     pub name: String,
@@ -50,6 +173,8 @@ pub struct GithubReleaseAsset {
 mod tests {
     use phylum_types::types::package::RiskLevel;
 
+    use super::PackageColumn;
+
     #[test]
     fn test_risk_level_ordering() {
         assert!(
@@ -60,4 +185,19 @@ mod tests {
             "Ordering of risk levels is invalid"
         );
     }
+
+    #[test]
+    fn parses_package_columns() {
+        assert_eq!(
+            PackageColumn::parse_list("name, version,vln,license").unwrap(),
+            vec![
+                PackageColumn::Name,
+                PackageColumn::Version,
+                PackageColumn::Vulnerability,
+                PackageColumn::License
+            ]
+        );
+        assert!(PackageColumn::parse_list("name,size").is_err());
+        assert!(PackageColumn::parse_list(" ,").is_err());
+    }
 }
@@ -8,10 +8,15 @@ use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
 use assert_cmd::Command;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use lazy_static::lazy_static;
 use phylum_cli::commands::extensions::*;
+use phylum_cli::config::{save_config, Config};
 use rand::Rng;
 use regex::Regex;
+use wiremock::matchers::{body_string, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
 
 lazy_static! {
     // Lock this mutex when setting an environment variable, for the lifetime of function calls
@@ -35,6 +40,7 @@ fn extension_is_installed_correctly() {
         .env("XDG_DATA_HOME", &tmp_dir)
         .arg("extension")
         .arg("add")
+        .arg("--allow-unsigned")
         .arg(fixtures_path().join("sample-extension"))
         .assert()
         .success();
@@ -60,6 +66,7 @@ fn can_run_installed_extension() {
         .env("XDG_DATA_HOME", &tmp_dir)
         .arg("extension")
         .arg("add")
+        .arg("--allow-unsigned")
         .arg(fixtures_path().join("sample-extension"))
         .assert()
         .success();
@@ -88,6 +95,7 @@ fn successful_installation_prints_message() {
         .env("XDG_DATA_HOME", &tmp_dir)
         .arg("extension")
         .arg("add")
+        .arg("--allow-unsigned")
         .arg(fixtures_path().join("sample-extension"))
         .assert()
         .success();
@@ -116,6 +124,7 @@ fn unsuccessful_installation_prints_failure_message() {
         .env("XDG_DATA_HOME", &tmp_dir)
         .arg("extension")
         .arg("add")
+        .arg("--allow-unsigned")
         .arg(fixtures_path().join("sample-extension"))
         .assert()
         .success();
@@ -127,6 +136,7 @@ fn unsuccessful_installation_prints_failure_message() {
             .env("XDG_DATA_HOME", &tmp_dir)
             .arg("extension")
             .arg("add")
+            .arg("--allow-unsigned")
             .arg(fixtures_path().join("sample-extension"))
             .assert()
             .failure(),
@@ -140,6 +150,7 @@ fn unsuccessful_installation_prints_failure_message() {
             .env("XDG_DATA_HOME", &tmp_dir)
             .arg("extension")
             .arg("add")
+            .arg("--allow-unsigned")
             .arg(
                 PathBuf::from(&tmp_dir)
                     .join("phylum")
@@ -152,6 +163,179 @@ fn unsuccessful_installation_prints_failure_message() {
     ));
 }
 
+// Extensions can be installed from archives, and remember where they were
+// installed from.
+#[test]
+fn extension_is_installed_from_archive() {
+    let tmp_dir = TmpDir::new();
+
+    let archive_path = PathBuf::from(&tmp_dir).join("sample-extension.tar.gz");
+    let archive = fs::File::create(&archive_path).unwrap();
+    let mut builder = tar::Builder::new(GzEncoder::new(archive, Compression::default()));
+    builder
+        .append_dir_all("sample-extension", fixtures_path().join("sample-extension"))
+        .unwrap();
+    builder.into_inner().unwrap().finish().unwrap();
+
+    install_extension_from(&tmp_dir, &archive_path);
+
+    let _guard = ENV_MUTEX.lock().unwrap();
+    env::set_var("XDG_DATA_HOME", &tmp_dir);
+
+    let installed_ext = Extension::load("sample-extension").unwrap();
+    assert_eq!(
+        installed_ext.metadata().unwrap().source,
+        ExtensionSource::Archive {
+            location: archive_path
+                .canonicalize()
+                .unwrap()
+                .to_string_lossy()
+                .into()
+        }
+    );
+}
+
+// Extensions can be installed from git repositories, at a specific ref.
+#[test]
+fn extension_is_installed_from_git() {
+    let tmp_dir = TmpDir::new();
+
+    let repo_path = PathBuf::from(&tmp_dir).join("repo");
+    copy_dir(&fixtures_path().join("sample-extension"), &repo_path);
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .args(&["-c", "user.name=Phylum", "-c", "user.email=test@phylum.io"])
+            .arg("-C")
+            .arg(&repo_path)
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    git(&["init", "--quiet"]);
+    git(&["add", "."]);
+    git(&["commit", "--quiet", "-m", "Initial commit"]);
+    git(&["tag", "v1.0.0"]);
+
+    let source = format!("git+file://{}#v1.0.0", repo_path.display());
+    install_extension_from(&tmp_dir, &source);
+
+    let extension_path = PathBuf::from(&tmp_dir)
+        .join("phylum")
+        .join("extensions")
+        .join("sample-extension");
+    assert!(extension_path.join("PhylumExt.toml").exists());
+    assert!(!extension_path.join(".git").exists());
+
+    let _guard = ENV_MUTEX.lock().unwrap();
+    env::set_var("XDG_DATA_HOME", &tmp_dir);
+
+    let installed_ext = Extension::load("sample-extension").unwrap();
+    assert_eq!(
+        installed_ext.metadata().unwrap().source,
+        ExtensionSource::Git {
+            url: format!("file://{}", repo_path.display()),
+            reference: Some("v1.0.0".into()),
+        }
+    );
+}
+
+// Installed extensions can be upgraded from their source, and rolled back to
+// the version they replaced.
+#[test]
+fn extension_is_upgraded_and_rolled_back() {
+    let tmp_dir = TmpDir::new();
+
+    let source_path = PathBuf::from(&tmp_dir).join("source");
+    copy_dir(&fixtures_path().join("sample-extension"), &source_path);
+    install_extension_from(&tmp_dir, &source_path);
+
+    let manifest_path = source_path.join("PhylumExt.toml");
+    let manifest = fs::read_to_string(&manifest_path).unwrap();
+    fs::write(&manifest_path, manifest.replace("1.0.0", "2.0.0")).unwrap();
+
+    let extension = |args: &[&str]| {
+        Command::cargo_bin("phylum")
+            .unwrap()
+            .env("XDG_DATA_HOME", &tmp_dir)
+            .arg("extension")
+            .args(args)
+            .assert()
+            .success()
+    };
+    let list_output = || {
+        let cmd = extension(&["list"]);
+        String::from_utf8(cmd.get_output().stdout.clone()).unwrap()
+    };
+
+    let cmd = extension(&["upgrade", "--allow-unsigned", "sample-extension"]);
+    let output = std::str::from_utf8(&cmd.get_output().stdout).unwrap();
+    assert!(output.contains("Upgraded sample-extension from version 1.0.0 to 2.0.0"));
+    assert!(list_output().contains("2.0.0"));
+
+    extension(&["rollback", "sample-extension"]);
+    assert!(list_output().contains("1.0.0"));
+
+    // Previous versions are neither listed nor left behind after removal.
+    assert_eq!(list_output().lines().count(), 1);
+    extension(&["remove", "sample-extension"]);
+    let extensions_path = PathBuf::from(&tmp_dir).join("phylum").join("extensions");
+    assert!(!extensions_path
+        .join(".previous")
+        .join("sample-extension")
+        .exists());
+}
+
+// Extensions must be signed by a trusted key, and unmodified since they were
+// signed, unless unsigned extensions are explicitly allowed.
+#[test]
+fn extension_signatures_are_verified() {
+    let tmp_dir = TmpDir::new();
+
+    let add = |config: &Path, source: &Path| {
+        Command::cargo_bin("phylum")
+            .unwrap()
+            .env("XDG_DATA_HOME", &tmp_dir)
+            .arg("--config")
+            .arg(config)
+            .args(&["extension", "add"])
+            .arg(source)
+            .assert()
+    };
+    let stderr = |assert: assert_cmd::assert::Assert| {
+        String::from_utf8(assert.get_output().stderr.clone()).unwrap()
+    };
+
+    let untrusting_config = PathBuf::from(&tmp_dir).join("untrusting.yaml");
+    save_config(&untrusting_config, &Config::default()).unwrap();
+    let trusting_config = PathBuf::from(&tmp_dir).join("trusting.yaml");
+    let config = Config {
+        extension_keys: Some(vec![SIGNED_EXTENSION_KEY.into()]),
+        ..Config::default()
+    };
+    save_config(&trusting_config, &config).unwrap();
+
+    // Unsigned extensions are refused.
+    let output = stderr(add(&trusting_config, &fixtures_path().join("sample-extension")).failure());
+    assert!(output.contains("extension is not signed"));
+
+    // Signatures from keys which aren't trusted are refused.
+    let signed_path = PathBuf::from(&tmp_dir).join("signed-extension");
+    copy_dir(&fixtures_path().join("signed-extension"), &signed_path);
+    let output = stderr(add(&untrusting_config, &signed_path).failure());
+    assert!(output.contains("not signed by a trusted key"));
+
+    // Tampered extensions are refused.
+    let entry_point = signed_path.join("main.js");
+    let original = fs::read_to_string(&entry_point).unwrap();
+    fs::write(&entry_point, "Deno.core.print(\"tampered\\n\");\n").unwrap();
+    let output = stderr(add(&trusting_config, &signed_path).failure());
+    assert!(output.contains("main.js: file was modified after signing"));
+    fs::write(&entry_point, original).unwrap();
+
+    add(&trusting_config, &signed_path).success();
+}
+
 // When a user runs `phylum extension remove <extensionName>` the extension
 // should be entirely removed from the user system.
 #[test]
@@ -163,6 +347,7 @@ fn extension_is_uninstalled_correctly() {
         .env("XDG_DATA_HOME", &tmp_dir)
         .arg("extension")
         .arg("add")
+        .arg("--allow-unsigned")
         .arg(fixtures_path().join("sample-extension"))
         .assert()
         .success();
@@ -212,6 +397,7 @@ fn extension_list_should_emit_output() {
         .env("XDG_DATA_HOME", &tmp_dir)
         .arg("extension")
         .arg("add")
+        .arg("--allow-unsigned")
         .arg(fixtures_path().join("sample-extension"))
         .assert();
 
@@ -224,11 +410,223 @@ fn extension_list_should_emit_output() {
         .assert();
 
     let output = std::str::from_utf8(&cmd.get_output().stdout).unwrap();
-    let re = Regex::new(r#"^sample-extension\s+This extension does a thing"#).unwrap();
+    let re = Regex::new(r#"^sample-extension\s+1\.0\.0\s+This extension does a thing"#).unwrap();
 
     assert!(output.lines().any(|m| re.is_match(m)));
 }
 
+// Arguments following the extension name should be forwarded to the extension.
+#[test]
+fn extension_receives_arguments() {
+    let tmp_dir = TmpDir::new();
+    install_extension(&tmp_dir, "args-extension");
+
+    let cmd = Command::cargo_bin("phylum")
+        .unwrap()
+        .env("XDG_DATA_HOME", &tmp_dir)
+        .args(&["args-extension", "first", "--flag", "-x", "value"])
+        .assert()
+        .success();
+
+    let output = std::str::from_utf8(&cmd.get_output().stdout).unwrap();
+    assert_eq!(output.trim(), r#"["first","--flag","-x","value"]"#);
+}
+
+// Global arguments of the CLI should only apply before the extension name.
+#[test]
+fn extension_receives_global_arguments() {
+    let tmp_dir = TmpDir::new();
+    install_extension(&tmp_dir, "args-extension");
+
+    let cmd = Command::cargo_bin("phylum")
+        .unwrap()
+        .env("XDG_DATA_HOME", &tmp_dir)
+        .args(&[
+            "--no-color",
+            "args-extension",
+            "--json",
+            "--format",
+            "x",
+            "-j",
+        ])
+        .assert()
+        .success();
+
+    let output = std::str::from_utf8(&cmd.get_output().stdout).unwrap();
+    assert_eq!(output.trim(), r#"["--json","--format","x","-j"]"#);
+}
+
+// Extensions declaring their arguments should get their parsed values, and
+// document them in `--help`.
+#[test]
+fn extension_argument_schema_is_applied() {
+    let tmp_dir = TmpDir::new();
+    install_extension(&tmp_dir, "schema-extension");
+
+    let cmd = Command::cargo_bin("phylum")
+        .unwrap()
+        .env("XDG_DATA_HOME", &tmp_dir)
+        .args(&["schema-extension", "-l", "main", "yarn.lock"])
+        .assert()
+        .success();
+
+    let output = std::str::from_utf8(&cmd.get_output().stdout).unwrap();
+    assert_eq!(
+        output.trim(),
+        r#"{"args":["yarn.lock"],"options":{"label":"main","lockfile":"yarn.lock","verbose":false}}"#
+    );
+
+    let cmd = Command::cargo_bin("phylum")
+        .unwrap()
+        .env("XDG_DATA_HOME", &tmp_dir)
+        .args(&["schema-extension", "--help"])
+        .assert()
+        .success();
+
+    let output = std::str::from_utf8(&cmd.get_output().stdout).unwrap();
+    assert!(output.contains("The label of the job"));
+
+    // Missing required arguments are rejected.
+    Command::cargo_bin("phylum")
+        .unwrap()
+        .env("XDG_DATA_HOME", &tmp_dir)
+        .arg("schema-extension")
+        .assert()
+        .failure();
+}
+
+// Extensions requesting permissions should only be installed once the user
+// accepts them, and should not access anything else.
+#[test]
+fn extension_permissions_are_enforced() {
+    let tmp_dir = TmpDir::new();
+
+    let cmd = Command::cargo_bin("phylum")
+        .unwrap()
+        .env("XDG_DATA_HOME", &tmp_dir)
+        .arg("extension")
+        .arg("add")
+        .arg("--allow-unsigned")
+        .arg(fixtures_path().join("permissions-extension"))
+        .assert()
+        .failure();
+
+    let output = std::str::from_utf8(&cmd.get_output().stdout).unwrap();
+    assert!(output.contains("Read environment variables: PHYLUM_TEST_VALUE"));
+
+    Command::cargo_bin("phylum")
+        .unwrap()
+        .env("XDG_DATA_HOME", &tmp_dir)
+        .args(&["extension", "add", "--yes", "--allow-unsigned"])
+        .arg(fixtures_path().join("permissions-extension"))
+        .assert()
+        .success();
+
+    let cmd = Command::cargo_bin("phylum")
+        .unwrap()
+        .env("XDG_DATA_HOME", &tmp_dir)
+        .env("PHYLUM_TEST_VALUE", "visible")
+        .env("PHYLUM_TEST_HIDDEN", "hidden")
+        .arg("permissions-extension")
+        .assert()
+        .success();
+
+    let output = std::str::from_utf8(&cmd.get_output().stdout).unwrap();
+    let lines = output.lines().collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        vec![
+            r#"{"PHYLUM_TEST_VALUE":"visible"}"#,
+            "Requires read access to `PhylumExt.toml`",
+            "Requires access to the Phylum API",
+        ]
+    );
+}
+
+// Extensions should access files, hosts, commands and environment variables
+// through the `phylum:std` module, within their permissions.
+#[tokio::test]
+async fn extension_can_use_stdlib() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/hello"))
+        .and(body_string("ping"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("pong"))
+        .mount(&mock_server)
+        .await;
+
+    let tmp_dir = TmpDir::new();
+    Command::cargo_bin("phylum")
+        .unwrap()
+        .env("XDG_DATA_HOME", &tmp_dir)
+        .args(&["extension", "add", "--yes", "--allow-unsigned"])
+        .arg(fixtures_path().join("stdlib-extension"))
+        .assert()
+        .success();
+
+    let work_dir = PathBuf::from(&tmp_dir).join("work");
+    fs::create_dir_all(&work_dir).unwrap();
+
+    let cmd = Command::cargo_bin("phylum")
+        .unwrap()
+        .current_dir(&work_dir)
+        .env("XDG_DATA_HOME", &tmp_dir)
+        .env("STD_TEST_VALUE", "visible")
+        .arg("stdlib-extension")
+        .arg(mock_server.uri())
+        .assert()
+        .success();
+
+    let output = std::str::from_utf8(&cmd.get_output().stdout).unwrap();
+    assert_eq!(
+        output.lines().collect::<Vec<_>>(),
+        vec![
+            "written",
+            "200 pong",
+            "hello",
+            "visible",
+            "Requires read access to `../outside.txt`",
+            "Requires network access to `example.com`",
+            "Requires permission to run `ls`",
+        ]
+    );
+    assert_eq!(
+        fs::read_to_string(work_dir.join("output.txt")).unwrap(),
+        "written"
+    );
+}
+
+// Extensions should be able to fail the command, reporting where they failed
+// in their TypeScript sources.
+#[test]
+fn extension_failures_set_exit_code() {
+    let tmp_dir = TmpDir::new();
+    install_extension(&tmp_dir, "exit-extension");
+
+    let run = |args: &[&str]| {
+        Command::cargo_bin("phylum")
+            .unwrap()
+            .env("XDG_DATA_HOME", &tmp_dir)
+            .arg("exit-extension")
+            .args(args)
+            .assert()
+    };
+
+    run(&[]).success();
+
+    let cmd = run(&["throw"]).code(1);
+    let output = std::str::from_utf8(&cmd.get_output().stderr).unwrap();
+    assert!(output.contains("Error: thrown"));
+    assert!(output.contains("main.ts:4:9"));
+
+    let cmd = run(&["reject"]).code(1);
+    let output = std::str::from_utf8(&cmd.get_output().stderr).unwrap();
+    assert!(output.contains("Error: rejected"));
+
+    run(&["exit", "0"]).success();
+    run(&["exit", "42"]).code(42);
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // Miscellaneous tests
 ////////////////////////////////////////////////////////////////////////////////
@@ -240,6 +638,35 @@ fn valid_extension_is_loaded_correctly() {
     assert_eq!(ext.name(), "sample-extension");
 }
 
+#[test]
+fn extension_with_reserved_arguments_is_rejected() {
+    let tmp_dir = TmpDir::new();
+    let manifest = |arg: &str| {
+        format!(
+            "name = \"reserved\"\nentry_point = \"main.js\"\n\n[[args]]\n{}\n",
+            arg
+        )
+    };
+    fs::write(tmp_dir.0.join("main.js"), "").unwrap();
+
+    for arg in [
+        "name = \"json\"",
+        "name = \"output\"\nlong = \"format\"",
+        "name = \"json-output\"\nshort = \"j\"",
+        "name = \"usage\"\nshort = \"h\"",
+    ] {
+        fs::write(tmp_dir.0.join("PhylumExt.toml"), manifest(arg)).unwrap();
+        assert!(Extension::try_from(tmp_dir.0.clone()).is_err(), "{}", arg);
+    }
+
+    fs::write(
+        tmp_dir.0.join("PhylumExt.toml"),
+        manifest("name = \"output\"\nlong = \"output\""),
+    )
+    .unwrap();
+    assert!(Extension::try_from(tmp_dir.0.clone()).is_ok());
+}
+
 #[test]
 fn conflicting_extension_name_is_filtered() {
     let tmp_dir = TmpDir::new();
@@ -249,6 +676,7 @@ fn conflicting_extension_name_is_filtered() {
         .env("XDG_DATA_HOME", &tmp_dir)
         .arg("extension")
         .arg("add")
+        .arg("--allow-unsigned")
         .arg(fixtures_path().join("ping-extension"))
         .assert()
         .success();
@@ -269,6 +697,37 @@ fn conflicting_extension_name_is_filtered() {
 // Utilities
 ////////////////////////////////////////////////////////////////////////////////
 
+/// Public key which signed the `signed-extension` fixture.
+const SIGNED_EXTENSION_KEY: &str = "RWRVHq9vhxp1IHHWeCK2aNex06QWLWMyW0WPWGUr5yvM5d2pSkmkHJ7c";
+
+fn install_extension(tmp_dir: &TmpDir, name: &str) {
+    install_extension_from(tmp_dir, fixtures_path().join(name));
+}
+
+fn install_extension_from(tmp_dir: &TmpDir, source: impl AsRef<OsStr>) {
+    Command::cargo_bin("phylum")
+        .unwrap()
+        .env("XDG_DATA_HOME", tmp_dir)
+        .arg("extension")
+        .arg("add")
+        .arg("--allow-unsigned")
+        .arg(source)
+        .assert()
+        .success();
+}
+
+fn copy_dir(source: &Path, dest: &Path) {
+    for entry in walkdir::WalkDir::new(source) {
+        let source_path = entry.unwrap().into_path();
+        let dest_path = dest.join(source_path.strip_prefix(source).unwrap());
+        if source_path.is_dir() {
+            fs::create_dir_all(dest_path).unwrap();
+        } else {
+            fs::copy(source_path, dest_path).unwrap();
+        }
+    }
+}
+
 fn project_root() -> PathBuf {
     Path::new(&env!("CARGO_MANIFEST_DIR"))
         .ancestors()
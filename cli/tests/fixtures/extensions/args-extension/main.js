@@ -0,0 +1 @@
+Deno.core.print(JSON.stringify(Phylum.args) + "\n");
@@ -0,0 +1,14 @@
+const [mode, code]: string[] = Phylum.args;
+
+function fail(message: string): never {
+  throw new Error(message);
+}
+
+if (mode === "throw") {
+  fail("thrown");
+} else if (mode === "reject") {
+  Promise.reject(new Error("rejected"));
+} else if (mode === "exit") {
+  Phylum.exit(Number(code));
+  throw new Error("unreachable");
+}
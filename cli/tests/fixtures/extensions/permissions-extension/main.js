@@ -0,0 +1,15 @@
+import { getUserInfo, parseLockfile } from "phylum:api";
+
+Deno.core.print(JSON.stringify(Phylum.env) + "\n");
+
+try {
+  parseLockfile("PhylumExt.toml");
+} catch (e) {
+  Deno.core.print(e.message + "\n");
+}
+
+try {
+  await getUserInfo();
+} catch (e) {
+  Deno.core.print(e.message + "\n");
+}
@@ -0,0 +1,2 @@
+const options: Record<string, unknown> = Phylum.options;
+Deno.core.print(JSON.stringify({ args: Phylum.args, options }) + "\n");
@@ -0,0 +1 @@
+Deno.core.print("signed\n");
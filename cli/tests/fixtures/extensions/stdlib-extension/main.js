@@ -0,0 +1,31 @@
+import { fetch, getEnv, readFile, run, writeFile } from "phylum:std";
+
+const print = (value) => Deno.core.print(value + "\n");
+const [url] = Phylum.args;
+
+await writeFile("output.txt", "written");
+print(await readFile("output.txt"));
+
+const response = await fetch(url + "/hello", { method: "POST", body: "ping" });
+print(`${response.status} ${await response.text()}`);
+
+print((await run("echo", ["hello"])).stdout.trim());
+print(getEnv("STD_TEST_VALUE"));
+
+try {
+  await readFile("../outside.txt");
+} catch (e) {
+  print(e.message);
+}
+
+try {
+  await fetch("https://example.com/");
+} catch (e) {
+  print(e.message);
+}
+
+try {
+  await run("ls");
+} catch (e) {
+  print(e.message);
+}